@@ -0,0 +1,187 @@
+use image::{imageops, DynamicImage, ImageBuffer, Pixel};
+use msdf::{
+    ColoringStrategy, DdsField, DdsFormat, DistanceCheckMode, ErrorCorrectionMode, FieldFile,
+    GrayFImage, Ktx2Config, Ktx2Field, Ktx2Format, SDFTrait,
+};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::str::FromStr;
+use std::vec;
+
+pub type CliResult<T> = Result<T, Box<dyn Error>>;
+
+/// Command-line arguments, consumed front to back.
+pub struct Args(vec::IntoIter<String>);
+
+impl Args {
+    pub fn new(args: Vec<String>) -> Self {
+        Args(args.into_iter())
+    }
+
+    pub fn next(&mut self) -> Option<String> {
+        self.0.next()
+    }
+
+    /// The value following `option`.
+    pub fn value(&mut self, option: &str) -> CliResult<String> {
+        self.next()
+            .ok_or_else(|| format!("missing value for {}", option).into())
+    }
+
+    /// The value following `option`, parsed.
+    pub fn parse<T: FromStr>(&mut self, option: &str) -> CliResult<T> {
+        let value = self.value(option)?;
+        value
+            .parse()
+            .map_err(|_| format!("invalid value `{}` for {}", value, option).into())
+    }
+}
+
+/// Parses an angle in radians, or in degrees when suffixed with `d`.
+pub fn parse_angle(value: &str) -> CliResult<f64> {
+    let invalid = || format!("invalid angle `{}`", value);
+    match value.strip_suffix('d') {
+        Some(degrees) => Ok(degrees.parse::<f64>().map_err(|_| invalid())?.to_radians()),
+        None => Ok(value.parse().map_err(|_| invalid())?),
+    }
+}
+
+pub fn parse_coloring_strategy(value: &str) -> CliResult<ColoringStrategy> {
+    match value {
+        "simple" => Ok(ColoringStrategy::Simple),
+        "inktrap" => Ok(ColoringStrategy::InkTrap),
+        "distance" => Ok(ColoringStrategy::ByDistance),
+        _ => Err(format!("unknown coloring strategy `{}`", value).into()),
+    }
+}
+
+/// Parses msdfgen's error correction names, such as `auto-fast` or `edge-full`.
+pub fn parse_error_correction(value: &str) -> CliResult<(ErrorCorrectionMode, DistanceCheckMode)> {
+    let (mode, check) = match value {
+        "disabled" | "off" | "none" => {
+            return Ok((ErrorCorrectionMode::Disabled, Default::default()))
+        }
+        _ => value.split_once('-').unwrap_or((value, "mixed")),
+    };
+
+    let mode = match mode {
+        "auto" | "default" => ErrorCorrectionMode::EdgePriority,
+        "distance" | "indiscriminate" => ErrorCorrectionMode::Indiscriminate,
+        "edge" => ErrorCorrectionMode::EdgeOnly,
+        _ => return Err(format!("unknown error correction mode `{}`", value).into()),
+    };
+    let check = match check {
+        "fast" => DistanceCheckMode::DoNotCheckDistance,
+        "mixed" => DistanceCheckMode::CheckDistanceAtEdge,
+        "full" => DistanceCheckMode::AlwaysCheckDistance,
+        _ => return Err(format!("unknown error correction mode `{}`", value).into()),
+    };
+    Ok((mode, check))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// File format of a generated field.
+pub enum OutputFormat {
+    Png,
+    Tiff,
+    BinFloat,
+    Ktx2,
+    Dds,
+    #[cfg(feature = "exr")]
+    Exr,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> CliResult<Self> {
+        match name {
+            "png" => Ok(OutputFormat::Png),
+            "tiff" | "tif" => Ok(OutputFormat::Tiff),
+            "binfloat" | "bin" | "raw" => Ok(OutputFormat::BinFloat),
+            "ktx2" => Ok(OutputFormat::Ktx2),
+            "dds" => Ok(OutputFormat::Dds),
+            #[cfg(feature = "exr")]
+            "exr" => Ok(OutputFormat::Exr),
+            _ => Err(format!("unknown output format `{}`", name).into()),
+        }
+    }
+
+    /// Guesses the format from the extension of `path`, falling back to PNG.
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| OutputFormat::from_name(&extension.to_lowercase()).ok())
+            .unwrap_or(OutputFormat::Png)
+    }
+}
+
+/// Saves a field in the given format. 8-bit formats quantize the field, the others keep the
+/// exact distances.
+pub fn save_field<T, P>(field: &T, path: &Path, format: OutputFormat) -> CliResult<()>
+where
+    T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>> + FieldFile + Ktx2Field + DdsField,
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    let single_channel = P::CHANNEL_COUNT == 1;
+    match format {
+        OutputFormat::Png => field.save_png(path)?,
+        OutputFormat::Tiff => field.save_tiff(path)?,
+        OutputFormat::BinFloat => field.save_raw(path)?,
+        OutputFormat::Ktx2 => {
+            let format = if single_channel {
+                Ktx2Format::R8
+            } else {
+                Ktx2Format::RGBA8
+            };
+            let config = Ktx2Config {
+                format,
+                ..Default::default()
+            };
+            field.write_ktx2(BufWriter::new(File::create(path)?), &config)?;
+        }
+        OutputFormat::Dds => {
+            let format = if single_channel {
+                DdsFormat::R8
+            } else {
+                DdsFormat::RGBA8
+            };
+            field.write_dds(BufWriter::new(File::create(path)?), format)?;
+        }
+        #[cfg(feature = "exr")]
+        OutputFormat::Exr => field.save_exr(path)?,
+    }
+    Ok(())
+}
+
+/// Returns a copy of the field flipped upside down. The range is kept, in pixels.
+pub fn flip_field<T, P>(field: &T) -> T
+where
+    T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    let image = imageops::flip_vertical(field.image());
+    T::from_image(image, field.pixel_range(), field.mid_value())
+}
+
+/// Renders the field to an 8-bit image, in color if `colored` is set.
+pub fn save_render<T>(
+    field: &T,
+    path: &Path,
+    width: u32,
+    height: u32,
+    colored: bool,
+) -> CliResult<()>
+where
+    T: SDFTrait,
+    DynamicImage: From<T::ColoredOutput>,
+{
+    let image = if colored {
+        DynamicImage::from(field.render_colored(width, height)).into_rgb8()
+    } else {
+        let render: GrayFImage = field.render(width, height);
+        <DynamicImage as From<GrayFImage>>::from(render).into_rgb8()
+    };
+    image.save(path)?;
+    Ok(())
+}
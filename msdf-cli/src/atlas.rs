@@ -0,0 +1,795 @@
+use crate::args::{
+    parse_angle, parse_coloring_strategy, parse_error_correction, save_field, Args, CliResult,
+    OutputFormat,
+};
+use image::Rgba;
+use msdf::{
+    parse_glyph_set, ArteryEncoding, Atlas, AtlasConfig, AtlasField, AtlasGlyph, AtlasRect,
+    AtlasType, Bounds, Charset, ColorGlyphLoader, FontVariations, Generator, LayerColor,
+    ResolvedCharset,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use ttf_parser::{name_id, Face, GlyphId, Tag, Variation};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum YOrigin {
+    Bottom,
+    Top,
+}
+
+struct Options {
+    fonts: Vec<PathBuf>,
+    chars: Charset,
+    glyphs: Vec<GlyphId>,
+    all_glyphs: bool,
+    variations: Vec<Variation>,
+    instances: Vec<String>,
+    all_instances: bool,
+    palette: Option<u16>,
+    config: AtlasConfig,
+    image_out: Option<PathBuf>,
+    format: Option<OutputFormat>,
+    json: Option<PathBuf>,
+    fnt: Option<PathBuf>,
+    arfont: Option<PathBuf>,
+    y_origin: YOrigin,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            fonts: vec![],
+            chars: Charset::new(),
+            glyphs: vec![],
+            all_glyphs: false,
+            variations: vec![],
+            instances: vec![],
+            all_instances: false,
+            palette: None,
+            config: Default::default(),
+            image_out: None,
+            format: None,
+            json: None,
+            fnt: None,
+            arfont: None,
+            y_origin: YOrigin::Bottom,
+        }
+    }
+}
+
+pub const USAGE: &str = "\
+Usage: msdf atlas -font <file> [options]
+
+Input:
+  -font <file>              TrueType or OpenType font. Repeat for several fonts, each getting its
+                            own atlas with the name of the font added to the output files.
+  -charset <file>           Characters to include, in msdf-atlas-gen's charset syntax.
+  -chars <charset>          Characters given inline, for example \"[0x20, 0x7e], 'é'\".
+  -textfile <file>          Includes every character used in a text file.
+  -glyphset <file>          Glyph indices to include, in charset syntax.
+  -glyphs <glyphset>        Glyph indices given inline.
+  -allglyphs                Includes every glyph of the font.
+  -variation <axis> <value> Sets an axis of a variable font, such as wght 700.
+  -instance <name>          Bakes a named instance of a variable font. Repeat for several
+                            instances, each getting its own atlas with the name of the instance
+                            added to the output files. Variations set with -variation still apply.
+  -allinstances             Bakes every named instance of a variable font.
+  -preset <file>            Loads options from a TOML preset. Options after it override it.
+
+Without any characters or glyphs, printable ASCII is included.
+
+Atlas:
+  -type <type>              sdf, psdf, msdf (default) or mtsdf.
+  -size <em size>           Size of an em in pixels, 32 by default.
+  -pxrange <range>          Distance range in pixels, 2 by default.
+  -pxpadding <padding>      Empty pixels between glyphs and around the atlas, 0 by default.
+  -maxwidth <width>         Maximum width of the atlas in pixels, 512 by default.
+  -pot                      Rounds the width and height of the atlas up to powers of two.
+  -palette <index>          Bakes color glyphs as their COLR layers, colored with a CPAL palette.
+                            Layers are only listed in the JSON.
+  -coloringstrategy <name>  simple (default), inktrap or distance.
+  -angle <angle>            Corner angle for edge coloring, in radians or degrees with a d suffix.
+  -seed <seed>              Seed for edge coloring.
+  -errorcorrection <mode>   Error correction mode, as for single glyphs.
+  -nooverlap                Disables support for overlapping contours.
+  -legacy                   Uses msdfgen's legacy generators.
+
+Output:
+  -imageout <file>          Atlas image.
+  -format <format>          png, tiff, binfloat, ktx2, dds or exr. Guessed from the image file.
+  -json <file>              Layout and metrics as JSON, in msdf-atlas-gen's format.
+  -fnt <file>               Layout as a BMFont text descriptor, referencing the image file.
+  -arfont <file>            Image and layout as an Artery Font file.
+  -yorigin <origin>         bottom (default) or top, the origin of atlas bounds in the JSON.
+
+Presets are TOML files whose keys are option names without the dash, such as
+  fonts = [\"Roboto-Medium.ttf\"]
+  charset = \"latin.txt\"
+  type = \"mtsdf\"
+  size = 48
+  pot = true
+  variations = { wght = 600, wdth = 90 }
+  instances = [\"Regular\", \"Bold\"]
+Paths in a preset are relative to the preset.
+";
+
+/// Generates an atlas for every font, like msdf-atlas-gen.
+pub fn run(mut args: Args) -> CliResult<()> {
+    let mut options = parse(&mut args)?;
+    if options.fonts.is_empty() {
+        return Err("no font specified, see -help".into());
+    }
+    if options.image_out.is_none()
+        && options.json.is_none()
+        && options.fnt.is_none()
+        && options.arfont.is_none()
+    {
+        return Err("no output specified, see -help".into());
+    }
+    if options.fnt.is_some() && options.image_out.is_none() {
+        return Err("-fnt requires -imageout".into());
+    }
+    if options.chars.is_empty() && options.glyphs.is_empty() && !options.all_glyphs {
+        options.chars = Charset::ascii();
+    }
+
+    let multiple = options.fonts.len() > 1;
+    for font in &options.fonts {
+        let data = fs::read(font)?;
+        let face = Face::from_slice(&data, 0).map_err(|e| format!("{}: {}", font.display(), e))?;
+
+        let instances =
+            instances(&face, &options).map_err(|e| format!("{}: {}", font.display(), e))?;
+        let multiple_instances = instances.len() > 1;
+        for (name, face) in instances {
+            let mut suffixes = vec![];
+            if multiple {
+                suffixes.push(stem(font));
+            }
+            if let Some(name) = name.filter(|_| multiple_instances) {
+                suffixes.push(name);
+            }
+            generate(&face, font, &suffixes, &options)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Faces to generate atlases for: the named instances that were asked for, or the face itself
+/// with the variations set on the command line. Instances are returned with their names.
+fn instances<'a>(face: &Face<'a>, options: &Options) -> CliResult<Vec<(Option<String>, Face<'a>)>> {
+    let named = face.named_instances();
+    let selected = if options.all_instances {
+        if named.is_empty() {
+            return Err("the font has no named instances".into());
+        }
+        named
+    } else {
+        options
+            .instances
+            .iter()
+            .map(|name| {
+                named
+                    .iter()
+                    .find(|instance| instance.name.as_deref() == Some(name.as_str()))
+                    .cloned()
+                    .ok_or_else(|| format!("the font has no instance named `{}`", name))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    if selected.is_empty() {
+        return Ok(vec![(None, face.with_variations(&options.variations)?)]);
+    }
+
+    let mut faces = vec![];
+    for (index, instance) in selected.into_iter().enumerate() {
+        let mut variations = instance.variations;
+        for &variation in &options.variations {
+            set_variation(&mut variations, variation);
+        }
+        // names become part of file names, so leave out spaces
+        let name = instance
+            .name
+            .map(|name| name.split_whitespace().collect::<String>())
+            .unwrap_or_else(|| format!("instance{}", index));
+        faces.push((Some(name), face.with_variations(&variations)?));
+    }
+    Ok(faces)
+}
+
+/// Generates the atlas of one face and writes every requested output, with `suffixes` added to
+/// the output file names.
+fn generate(face: &Face, font: &Path, suffixes: &[String], options: &Options) -> CliResult<()> {
+    let resolved = options.chars.resolve(face);
+    if !resolved.missing.is_empty() {
+        let missing: Vec<String> = resolved
+            .missing
+            .iter()
+            .map(|&c| format!("U+{:04X}", c as u32))
+            .collect();
+        eprintln!(
+            "warning: {} characters are missing from {}: {}",
+            missing.len(),
+            font.display(),
+            missing.join(" ")
+        );
+    }
+
+    let glyphs = glyphs(face, &resolved, options);
+    let atlas = match options.palette {
+        Some(palette) => {
+            if palette >= face.palette_count() {
+                eprintln!("warning: {} has no palette {}", font.display(), palette);
+            }
+            Atlas::from_color_glyphs(face, glyphs, palette, &options.config)
+        }
+        None => Atlas::from_glyphs(face, glyphs, &options.config),
+    };
+    let output = |path: &Option<PathBuf>| path.as_ref().map(|path| output_path(path, suffixes));
+
+    let image_out = output(&options.image_out);
+    if let Some(path) = &image_out {
+        let format = options
+            .format
+            .unwrap_or_else(|| OutputFormat::from_path(path));
+        match &atlas.field {
+            AtlasField::SDF(field) | AtlasField::PsuedoSDF(field) => {
+                save_field(field, path, format)?
+            }
+            AtlasField::MSDF(field) => save_field(field, path, format)?,
+            AtlasField::MTSDF(field) => save_field(field, path, format)?,
+        }
+    }
+    if let Some(path) = output(&options.json) {
+        let json = layout_json(&atlas, options.y_origin);
+        fs::write(path, serde_json::to_string_pretty(&json)?)?;
+    }
+    if let (Some(path), Some(image_out)) = (output(&options.fnt), &image_out) {
+        let name = family_name(face).unwrap_or_else(|| stem(font));
+        let fnt = bmfont(&atlas, &name, image_out, options.config.padding);
+        fs::write(path, fnt)?;
+    }
+    if let Some(path) = output(&options.arfont) {
+        atlas.write_arfont(BufWriter::new(File::create(path)?), ArteryEncoding::Png)?;
+    }
+    Ok(())
+}
+
+fn parse(args: &mut Args) -> CliResult<Options> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        let option = arg.as_str();
+        match option {
+            "-font" => options.fonts.push(args.value(option)?.into()),
+            "-charset" => add_charset(&mut options, &fs::read_to_string(args.value(option)?)?)?,
+            "-chars" => add_charset(&mut options, &args.value(option)?)?,
+            "-textfile" => {
+                let text = fs::read_to_string(args.value(option)?)?;
+                options.chars.extend(&Charset::from_text(&text));
+            }
+            "-glyphset" => add_glyphset(&mut options, &fs::read_to_string(args.value(option)?)?)?,
+            "-glyphs" => add_glyphset(&mut options, &args.value(option)?)?,
+            "-allglyphs" => options.all_glyphs = true,
+            "-variation" | "-var" => {
+                let axis = parse_tag(&args.value(option)?)?;
+                let value = args.parse(option)?;
+                set_variation(&mut options.variations, Variation { axis, value });
+            }
+            "-instance" => options.instances.push(args.value(option)?),
+            "-allinstances" => options.all_instances = true,
+            "-preset" => Preset::load(args.value(option)?.as_ref())?.apply(&mut options)?,
+            "-type" => options.config.atlas_type = parse_type(&args.value(option)?)?,
+            "-size" => options.config.em_size = args.parse(option)?,
+            "-pxrange" => options.config.pixel_range = args.parse(option)?,
+            "-pxpadding" => options.config.padding = args.parse(option)?,
+            "-maxwidth" => options.config.max_width = args.parse(option)?,
+            "-pot" => options.config.power_of_two = true,
+            "-palette" => options.palette = Some(args.parse(option)?),
+            "-coloringstrategy" | "-edgecoloring" => {
+                options.config.coloring_strategy = parse_coloring_strategy(&args.value(option)?)?
+            }
+            "-angle" => options.config.coloring_config.angle = parse_angle(&args.value(option)?)?,
+            "-seed" => options.config.coloring_config.seed = args.parse(option)?,
+            "-errorcorrection" => set_error_correction(&mut options, &args.value(option)?)?,
+            "-nooverlap" => options.config.msdf_config.overlap_support = false,
+            "-overlap" => options.config.msdf_config.overlap_support = true,
+            "-legacy" => options.config.msdf_config.generator = Generator::Legacy,
+            "-imageout" => options.image_out = Some(args.value(option)?.into()),
+            "-format" => options.format = Some(OutputFormat::from_name(&args.value(option)?)?),
+            "-json" => options.json = Some(args.value(option)?.into()),
+            "-fnt" => options.fnt = Some(args.value(option)?.into()),
+            "-arfont" => options.arfont = Some(args.value(option)?.into()),
+            "-yorigin" => options.y_origin = parse_y_origin(&args.value(option)?)?,
+            _ => return Err(format!("unknown option `{}`, see -help", option).into()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_type(name: &str) -> CliResult<AtlasType> {
+    match name {
+        "sdf" => Ok(AtlasType::SDF),
+        "psdf" => Ok(AtlasType::PsuedoSDF),
+        "msdf" => Ok(AtlasType::MSDF),
+        "mtsdf" => Ok(AtlasType::MTSDF),
+        _ => Err(format!("unknown atlas type `{}`", name).into()),
+    }
+}
+
+/// Sets the value of an axis, replacing an earlier value of the same axis.
+fn set_variation(variations: &mut Vec<Variation>, variation: Variation) {
+    match variations.iter_mut().find(|v| v.axis == variation.axis) {
+        Some(existing) => existing.value = variation.value,
+        None => variations.push(variation),
+    }
+}
+
+/// Parses an axis tag of up to four characters, like `wght`.
+fn parse_tag(tag: &str) -> CliResult<Tag> {
+    if tag.is_empty() || tag.len() > 4 || !tag.is_ascii() {
+        return Err(format!("invalid axis tag `{}`", tag).into());
+    }
+    Ok(Tag::from_bytes_lossy(tag.as_bytes()))
+}
+
+fn parse_y_origin(name: &str) -> CliResult<YOrigin> {
+    match name {
+        "bottom" => Ok(YOrigin::Bottom),
+        "top" => Ok(YOrigin::Top),
+        _ => Err(format!("unknown y origin `{}`", name).into()),
+    }
+}
+
+fn set_error_correction(options: &mut Options, name: &str) -> CliResult<()> {
+    let (mode, check) = parse_error_correction(name)?;
+    let config = &mut options.config.msdf_config.error_correction_config;
+    config.error_correction_mode = mode;
+    config.distance_check_mode = check;
+    Ok(())
+}
+
+fn add_charset(options: &mut Options, charset: &str) -> CliResult<()> {
+    options.chars.extend(&Charset::parse(charset)?);
+    Ok(())
+}
+
+fn add_glyphset(options: &mut Options, glyphset: &str) -> CliResult<()> {
+    options.glyphs.extend(parse_glyph_set(glyphset)?);
+    Ok(())
+}
+
+/// Glyphs of the atlas: those of the characters first, then glyphs by index that weren't added
+/// for a character.
+fn glyphs(
+    face: &Face,
+    resolved: &ResolvedCharset,
+    options: &Options,
+) -> Vec<(Option<char>, GlyphId)> {
+    let mut glyphs: Vec<(Option<char>, GlyphId)> = resolved
+        .glyphs
+        .iter()
+        .map(|&(c, glyph_id)| (Some(c), glyph_id))
+        .collect();
+
+    let indices: Vec<GlyphId> = if options.all_glyphs {
+        (0..face.number_of_glyphs()).map(GlyphId).collect()
+    } else {
+        options.glyphs.clone()
+    };
+    for glyph_id in indices {
+        if glyph_id.0 < face.number_of_glyphs() && glyphs.iter().all(|&(_, id)| id != glyph_id) {
+            glyphs.push((None, glyph_id));
+        }
+    }
+    glyphs
+}
+
+/// Output path with `suffixes` appended to the file name, so atlases of several fonts or
+/// instances don't overwrite each other.
+fn output_path(path: &Path, suffixes: &[String]) -> PathBuf {
+    if suffixes.is_empty() {
+        return path.to_owned();
+    }
+
+    let mut name = OsString::from(path.file_stem().unwrap_or_default());
+    for suffix in suffixes {
+        name.push("-");
+        name.push(suffix);
+    }
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn family_name(face: &Face) -> Option<String> {
+    face.names()
+        .into_iter()
+        .filter(|name| name.name_id == name_id::FAMILY && name.is_unicode())
+        .find_map(|name| name.to_string())
+}
+
+fn type_name(atlas_type: AtlasType) -> &'static str {
+    match atlas_type {
+        AtlasType::SDF => "sdf",
+        AtlasType::PsuedoSDF => "psdf",
+        AtlasType::MSDF => "msdf",
+        AtlasType::MTSDF => "mtsdf",
+    }
+}
+
+/// Glyphs are identified by code point when all of them have one, otherwise by glyph index.
+fn glyph_key(glyph: &AtlasGlyph, by_codepoint: bool) -> (&'static str, u32) {
+    match glyph.codepoint {
+        Some(c) if by_codepoint => ("unicode", c as u32),
+        _ => ("index", glyph.glyph_id.0 as u32),
+    }
+}
+
+fn bounds_json(bounds: &Bounds) -> Value {
+    json!({
+        "left": bounds.left,
+        "bottom": bounds.bottom,
+        "right": bounds.right,
+        "top": bounds.top,
+    })
+}
+
+/// Layout in msdf-atlas-gen's JSON format, with plane bounds in ems and atlas bounds in pixels.
+fn layout_json(atlas: &Atlas, y_origin: YOrigin) -> Value {
+    let (width, height) = atlas.field.dimensions();
+    let by_codepoint = atlas.glyphs.iter().all(|glyph| glyph.codepoint.is_some());
+
+    let set_bounds = |value: &mut Value, plane: Option<&Bounds>, rect: Option<AtlasRect>| {
+        if let (Some(plane), Some(rect)) = (plane, rect) {
+            let (top, bottom) = match y_origin {
+                YOrigin::Top => (rect.y, rect.y + rect.height),
+                YOrigin::Bottom => (height - rect.y, height - rect.y - rect.height),
+            };
+            value["planeBounds"] = bounds_json(plane);
+            value["atlasBounds"] = bounds_json(&Bounds {
+                left: rect.x as f64,
+                bottom: bottom as f64,
+                right: (rect.x + rect.width) as f64,
+                top: top as f64,
+            });
+        }
+    };
+
+    let glyphs: Vec<Value> = atlas
+        .glyphs
+        .iter()
+        .enumerate()
+        .map(|(index, glyph)| {
+            let (key, id) = glyph_key(glyph, by_codepoint);
+            let mut value = json!({ key: id, "advance": glyph.advance });
+            set_bounds(&mut value, glyph.plane_bounds.as_ref(), glyph.atlas_rect);
+
+            // color layers aren't part of msdf-atlas-gen's format
+            let layers: Vec<Value> = atlas
+                .layers
+                .iter()
+                .filter(|layer| layer.glyph == index)
+                .map(|layer| {
+                    let mut value = match layer.color {
+                        LayerColor::Palette(Rgba([r, g, b, a])) => json!({
+                            "color": format!("#{:02x}{:02x}{:02x}", r, g, b),
+                            "alpha": a as f64 / 255.0,
+                        }),
+                        LayerColor::Foreground(a) => json!({
+                            "color": "foreground",
+                            "alpha": a as f64 / 255.0,
+                        }),
+                    };
+                    set_bounds(&mut value, layer.plane_bounds.as_ref(), layer.atlas_rect);
+                    value
+                })
+                .collect();
+            if !layers.is_empty() {
+                value["layers"] = Value::Array(layers);
+            }
+            value
+        })
+        .collect();
+
+    let by_id: HashMap<GlyphId, &AtlasGlyph> = atlas
+        .glyphs
+        .iter()
+        .rev()
+        .map(|glyph| (glyph.glyph_id, glyph))
+        .collect();
+    let kerning: Vec<Value> = atlas
+        .kerning
+        .iter()
+        .map(|pair| {
+            let (key1, id1) = glyph_key(by_id[&pair.left], by_codepoint);
+            let (key2, id2) = glyph_key(by_id[&pair.right], by_codepoint);
+            json!({
+                format!("{}1", key1): id1,
+                format!("{}2", key2): id2,
+                "advance": pair.advance,
+            })
+        })
+        .collect();
+
+    let metrics = &atlas.metrics;
+    json!({
+        "atlas": {
+            "type": type_name(atlas.field.atlas_type()),
+            "distanceRange": atlas.field.pixel_range(),
+            "size": atlas.em_size,
+            "width": width,
+            "height": height,
+            "yOrigin": if y_origin == YOrigin::Top { "top" } else { "bottom" },
+        },
+        "metrics": {
+            "emSize": 1,
+            "lineHeight": metrics.line_height,
+            "ascender": metrics.ascender,
+            "descender": metrics.descender,
+            "underlineY": metrics.underline_y,
+            "underlineThickness": metrics.underline_thickness,
+        },
+        "glyphs": glyphs,
+        "kerning": kerning,
+    })
+}
+
+/// Layout as a BMFont text descriptor, in whole pixels at the em size. Glyphs without a
+/// character are left out, since BMFont identifies glyphs by character.
+fn bmfont(atlas: &Atlas, name: &str, image: &Path, padding: u32) -> String {
+    let em = atlas.em_size;
+    let px = |ems: f64| (ems * em).round() as i64;
+    let (width, height) = atlas.field.dimensions();
+    let base = px(atlas.metrics.ascender);
+    let glyphs: Vec<&AtlasGlyph> = atlas
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.codepoint.is_some())
+        .collect();
+
+    let mut fnt = String::new();
+    // writing to a string can't fail
+    let _ = writeln!(
+        fnt,
+        "info face=\"{}\" size={} bold=0 italic=0 charset=\"\" unicode=1 stretchH=100 smooth=1 \
+         aa=1 padding=0,0,0,0 spacing={},{}",
+        name, em, padding, padding
+    );
+    let _ = writeln!(
+        fnt,
+        "common lineHeight={} base={} scaleW={} scaleH={} pages=1 packed=0",
+        px(atlas.metrics.line_height),
+        base,
+        width,
+        height
+    );
+    let _ = writeln!(
+        fnt,
+        "page id=0 file=\"{}\"",
+        image.file_name().unwrap_or_default().to_string_lossy()
+    );
+    let _ = writeln!(
+        fnt,
+        "distanceField fieldType={} distanceRange={}",
+        type_name(atlas.field.atlas_type()),
+        atlas.field.pixel_range()
+    );
+    let _ = writeln!(fnt, "chars count={}", glyphs.len());
+    for glyph in &glyphs {
+        let (x, y, w, h) = glyph.atlas_rect.map_or((0, 0, 0, 0), |rect| {
+            (rect.x, rect.y, rect.width, rect.height)
+        });
+        let (xoffset, yoffset) = glyph
+            .plane_bounds
+            .map_or((0, 0), |plane| (px(plane.left), base - px(plane.top)));
+        let _ = writeln!(
+            fnt,
+            "char id={} x={} y={} width={} height={} xoffset={} yoffset={} xadvance={} page=0 \
+             chnl=15",
+            glyph.codepoint.unwrap() as u32,
+            x,
+            y,
+            w,
+            h,
+            xoffset,
+            yoffset,
+            px(glyph.advance)
+        );
+    }
+
+    let by_id: HashMap<GlyphId, char> = glyphs
+        .iter()
+        .rev()
+        .map(|glyph| (glyph.glyph_id, glyph.codepoint.unwrap()))
+        .collect();
+    let kerning: Vec<_> = atlas
+        .kerning
+        .iter()
+        .filter_map(|pair| Some((by_id.get(&pair.left)?, by_id.get(&pair.right)?, pair)))
+        .collect();
+    let _ = writeln!(fnt, "kernings count={}", kerning.len());
+    for (first, second, pair) in kerning {
+        let _ = writeln!(
+            fnt,
+            "kerning first={} second={} amount={}",
+            *first as u32,
+            *second as u32,
+            px(pair.advance)
+        );
+    }
+
+    fnt
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+/// Options stored in a TOML file, named like the command-line options.
+struct Preset {
+    #[serde(skip)]
+    directory: PathBuf,
+    fonts: Vec<PathBuf>,
+    charset: Option<PathBuf>,
+    chars: Option<String>,
+    textfile: Option<PathBuf>,
+    glyphset: Option<PathBuf>,
+    glyphs: Option<String>,
+    allglyphs: Option<bool>,
+    variations: BTreeMap<String, f32>,
+    instances: Vec<String>,
+    allinstances: Option<bool>,
+    #[serde(rename = "type")]
+    atlas_type: Option<String>,
+    size: Option<f64>,
+    pxrange: Option<f64>,
+    pxpadding: Option<u32>,
+    maxwidth: Option<u32>,
+    pot: Option<bool>,
+    palette: Option<u16>,
+    coloringstrategy: Option<String>,
+    angle: Option<String>,
+    seed: Option<u64>,
+    errorcorrection: Option<String>,
+    overlap: Option<bool>,
+    legacy: Option<bool>,
+    imageout: Option<PathBuf>,
+    format: Option<String>,
+    json: Option<PathBuf>,
+    fnt: Option<PathBuf>,
+    arfont: Option<PathBuf>,
+    yorigin: Option<String>,
+}
+
+impl Preset {
+    fn load(path: &Path) -> CliResult<Preset> {
+        let text = fs::read_to_string(path)?;
+        let mut preset: Preset =
+            toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        preset.directory = path.parent().unwrap_or_else(|| Path::new("")).to_owned();
+        Ok(preset)
+    }
+
+    fn path(&self, path: &Path) -> PathBuf {
+        self.directory.join(path)
+    }
+
+    fn apply(self, options: &mut Options) -> CliResult<()> {
+        let config = &mut options.config;
+        if let Some(atlas_type) = &self.atlas_type {
+            config.atlas_type = parse_type(atlas_type)?;
+        }
+        if let Some(size) = self.size {
+            config.em_size = size;
+        }
+        if let Some(range) = self.pxrange {
+            config.pixel_range = range;
+        }
+        if let Some(padding) = self.pxpadding {
+            config.padding = padding;
+        }
+        if let Some(width) = self.maxwidth {
+            config.max_width = width;
+        }
+        if let Some(pot) = self.pot {
+            config.power_of_two = pot;
+        }
+        if let Some(strategy) = &self.coloringstrategy {
+            config.coloring_strategy = parse_coloring_strategy(strategy)?;
+        }
+        if let Some(angle) = &self.angle {
+            config.coloring_config.angle = parse_angle(angle)?;
+        }
+        if let Some(seed) = self.seed {
+            config.coloring_config.seed = seed;
+        }
+        if let Some(overlap) = self.overlap {
+            config.msdf_config.overlap_support = overlap;
+        }
+        if let Some(legacy) = self.legacy {
+            config.msdf_config.generator = if legacy {
+                Generator::Legacy
+            } else {
+                Default::default()
+            };
+        }
+        if let Some(mode) = &self.errorcorrection {
+            set_error_correction(options, mode)?;
+        }
+
+        for font in &self.fonts {
+            options.fonts.push(self.path(font));
+        }
+        if let Some(charset) = &self.charset {
+            add_charset(options, &fs::read_to_string(self.path(charset))?)?;
+        }
+        if let Some(chars) = &self.chars {
+            add_charset(options, chars)?;
+        }
+        if let Some(textfile) = &self.textfile {
+            let text = fs::read_to_string(self.path(textfile))?;
+            options.chars.extend(&Charset::from_text(&text));
+        }
+        if let Some(glyphset) = &self.glyphset {
+            add_glyphset(options, &fs::read_to_string(self.path(glyphset))?)?;
+        }
+        if let Some(glyphs) = &self.glyphs {
+            add_glyphset(options, glyphs)?;
+        }
+        if let Some(all_glyphs) = self.allglyphs {
+            options.all_glyphs = all_glyphs;
+        }
+        for (axis, &value) in &self.variations {
+            let axis = parse_tag(axis)?;
+            set_variation(&mut options.variations, Variation { axis, value });
+        }
+        options.instances.extend(self.instances.iter().cloned());
+        if let Some(all_instances) = self.allinstances {
+            options.all_instances = all_instances;
+        }
+        if self.palette.is_some() {
+            options.palette = self.palette;
+        }
+
+        if let Some(path) = &self.imageout {
+            options.image_out = Some(self.path(path));
+        }
+        if let Some(format) = &self.format {
+            options.format = Some(OutputFormat::from_name(format)?);
+        }
+        if let Some(path) = &self.json {
+            options.json = Some(self.path(path));
+        }
+        if let Some(path) = &self.fnt {
+            options.fnt = Some(self.path(path));
+        }
+        if let Some(path) = &self.arfont {
+            options.arfont = Some(self.path(path));
+        }
+        if let Some(origin) = &self.yorigin {
+            options.y_origin = parse_y_origin(origin)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,401 @@
+use super::{bmfont, instances, layout_json, output_path, parse, Options, Preset, YOrigin};
+use crate::args::{Args, OutputFormat};
+use image::{ImageBuffer, Rgba};
+use msdf::{
+    Atlas, AtlasField, AtlasGlyph, AtlasLayer, AtlasRect, AtlasType, Bounds, FontMetrics,
+    KerningPair, LayerColor, SDFTrait, MSDF,
+};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use ttf_parser::{Face, GlyphId, Tag, Variation};
+
+fn read_font(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../msdf/test_resources")
+        .join(name);
+    fs::read(path).unwrap()
+}
+
+/// Names of the faces to bake and the right edge of their `A`.
+fn baked(faces: &[(Option<String>, Face)]) -> Vec<(Option<String>, i16)> {
+    faces
+        .iter()
+        .map(|(name, face)| {
+            let glyph = face.glyph_index('A').unwrap();
+            (name.clone(), face.glyph_bounding_box(glyph).unwrap().x_max)
+        })
+        .collect()
+}
+
+fn test_atlas() -> Atlas {
+    Atlas {
+        field: AtlasField::MSDF(MSDF::from_image(ImageBuffer::new(32, 16), 4.0, 0.5)),
+        glyphs: vec![
+            AtlasGlyph {
+                codepoint: Some('A'),
+                glyph_id: GlyphId(36),
+                advance: 0.5,
+                plane_bounds: Some(Bounds {
+                    left: 0.0,
+                    bottom: -0.125,
+                    right: 0.5,
+                    top: 0.625,
+                }),
+                atlas_rect: Some(AtlasRect {
+                    x: 1,
+                    y: 2,
+                    width: 10,
+                    height: 12,
+                }),
+            },
+            AtlasGlyph {
+                codepoint: Some(' '),
+                glyph_id: GlyphId(3),
+                advance: 0.25,
+                plane_bounds: None,
+                atlas_rect: None,
+            },
+        ],
+        kerning: vec![KerningPair {
+            left: GlyphId(36),
+            right: GlyphId(3),
+            advance: -0.0625,
+        }],
+        layers: vec![],
+        metrics: FontMetrics {
+            ascender: 0.9,
+            descender: -0.25,
+            line_height: 1.2,
+            underline_y: -0.1,
+            underline_thickness: 0.05,
+        },
+        em_size: 32.0,
+    }
+}
+
+#[test]
+fn can_write_layout_json() {
+    let atlas = test_atlas();
+
+    let json = layout_json(&atlas, YOrigin::Bottom);
+    assert_eq!(
+        json["atlas"],
+        json!({
+            "type": "msdf",
+            "distanceRange": 4.0,
+            "size": 32.0,
+            "width": 32,
+            "height": 16,
+            "yOrigin": "bottom",
+        })
+    );
+    assert_eq!(json["metrics"]["emSize"], 1);
+    assert_eq!(json["metrics"]["lineHeight"], 1.2);
+    assert_eq!(json["metrics"]["underlineY"], -0.1);
+    assert_eq!(
+        json["glyphs"],
+        json!([
+            {
+                "unicode": 65,
+                "advance": 0.5,
+                "planeBounds": { "left": 0.0, "bottom": -0.125, "right": 0.5, "top": 0.625 },
+                "atlasBounds": { "left": 1.0, "bottom": 2.0, "right": 11.0, "top": 14.0 },
+            },
+            { "unicode": 32, "advance": 0.25 },
+        ])
+    );
+    assert_eq!(
+        json["kerning"],
+        json!([{ "unicode1": 65, "unicode2": 32, "advance": -0.0625 }])
+    );
+
+    let json = layout_json(&atlas, YOrigin::Top);
+    assert_eq!(json["atlas"]["yOrigin"], "top");
+    assert_eq!(
+        json["glyphs"][0]["atlasBounds"],
+        json!({ "left": 1.0, "bottom": 14.0, "right": 11.0, "top": 2.0 })
+    );
+}
+
+#[test]
+fn can_write_layout_json_by_index() {
+    let mut atlas = test_atlas();
+    atlas.glyphs.push(AtlasGlyph {
+        codepoint: None,
+        glyph_id: GlyphId(99),
+        advance: 0.0,
+        plane_bounds: None,
+        atlas_rect: None,
+    });
+
+    // a single glyph without a character identifies every glyph by index
+    let json = layout_json(&atlas, YOrigin::Bottom);
+    assert_eq!(json["glyphs"][0]["index"], 36);
+    assert_eq!(json["glyphs"][2]["index"], 99);
+    assert!(json["glyphs"][0].get("unicode").is_none());
+    assert_eq!(
+        json["kerning"],
+        json!([{ "index1": 36, "index2": 3, "advance": -0.0625 }])
+    );
+}
+
+#[test]
+fn can_write_layout_json_layers() {
+    let mut atlas = test_atlas();
+    atlas.layers = vec![
+        AtlasLayer {
+            glyph: 0,
+            color: LayerColor::Palette(Rgba([255, 0, 0, 51])),
+            plane_bounds: Some(Bounds {
+                left: 0.0,
+                bottom: 0.0,
+                right: 0.25,
+                top: 0.25,
+            }),
+            atlas_rect: Some(AtlasRect {
+                x: 12,
+                y: 0,
+                width: 6,
+                height: 6,
+            }),
+        },
+        AtlasLayer {
+            glyph: 0,
+            color: LayerColor::Foreground(255),
+            plane_bounds: None,
+            atlas_rect: None,
+        },
+    ];
+
+    let json = layout_json(&atlas, YOrigin::Bottom);
+    assert_eq!(
+        json["glyphs"][0]["layers"],
+        json!([
+            {
+                "color": "#ff0000",
+                "alpha": 0.2,
+                "planeBounds": { "left": 0.0, "bottom": 0.0, "right": 0.25, "top": 0.25 },
+                "atlasBounds": { "left": 12.0, "bottom": 10.0, "right": 18.0, "top": 16.0 },
+            },
+            { "color": "foreground", "alpha": 1.0 },
+        ])
+    );
+    assert!(json["glyphs"][1].get("layers").is_none());
+}
+
+#[test]
+fn can_write_bmfont() {
+    let mut atlas = test_atlas();
+    atlas.glyphs.push(AtlasGlyph {
+        codepoint: None,
+        glyph_id: GlyphId(99),
+        advance: 0.0,
+        plane_bounds: None,
+        atlas_rect: None,
+    });
+
+    let fnt = bmfont(&atlas, "Test", Path::new("out/atlas.png"), 2);
+    let lines: Vec<&str> = fnt.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "info face=\"Test\" size=32 bold=0 italic=0 charset=\"\" unicode=1 stretchH=100 \
+             smooth=1 aa=1 padding=0,0,0,0 spacing=2,2",
+            "common lineHeight=38 base=29 scaleW=32 scaleH=16 pages=1 packed=0",
+            "page id=0 file=\"atlas.png\"",
+            "distanceField fieldType=msdf distanceRange=4",
+            "chars count=2",
+            "char id=65 x=1 y=2 width=10 height=12 xoffset=0 yoffset=9 xadvance=16 page=0 chnl=15",
+            "char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=8 page=0 chnl=15",
+            "kernings count=1",
+            "kerning first=65 second=32 amount=-2",
+        ]
+    );
+}
+
+#[test]
+fn can_suffix_output_path() {
+    let path = Path::new("out/atlas.png");
+    assert_eq!(output_path(path, &[]), PathBuf::from("out/atlas.png"));
+    assert_eq!(
+        output_path(path, &["Roboto".to_string(), "Bold".to_string()]),
+        PathBuf::from("out/atlas-Roboto-Bold.png")
+    );
+    assert_eq!(
+        output_path(Path::new("layout"), &["Bold".to_string()]),
+        PathBuf::from("layout-Bold")
+    );
+}
+
+#[test]
+fn can_apply_preset() {
+    let preset = Preset {
+        directory: PathBuf::from("presets"),
+        fonts: vec![
+            PathBuf::from("Roboto.ttf"),
+            PathBuf::from("/fonts/Noto.ttf"),
+        ],
+        chars: Some("'A', 'B'".to_string()),
+        variations: BTreeMap::from([("wght".to_string(), 600.0)]),
+        instances: vec!["Bold".to_string()],
+        atlas_type: Some("mtsdf".to_string()),
+        size: Some(48.0),
+        pot: Some(true),
+        palette: Some(1),
+        imageout: Some(PathBuf::from("out/atlas.png")),
+        format: Some("tiff".to_string()),
+        json: Some(PathBuf::from("atlas.json")),
+        yorigin: Some("top".to_string()),
+        ..Default::default()
+    };
+    let mut options = Options {
+        fonts: vec![PathBuf::from("first.ttf")],
+        ..Default::default()
+    };
+    preset.apply(&mut options).unwrap();
+
+    // paths are relative to the preset, unless they are absolute
+    assert_eq!(
+        options.fonts,
+        [
+            PathBuf::from("first.ttf"),
+            Path::new("presets").join("Roboto.ttf"),
+            PathBuf::from("/fonts/Noto.ttf"),
+        ]
+    );
+    assert_eq!(
+        options.image_out,
+        Some(Path::new("presets").join("out/atlas.png"))
+    );
+    assert_eq!(options.json, Some(Path::new("presets").join("atlas.json")));
+    assert_eq!(options.fnt, None);
+
+    assert!(options.chars.contains('A') && options.chars.contains('B'));
+    assert_eq!(options.chars.len(), 2);
+    assert_eq!(
+        options.variations,
+        [Variation {
+            axis: Tag::from_bytes(b"wght"),
+            value: 600.0,
+        }]
+    );
+    assert_eq!(options.instances, ["Bold"]);
+    assert_eq!(options.config.atlas_type, AtlasType::MTSDF);
+    assert_eq!(options.config.em_size, 48.0);
+    assert!(options.config.power_of_two);
+    assert_eq!(options.palette, Some(1));
+    assert_eq!(options.format, Some(OutputFormat::Tiff));
+    assert!(options.y_origin == YOrigin::Top);
+}
+
+#[test]
+fn can_load_preset() {
+    let directory = env::temp_dir().join(format!("msdf-cli-preset-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("preset.toml");
+    fs::write(
+        &path,
+        "fonts = [\"Roboto.ttf\"]\n\
+         size = 48\n\
+         pxrange = 6\n\
+         variations = { wght = 600 }\n",
+    )
+    .unwrap();
+
+    // options after the preset override it
+    let mut args = Args::new(
+        ["-preset", path.to_str().unwrap(), "-size", "64"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect(),
+    );
+    let options = parse(&mut args);
+    fs::remove_dir_all(&directory).unwrap();
+    let options = options.unwrap();
+
+    assert_eq!(options.fonts, [directory.join("Roboto.ttf")]);
+    assert_eq!(options.config.em_size, 64.0);
+    assert_eq!(options.config.pixel_range, 6.0);
+    assert_eq!(options.variations.len(), 1);
+}
+
+#[test]
+fn rejects_invalid_preset() {
+    assert!(toml::from_str::<Preset>("size = 48\nunknown = true").is_err());
+
+    let preset = Preset {
+        atlas_type: Some("bitmap".to_string()),
+        ..Default::default()
+    };
+    assert!(preset.apply(&mut Options::default()).is_err());
+
+    let preset = Preset {
+        variations: BTreeMap::from([("weight".to_string(), 600.0)]),
+        ..Default::default()
+    };
+    assert!(preset.apply(&mut Options::default()).is_err());
+}
+
+#[test]
+fn can_select_instances() {
+    let data = read_font("variable.ttf");
+    let face = Face::from_slice(&data, 0).unwrap();
+    let weight = |value| Variation {
+        axis: Tag::from_bytes(b"wght"),
+        value,
+    };
+
+    // every named instance is baked with its own outlines
+    let options = Options {
+        all_instances: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        baked(&instances(&face, &options).unwrap()),
+        [
+            (Some("Thin".to_string()), 300),
+            (Some("Regular".to_string()), 500),
+            (Some("Black".to_string()), 700),
+        ]
+    );
+
+    // variations on the command line override the instances
+    let options = Options {
+        instances: vec!["Black".to_string()],
+        variations: vec![weight(400.0)],
+        ..Default::default()
+    };
+    assert_eq!(
+        baked(&instances(&face, &options).unwrap()),
+        [(Some("Black".to_string()), 500)]
+    );
+
+    // without instances the face itself is baked with the variations
+    let options = Options {
+        variations: vec![weight(100.0)],
+        ..Default::default()
+    };
+    assert_eq!(baked(&instances(&face, &options).unwrap()), [(None, 300)]);
+
+    let options = Options {
+        instances: vec!["Medium".to_string()],
+        ..Default::default()
+    };
+    assert!(instances(&face, &options).is_err());
+}
+
+#[test]
+fn rejects_instances_of_static_fonts() {
+    let data = read_font("Roboto-Medium.ttf");
+    let face = Face::from_slice(&data, 0).unwrap();
+
+    let options = Options {
+        all_instances: true,
+        ..Default::default()
+    };
+    assert!(instances(&face, &options).is_err());
+    assert_eq!(instances(&face, &Options::default()).unwrap().len(), 1);
+}
@@ -0,0 +1,544 @@
+use crate::args::{
+    flip_field, parse_angle, parse_coloring_strategy, parse_error_correction, save_field,
+    save_render, Args, CliResult, OutputFormat,
+};
+use image::{DynamicImage, ImageBuffer, Pixel};
+use mint::Vector2;
+use msdf::{
+    Bounds, ColoredShape, ColoringStrategy, DdsField, EdgeColor, EdgeColoringConfig, FieldFile,
+    Generator, GlyphLoader, Ktx2Field, MSDFConfig, Projection, SDFConfig, SDFTrait, Shape,
+};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use ttf_parser::{Face, GlyphId};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Mode {
+    Sdf,
+    PsuedoSdf,
+    Msdf,
+    Mtsdf,
+}
+
+impl Mode {
+    fn from_name(name: &str) -> Option<Mode> {
+        match name {
+            "sdf" => Some(Mode::Sdf),
+            "psdf" => Some(Mode::PsuedoSdf),
+            "msdf" => Some(Mode::Msdf),
+            "mtsdf" => Some(Mode::Mtsdf),
+            _ => None,
+        }
+    }
+}
+
+enum Input {
+    Font { path: PathBuf, glyph: String },
+    Svg(PathBuf),
+    DescriptionFile(PathBuf),
+    Description(String),
+    Stdin,
+}
+
+#[derive(Copy, Clone)]
+enum Range {
+    Pixels(f64),
+    Units(f64),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Orientation {
+    Keep,
+    Reverse,
+    Guess,
+}
+
+struct TestRender {
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    colored: bool,
+}
+
+struct Options {
+    mode: Mode,
+    input: Option<Input>,
+    output: PathBuf,
+    format: Option<OutputFormat>,
+    width: u32,
+    height: u32,
+    range: Range,
+    autoframe: bool,
+    scale: Option<Vector2<f64>>,
+    translate: Vector2<f64>,
+    coloring_strategy: ColoringStrategy,
+    coloring_config: EdgeColoringConfig,
+    msdf_config: MSDFConfig,
+    orientation: Orientation,
+    y_flip: bool,
+    print_metrics: bool,
+    test_renders: Vec<TestRender>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            mode: Mode::Msdf,
+            input: None,
+            output: PathBuf::from("output.png"),
+            format: None,
+            width: 64,
+            height: 64,
+            range: Range::Pixels(2.0),
+            autoframe: false,
+            scale: None,
+            translate: Vector2 { x: 0.0, y: 0.0 },
+            coloring_strategy: Default::default(),
+            coloring_config: Default::default(),
+            msdf_config: Default::default(),
+            orientation: Orientation::Guess,
+            y_flip: false,
+            print_metrics: false,
+            test_renders: vec![],
+        }
+    }
+}
+
+pub const USAGE: &str = "\
+Usage: msdf [sdf | psdf | msdf | mtsdf] <input> [options]
+       msdf atlas -font <file> [options]
+
+Use `msdf atlas -help` for the options of glyph atlases.
+
+Input:
+  -font <file> <glyph>      Glyph of a TrueType or OpenType font, in font units. The glyph is a
+                            character, a code point (65, 0x41 or U+0041) or a glyph index (g36).
+  -svg <file>               Path elements of an SVG file, without transforms or <defs>.
+  -shapedesc <file>         Shape description file, in msdfgen's format.
+  -defineshape <text>       Shape description given inline.
+  -stdin                    Shape description read from standard input.
+
+Options:
+  -mode <mode>              sdf, psdf, msdf (default) or mtsdf.
+  -o <file>                 Output file, output.png by default.
+  -format <format>          png, tiff, binfloat, ktx2, dds or exr. Guessed from the output file.
+  -size <width> <height>    Size of the field in pixels, 64x64 by default.
+  -pxrange <range>          Distance range in pixels of the output, 2 by default.
+  -range <range>            Distance range in shape units.
+  -autoframe                Scales and centers the shape to fit the field, with room for the range.
+  -scale <scale>            Scale from shape units to pixels.
+  -ascale <x> <y>           Scale with different factors for each axis.
+  -translate <x> <y>        Translation in shape units, applied before scaling.
+  -coloringstrategy <name>  simple (default), inktrap or distance.
+  -angle <angle>            Corner angle for edge coloring, in radians or degrees with a d suffix.
+  -seed <seed>              Seed for edge coloring.
+  -errorcorrection <mode>   auto-fast, auto-mixed (default), auto-full, distance-fast,
+                            distance-full, edge-fast, edge-full or disabled.
+  -errordeviationratio <r>  Minimum deviation ratio of error correction.
+  -errorimproveratio <r>    Minimum improvement ratio of error correction.
+  -nooverlap                Disables support for overlapping contours.
+  -legacy                   Uses msdfgen's legacy generators.
+  -keeporder                Keeps the direction of contours as loaded.
+  -reverseorder             Reverses the direction of all contours.
+  -guessorder               Reverses contours if the outside of the shape would be inside (default).
+  -yflip                    Flips the output upside down.
+  -printmetrics             Prints the bounds, projection and range used.
+  -testrender <file> <width> <height>
+                            Renders the field to an image, to check it.
+  -testrendermulti <file> <width> <height>
+                            Renders the field with its channels shown as colors.
+";
+
+/// Generates a field for a single shape, like msdfgen's command-line tool.
+pub fn run(mut args: Args) -> CliResult<()> {
+    let options = parse(&mut args)?;
+    let input = options
+        .input
+        .as_ref()
+        .ok_or("no input specified, see -help")?;
+
+    let mut shape = load_shape(input)?;
+    let bounds = shape.bounds().ok_or("the shape has no edges")?;
+
+    let reverse = match options.orientation {
+        Orientation::Keep => false,
+        Orientation::Reverse => true,
+        Orientation::Guess => is_inside_out(&shape, &bounds),
+    };
+    if reverse {
+        shape.reverse();
+    }
+
+    let (projection, range) = frame(&options, &bounds)?;
+    if options.print_metrics {
+        println!(
+            "bounds = {}, {}, {}, {}",
+            bounds.left, bounds.bottom, bounds.right, bounds.top
+        );
+        println!("scale = {}, {}", projection.scale.x, projection.scale.y);
+        println!(
+            "translate = {}, {}",
+            projection.translation.x, projection.translation.y
+        );
+        println!("range = {}", range);
+        println!(
+            "pixel range = {}",
+            range * projection.scale.x.min(projection.scale.y)
+        );
+        if reverse {
+            println!("contours reversed");
+        }
+    }
+
+    let sdf_config = SDFConfig {
+        overlap_support: options.msdf_config.overlap_support,
+        generator: options.msdf_config.generator,
+    };
+    let (width, height) = (options.width, options.height);
+    match options.mode {
+        Mode::Sdf => finish(
+            shape.generate_sdf(width, height, range, &projection, &sdf_config),
+            &options,
+        ),
+        Mode::PsuedoSdf => finish(
+            shape.generate_psuedo_sdf(width, height, range, &projection, &sdf_config),
+            &options,
+        ),
+        Mode::Msdf => {
+            let shape = color(shape, &options)?;
+            finish(
+                shape.generate_msdf(width, height, range, &projection, &options.msdf_config),
+                &options,
+            )
+        }
+        Mode::Mtsdf => {
+            let shape = color(shape, &options)?;
+            finish(
+                shape.generate_mtsdf(width, height, range, &projection, &options.msdf_config),
+                &options,
+            )
+        }
+    }
+}
+
+fn parse(args: &mut Args) -> CliResult<Options> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        let option = arg.as_str();
+        match option {
+            "-mode" => {
+                let mode = args.value(option)?;
+                options.mode =
+                    Mode::from_name(&mode).ok_or_else(|| format!("unknown mode `{}`", mode))?;
+            }
+            "-font" => {
+                options.input = Some(Input::Font {
+                    path: args.value(option)?.into(),
+                    glyph: args.value(option)?,
+                })
+            }
+            "-svg" => options.input = Some(Input::Svg(args.value(option)?.into())),
+            "-shapedesc" => {
+                options.input = Some(Input::DescriptionFile(args.value(option)?.into()))
+            }
+            "-defineshape" => options.input = Some(Input::Description(args.value(option)?)),
+            "-stdin" => options.input = Some(Input::Stdin),
+            "-o" | "-out" => options.output = args.value(option)?.into(),
+            "-format" => options.format = Some(OutputFormat::from_name(&args.value(option)?)?),
+            "-size" => {
+                options.width = args.parse(option)?;
+                options.height = args.parse(option)?;
+            }
+            "-pxrange" => options.range = Range::Pixels(args.parse(option)?),
+            "-range" => options.range = Range::Units(args.parse(option)?),
+            "-autoframe" => options.autoframe = true,
+            "-scale" => {
+                let scale = args.parse(option)?;
+                options.scale = Some(Vector2 { x: scale, y: scale });
+            }
+            "-ascale" => {
+                options.scale = Some(Vector2 {
+                    x: args.parse(option)?,
+                    y: args.parse(option)?,
+                })
+            }
+            "-translate" => {
+                options.translate = Vector2 {
+                    x: args.parse(option)?,
+                    y: args.parse(option)?,
+                }
+            }
+            "-coloringstrategy" | "-edgecoloring" => {
+                options.coloring_strategy = parse_coloring_strategy(&args.value(option)?)?
+            }
+            "-angle" => options.coloring_config.angle = parse_angle(&args.value(option)?)?,
+            "-seed" => options.coloring_config.seed = args.parse(option)?,
+            "-errorcorrection" => {
+                let (mode, check) = parse_error_correction(&args.value(option)?)?;
+                let config = &mut options.msdf_config.error_correction_config;
+                config.error_correction_mode = mode;
+                config.distance_check_mode = check;
+            }
+            "-errordeviationratio" => {
+                options
+                    .msdf_config
+                    .error_correction_config
+                    .min_deviation_ratio = args.parse(option)?
+            }
+            "-errorimproveratio" => {
+                options
+                    .msdf_config
+                    .error_correction_config
+                    .min_improve_ratio = args.parse(option)?
+            }
+            "-nooverlap" => options.msdf_config.overlap_support = false,
+            "-overlap" => options.msdf_config.overlap_support = true,
+            "-legacy" => options.msdf_config.generator = Generator::Legacy,
+            "-keeporder" => options.orientation = Orientation::Keep,
+            "-reverseorder" => options.orientation = Orientation::Reverse,
+            "-guessorder" => options.orientation = Orientation::Guess,
+            "-yflip" => options.y_flip = true,
+            "-printmetrics" => options.print_metrics = true,
+            "-testrender" | "-testrendermulti" => options.test_renders.push(TestRender {
+                path: args.value(option)?.into(),
+                width: args.parse(option)?,
+                height: args.parse(option)?,
+                colored: option == "-testrendermulti",
+            }),
+            _ => match Mode::from_name(option) {
+                Some(mode) => options.mode = mode,
+                None => return Err(format!("unknown option `{}`, see -help", option).into()),
+            },
+        }
+    }
+
+    Ok(options)
+}
+
+fn load_shape(input: &Input) -> CliResult<Shape> {
+    let shape = match input {
+        Input::Font { path, glyph } => {
+            let data = fs::read(path)?;
+            let face =
+                Face::from_slice(&data, 0).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let glyph = parse_glyph(&face, glyph)?;
+            face.load_shape(glyph)
+                .ok_or_else(|| format!("glyph {} has no outline", glyph.0))?
+        }
+        Input::Svg(path) => {
+            let svg = fs::read_to_string(path)?;
+            let paths = svg_paths(&svg).map_err(|e| format!("{}: {}", path.display(), e))?;
+            if paths.is_empty() {
+                return Err(format!("{} contains no paths", path.display()).into());
+            }
+            Shape::from_svg_path(&paths.join(" "))?
+        }
+        Input::DescriptionFile(path) => Shape::from_description(&fs::read_to_string(path)?)?,
+        Input::Description(description) => Shape::from_description(description)?,
+        Input::Stdin => {
+            let mut description = String::new();
+            io::stdin().read_to_string(&mut description)?;
+            Shape::from_description(&description)?
+        }
+    };
+    Ok(shape)
+}
+
+/// Parses a glyph given as a character, a code point or a glyph index.
+fn parse_glyph(face: &Face, glyph: &str) -> CliResult<GlyphId> {
+    let invalid = || format!("invalid glyph `{}`", glyph);
+    let unquoted = glyph
+        .strip_prefix('\'')
+        .and_then(|glyph| glyph.strip_suffix('\''))
+        .unwrap_or(glyph);
+
+    let mut chars = unquoted.chars();
+    let codepoint = match (chars.next(), chars.next()) {
+        (Some(c), None) if unquoted != glyph || !c.is_ascii_digit() => c as u32,
+        _ => {
+            if let Some(index) = glyph.strip_prefix('g') {
+                return Ok(GlyphId(index.parse().map_err(|_| invalid())?));
+            }
+            let hex = glyph
+                .strip_prefix("0x")
+                .or_else(|| glyph.strip_prefix("U+"))
+                .or_else(|| glyph.strip_prefix("u+"));
+            match hex {
+                Some(hex) => u32::from_str_radix(hex, 16).map_err(|_| invalid())?,
+                None => glyph.parse().map_err(|_| invalid())?,
+            }
+        }
+    };
+
+    let c = char::from_u32(codepoint).ok_or_else(invalid)?;
+    face.glyph_index(c)
+        .ok_or_else(|| format!("the font has no glyph for U+{:04X}", codepoint).into())
+}
+
+/// Whether the outside of the shape has a positive distance, like msdfgen's `-guessorder`, which
+/// checks the sign of the distance at a point outside of the bounds.
+fn is_inside_out(shape: &Shape, bounds: &Bounds) -> bool {
+    let outside = Vector2 {
+        x: bounds.left - bounds.width() - 1.0,
+        y: bounds.bottom - bounds.height() - 1.0,
+    };
+    shape.signed_distance(outside) > 0.0
+}
+
+/// Extracts the `d` attribute of every `<path>` element of an SVG document. Transforms and
+/// definitions aren't supported, so documents using them are rejected instead of loading a
+/// different shape than they describe.
+fn svg_paths(svg: &str) -> CliResult<Vec<&str>> {
+    let mut paths = vec![];
+    let mut rest = svg;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = rest.find('>').unwrap_or(rest.len());
+        let element = &rest[..end];
+        rest = &rest[end..];
+
+        let name = element
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        match name {
+            "defs" | "use" | "symbol" => {
+                return Err(format!("<{}> elements are not supported", name).into())
+            }
+            _ if attribute(element, "transform").is_some() => {
+                return Err(
+                    format!("the transform of a <{}> element is not supported", name).into(),
+                )
+            }
+            "path" => paths.extend(attribute(element, "d")),
+            _ => {}
+        }
+    }
+    Ok(paths)
+}
+
+/// Finds the quoted value of an attribute in the text of an element.
+fn attribute<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = element;
+    while let Some(position) = rest.find(name) {
+        let preceded_by_space =
+            matches!(rest[..position].chars().last(), Some(c) if c.is_whitespace());
+        let value = &rest[position + name.len()..];
+        rest = value;
+        let value = match value.strip_prefix('=') {
+            Some(value) if preceded_by_space => value,
+            _ => continue,
+        };
+        let quote = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => continue,
+        };
+        return value[1..].find(quote).map(|length| &value[1..length + 1]);
+    }
+    None
+}
+
+/// Computes the projection and the range in shape units.
+fn frame(options: &Options, bounds: &Bounds) -> CliResult<(Projection, f64)> {
+    let mut projection = Projection {
+        scale: options.scale.unwrap_or(Vector2 { x: 1.0, y: 1.0 }),
+        translation: options.translate,
+    };
+
+    if options.autoframe {
+        let (mut left, mut bottom, mut right, mut top) =
+            (bounds.left, bounds.bottom, bounds.right, bounds.top);
+        let mut frame_width = options.width as f64;
+        let mut frame_height = options.height as f64;
+        match options.range {
+            Range::Units(range) => {
+                left -= range / 2.0;
+                bottom -= range / 2.0;
+                right += range / 2.0;
+                top += range / 2.0;
+            }
+            Range::Pixels(range) => {
+                frame_width -= range;
+                frame_height -= range;
+            }
+        }
+        if frame_width <= 0.0 || frame_height <= 0.0 || right <= left || top <= bottom {
+            return Err("cannot fit the shape and range into the field".into());
+        }
+
+        let (width, height) = (right - left, top - bottom);
+        let translation = if options.scale.is_none() {
+            // fit the limiting dimension and center the other
+            let scale = (frame_width / width).min(frame_height / height);
+            projection.scale = Vector2 { x: scale, y: scale };
+            Vector2 {
+                x: 0.5 * (frame_width / scale - width) - left,
+                y: 0.5 * (frame_height / scale - height) - bottom,
+            }
+        } else {
+            Vector2 {
+                x: 0.5 * (frame_width / projection.scale.x - width) - left,
+                y: 0.5 * (frame_height / projection.scale.y - height) - bottom,
+            }
+        };
+        projection.translation = translation;
+        if let Range::Pixels(range) = options.range {
+            projection.translation.x += range / 2.0 / projection.scale.x;
+            projection.translation.y += range / 2.0 / projection.scale.y;
+        }
+    }
+
+    let range = match options.range {
+        Range::Units(range) => range,
+        Range::Pixels(range) => range / projection.scale.x.min(projection.scale.y),
+    };
+    Ok((projection, range))
+}
+
+/// Colors the shape, unless it was loaded with valid colors already.
+fn color(shape: Shape, options: &Options) -> CliResult<ColoredShape> {
+    let colored = shape
+        .edge_colors()
+        .iter()
+        .flatten()
+        .any(|&color| color != EdgeColor::White);
+    if colored {
+        return Ok(shape.into_colored(options.coloring_config.angle)?);
+    }
+
+    Ok(shape.color_edges(options.coloring_strategy, &options.coloring_config))
+}
+
+fn finish<T, P>(field: T, options: &Options) -> CliResult<()>
+where
+    T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>> + FieldFile + Ktx2Field + DdsField,
+    P: Pixel<Subpixel = f32> + 'static,
+    DynamicImage: From<T::ColoredOutput>,
+{
+    let field = if options.y_flip {
+        flip_field(&field)
+    } else {
+        field
+    };
+
+    let format = options
+        .format
+        .unwrap_or_else(|| OutputFormat::from_path(&options.output));
+    save_field(&field, &options.output, format)?;
+
+    for render in &options.test_renders {
+        save_render(
+            &field,
+            Path::new(&render.path),
+            render.width,
+            render.height,
+            render.colored,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,274 @@
+use super::{
+    frame, is_inside_out, parse, parse_glyph, svg_paths, Input, Mode, Options, Orientation, Range,
+};
+use crate::args::{parse_error_correction, Args, OutputFormat};
+use msdf::{Bounds, DistanceCheckMode, ErrorCorrectionMode, Generator, Shape};
+use std::fs;
+use std::path::{Path, PathBuf};
+use ttf_parser::{Face, GlyphId};
+
+fn args(args: &[&str]) -> Args {
+    Args::new(args.iter().map(|arg| arg.to_string()).collect())
+}
+
+fn roboto() -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../msdf/test_resources")
+        .join("Roboto-Medium.ttf");
+    fs::read(path).unwrap()
+}
+
+#[test]
+fn can_parse_arguments() {
+    let options = parse(&mut args(&[
+        "mtsdf",
+        "-font",
+        "font.ttf",
+        "A",
+        "-o",
+        "out.bin",
+        "-format",
+        "binfloat",
+        "-size",
+        "32",
+        "16",
+        "-range",
+        "4",
+        "-autoframe",
+        "-seed",
+        "7",
+        "-angle",
+        "90d",
+        "-errorcorrection",
+        "edge-full",
+        "-legacy",
+        "-reverseorder",
+        "-yflip",
+        "-testrendermulti",
+        "render.png",
+        "128",
+        "64",
+    ]))
+    .unwrap();
+
+    assert_eq!(options.mode, Mode::Mtsdf);
+    assert!(matches!(
+        &options.input,
+        Some(Input::Font { path, glyph }) if path == Path::new("font.ttf") && glyph == "A"
+    ));
+    assert_eq!(options.output, PathBuf::from("out.bin"));
+    assert_eq!(options.format, Some(OutputFormat::BinFloat));
+    assert_eq!((options.width, options.height), (32, 16));
+    assert!(matches!(options.range, Range::Units(range) if range == 4.0));
+    assert!(options.autoframe);
+    assert_eq!(options.coloring_config.seed, 7);
+    assert!((options.coloring_config.angle - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+    assert!(matches!(
+        options
+            .msdf_config
+            .error_correction_config
+            .error_correction_mode,
+        ErrorCorrectionMode::EdgeOnly
+    ));
+    assert!(matches!(options.msdf_config.generator, Generator::Legacy));
+    assert!(options.orientation == Orientation::Reverse);
+    assert!(options.y_flip);
+    assert_eq!(options.test_renders.len(), 1);
+    let render = &options.test_renders[0];
+    assert_eq!(render.path, PathBuf::from("render.png"));
+    assert_eq!(
+        (render.width, render.height, render.colored),
+        (128, 64, true)
+    );
+}
+
+#[test]
+fn can_parse_default_arguments() {
+    let options = parse(&mut args(&["-defineshape", "{ # }"])).unwrap();
+
+    assert_eq!(options.mode, Mode::Msdf);
+    assert!(
+        matches!(&options.input, Some(Input::Description(description)) if description == "{ # }")
+    );
+    assert_eq!(options.output, PathBuf::from("output.png"));
+    assert_eq!(options.format, None);
+    assert!(matches!(options.range, Range::Pixels(range) if range == 2.0));
+    assert!(options.orientation == Orientation::Guess);
+}
+
+#[test]
+fn rejects_invalid_arguments() {
+    assert!(parse(&mut args(&["-unknown"])).is_err());
+    assert!(parse(&mut args(&["-mode", "bitmap"])).is_err());
+    assert!(parse(&mut args(&["-size", "32"])).is_err());
+    assert!(parse(&mut args(&["-pxrange", "wide"])).is_err());
+    assert!(parse(&mut args(&["-format", "gif"])).is_err());
+    assert!(parse(&mut args(&["-font", "font.ttf"])).is_err());
+}
+
+#[test]
+fn can_parse_glyph() {
+    let data = roboto();
+    let face = Face::from_slice(&data, 0).unwrap();
+    let a = face.glyph_index('A').unwrap();
+
+    for glyph in ["A", "65", "0x41", "U+0041", "u+0041", "'A'"] {
+        assert_eq!(parse_glyph(&face, glyph).unwrap(), a, "{}", glyph);
+    }
+    assert_eq!(parse_glyph(&face, "g36").unwrap(), GlyphId(36));
+    // a quoted digit is a character, an unquoted one a code point
+    assert_eq!(
+        parse_glyph(&face, "'7'").unwrap(),
+        face.glyph_index('7').unwrap()
+    );
+    assert!(parse_glyph(&face, "7").is_err());
+
+    assert!(parse_glyph(&face, "AB").is_err());
+    assert!(parse_glyph(&face, "gx").is_err());
+    assert!(parse_glyph(&face, "0xD800").is_err());
+}
+
+#[test]
+fn can_frame_shape() {
+    let bounds = Bounds {
+        left: 0.0,
+        bottom: 0.0,
+        right: 10.0,
+        top: 5.0,
+    };
+
+    let options = Options {
+        scale: Some(mint::Vector2 { x: 2.0, y: 2.0 }),
+        translate: mint::Vector2 { x: 1.0, y: 1.0 },
+        range: Range::Pixels(4.0),
+        ..Default::default()
+    };
+    let (projection, range) = frame(&options, &bounds).unwrap();
+    assert_eq!((projection.scale.x, projection.scale.y), (2.0, 2.0));
+    assert_eq!(
+        (projection.translation.x, projection.translation.y),
+        (1.0, 1.0)
+    );
+    assert!((range - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn can_autoframe_shape() {
+    let bounds = Bounds {
+        left: 0.0,
+        bottom: 0.0,
+        right: 10.0,
+        top: 5.0,
+    };
+
+    // the range in pixels is kept free around the field, the wide shape is centered vertically
+    let options = Options {
+        autoframe: true,
+        range: Range::Pixels(4.0),
+        ..Default::default()
+    };
+    let (projection, range) = frame(&options, &bounds).unwrap();
+    assert!((projection.scale.x - 6.0).abs() < 1e-9 && (projection.scale.y - 6.0).abs() < 1e-9);
+    assert!((projection.translation.x - 1.0 / 3.0).abs() < 1e-9);
+    assert!((projection.translation.y - (2.5 + 1.0 / 3.0)).abs() < 1e-9);
+    assert!((range - 4.0 / 6.0).abs() < 1e-9);
+
+    // the range in shape units widens the bounds instead
+    let options = Options {
+        autoframe: true,
+        range: Range::Units(2.0),
+        ..Default::default()
+    };
+    let (projection, range) = frame(&options, &bounds).unwrap();
+    assert!((projection.scale.x - 64.0 / 12.0).abs() < 1e-9);
+    assert!((projection.translation.x - 1.0).abs() < 1e-9);
+    assert!((projection.translation.y - 3.5).abs() < 1e-9);
+    assert_eq!(range, 2.0);
+
+    let options = Options {
+        autoframe: true,
+        range: Range::Pixels(64.0),
+        ..Default::default()
+    };
+    assert!(frame(&options, &bounds).is_err());
+}
+
+#[test]
+fn can_extract_svg_paths() {
+    let svg = r#"<?xml version="1.0"?>
+<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+  <!-- a comment -->
+  <path id="square" d="M0 0 H10 V10 H0 Z"/>
+  <g fill="black">
+    <path fill-rule='nonzero' d='M2 2 h2 v2 h-2 z'></path>
+  </g>
+  <path fill="none"/>
+</svg>"#;
+
+    assert_eq!(
+        svg_paths(svg).unwrap(),
+        vec!["M0 0 H10 V10 H0 Z", "M2 2 h2 v2 h-2 z"]
+    );
+    assert!(svg_paths("<svg></svg>").unwrap().is_empty());
+}
+
+#[test]
+fn rejects_unsupported_svg() {
+    let transformed = r#"<svg><path transform="scale(2)" d="M0 0 H10 V10 Z"/></svg>"#;
+    assert!(svg_paths(transformed).is_err());
+
+    let grouped = r#"<svg><g transform="translate(5 5)"><path d="M0 0 H10 V10 Z"/></g></svg>"#;
+    assert!(svg_paths(grouped).is_err());
+
+    let defined = r##"<svg>
+  <defs><path id="p" d="M0 0 H10 V10 Z"/></defs>
+  <use href="#p"/>
+</svg>"##;
+    assert!(svg_paths(defined).is_err());
+}
+
+#[test]
+fn can_parse_error_correction() {
+    let parse = |value| parse_error_correction(value).unwrap();
+
+    assert!(matches!(
+        parse("auto-fast"),
+        (
+            ErrorCorrectionMode::EdgePriority,
+            DistanceCheckMode::DoNotCheckDistance
+        )
+    ));
+    assert!(matches!(
+        parse("distance-full"),
+        (
+            ErrorCorrectionMode::Indiscriminate,
+            DistanceCheckMode::AlwaysCheckDistance
+        )
+    ));
+    assert!(matches!(
+        parse("edge"),
+        (
+            ErrorCorrectionMode::EdgeOnly,
+            DistanceCheckMode::CheckDistanceAtEdge
+        )
+    ));
+    assert!(matches!(parse("disabled").0, ErrorCorrectionMode::Disabled));
+
+    assert!(parse_error_correction("auto-slow").is_err());
+    assert!(parse_error_correction("fast").is_err());
+}
+
+#[test]
+fn can_guess_orientation() {
+    // clockwise contours are filled, so their outside is already negative
+    let clockwise = Shape::from_description("{ 0, 0; 0, 10; 10, 10; 10, 0; # }").unwrap();
+    assert!(!is_inside_out(&clockwise, &clockwise.bounds().unwrap()));
+
+    let mut counter_clockwise =
+        Shape::from_description("{ 0, 0; 10, 0; 10, 10; 0, 10; # }").unwrap();
+    let bounds = counter_clockwise.bounds().unwrap();
+    assert!(is_inside_out(&counter_clockwise, &bounds));
+
+    counter_clockwise.reverse();
+    assert!(!is_inside_out(&counter_clockwise, &bounds));
+}
@@ -0,0 +1,33 @@
+//! Command-line interface to msdf-rs, modelled after msdfgen's standalone tool.
+
+mod args;
+mod atlas;
+mod glyph;
+
+use args::Args;
+use std::env;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        None | Some("-help" | "--help" | "-h") => {
+            print!("{}", glyph::USAGE);
+            Ok(())
+        }
+        Some("atlas") => match args.get(1).map(String::as_str) {
+            None | Some("-help" | "--help" | "-h") => {
+                print!("{}", atlas::USAGE);
+                Ok(())
+            }
+            _ => atlas::run(Args::new(args[1..].to_vec())),
+        },
+        _ => glyph::run(Args::new(args)),
+    };
+
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
@@ -40,6 +40,17 @@ fn main() {
     cmake_builder.profile("Release");
 
     println!("cargo:rerun-if-changed=wrapper.h");
+    println!("cargo:rerun-if-changed=shim.h");
+    println!("cargo:rerun-if-changed=shim.cpp");
+
+    // the shim links against msdfgen-core, so it has to come first in the link order
+    cc::Build::new()
+        .cpp(true)
+        .flag_if_supported("-std=c++11")
+        .include(&msdfgen_dir)
+        .file("shim.cpp")
+        .compile("msdf-shim");
+
     println!("cargo:rustc-link-lib=static=msdfgen-core");
 
     let dst = cmake_builder.build();
@@ -61,6 +72,7 @@ fn main() {
         .opaque_type("std::.*")
         .allowlist_type("msdfgen::.*")
         .allowlist_function("msdfgen::.*")
+        .allowlist_function("msdfsys::.*")
         .header("wrapper.h")
         .parse_callbacks(Box::new(bindgen::CargoCallbacks))
         .generate()
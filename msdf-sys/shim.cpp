@@ -0,0 +1,73 @@
+#include "shim.h"
+
+namespace msdfsys {
+
+void shapeCopy(msdfgen::Shape *target, const msdfgen::Shape &shape) {
+    *target = shape;
+}
+
+int shapeContourCount(const msdfgen::Shape &shape) {
+    return (int) shape.contours.size();
+}
+
+msdfgen::Contour *shapeContour(msdfgen::Shape *shape, int index) {
+    return &shape->contours[index];
+}
+
+int contourEdgeCount(const msdfgen::Contour &contour) {
+    return (int) contour.edges.size();
+}
+
+msdfgen::EdgeSegment *contourEdge(msdfgen::Contour *contour, int index) {
+    return contour->edges[index];
+}
+
+int edgePointCount(const msdfgen::EdgeSegment &edge) {
+    if (dynamic_cast<const msdfgen::LinearSegment *>(&edge))
+        return 2;
+    if (dynamic_cast<const msdfgen::QuadraticSegment *>(&edge))
+        return 3;
+    if (dynamic_cast<const msdfgen::CubicSegment *>(&edge))
+        return 4;
+    return 0;
+}
+
+msdfgen::Point2 *edgePoints(msdfgen::EdgeSegment *edge) {
+    if (msdfgen::LinearSegment *linear = dynamic_cast<msdfgen::LinearSegment *>(edge))
+        return linear->p;
+    if (msdfgen::QuadraticSegment *quadratic = dynamic_cast<msdfgen::QuadraticSegment *>(edge))
+        return quadratic->p;
+    if (msdfgen::CubicSegment *cubic = dynamic_cast<msdfgen::CubicSegment *>(edge))
+        return cubic->p;
+    return nullptr;
+}
+
+int shapeWinding(const msdfgen::Shape &shape, double x, double y) {
+    msdfgen::Scanline scanline;
+    shape.scanline(scanline, y);
+    return scanline.sumIntersections(x);
+}
+
+bool shapeNearestEdge(const msdfgen::Shape &shape, double x, double y, int *contour, int *edge, double *param, double *distance) {
+    msdfgen::Point2 origin(x, y);
+    msdfgen::SignedDistance nearest;
+    bool found = false;
+    for (int i = 0; i < (int) shape.contours.size(); ++i) {
+        const msdfgen::Contour &current = shape.contours[i];
+        for (int j = 0; j < (int) current.edges.size(); ++j) {
+            double t;
+            msdfgen::SignedDistance edgeDistance = current.edges[j]->signedDistance(origin, t);
+            if (edgeDistance < nearest) {
+                nearest = edgeDistance;
+                *contour = i;
+                *edge = j;
+                *param = t;
+                found = true;
+            }
+        }
+    }
+    *distance = nearest.distance;
+    return found;
+}
+
+}
@@ -0,0 +1,32 @@
+#pragma once
+
+#include "msdfgen.h"
+
+/**
+ * Small helpers for reaching into msdfgen's shape containers, which bindgen can only expose as
+ * opaque std::vector blobs.
+ */
+namespace msdfsys {
+
+/// Replaces a shape with a copy of another, including the colors of its edges.
+void shapeCopy(msdfgen::Shape *target, const msdfgen::Shape &shape);
+
+int shapeContourCount(const msdfgen::Shape &shape);
+msdfgen::Contour *shapeContour(msdfgen::Shape *shape, int index);
+
+int contourEdgeCount(const msdfgen::Contour &contour);
+msdfgen::EdgeSegment *contourEdge(msdfgen::Contour *contour, int index);
+
+/// Returns the number of control points of the edge: 2 for linear, 3 for quadratic and 4 for cubic segments.
+int edgePointCount(const msdfgen::EdgeSegment &edge);
+msdfgen::Point2 *edgePoints(msdfgen::EdgeSegment *edge);
+
+/// Returns the winding number of the shape's contours around a point.
+int shapeWinding(const msdfgen::Shape &shape, double x, double y);
+
+/// Finds the edge nearest to a point like msdfgen's true distance finders, and stores its position, the parameter of
+/// the nearest point along it and the signed distance to it, whose sign follows the orientation of the edge like in
+/// generated fields. Returns false if the shape has no edges.
+bool shapeNearestEdge(const msdfgen::Shape &shape, double x, double y, int *contour, int *edge, double *param, double *distance);
+
+}
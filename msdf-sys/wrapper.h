@@ -1,4 +1,5 @@
 #include "msdfgen.h"
+#include "shim.h"
 
 /**
  * <div rustbindgen replaces="msdfgen::Bitmap"></div>
@@ -0,0 +1,101 @@
+use crate::{ColoredShape, DistanceRange, GrayFImage, Projection, SDFTrait, Shape};
+use image::{GenericImageView, Pixel};
+use mint::Vector2;
+
+#[derive(Clone, Debug)]
+/// Measured accuracy of a distance field, compared to the exact distance of the shape it was
+/// generated from. All distances are in shape units and limited to the range of the field, since
+/// distances beyond it are not meaningful once the field is quantized.
+pub struct QualityReport {
+    /// Signed error of every pixel, the decoded distance minus the exact distance.
+    pub error: GrayFImage,
+    /// Mean absolute error over all pixels.
+    pub mean_error: f64,
+    /// Largest absolute error of any pixel.
+    pub max_deviation: f64,
+    /// Number of pixels that disagree with the shape about being inside or outside of it. These
+    /// show up as artifacts when the field is rendered.
+    pub sign_flips: u32,
+}
+
+impl Shape {
+    /// Compares a distance field generated from this shape against the exact distance at the
+    /// center of every pixel, as given by [Shape::signed_distance]. `projection` must be the one
+    /// the field was generated with. The distance of a multi-channel field is decoded as the
+    /// median of its color channels, like a renderer would.
+    pub fn measure_quality<T>(&self, field: &T, projection: &Projection) -> QualityReport
+    where
+        T: SDFTrait,
+        <T::Image as GenericImageView>::Pixel: Pixel<Subpixel = f32>,
+    {
+        let (width, height) = field.image().dimensions();
+        let to_shape_units =
+            field.range_units().pixels_per_unit() / projection.scale.x.min(projection.scale.y);
+        let range = field.distance_range();
+        let range = DistanceRange {
+            lower: range.lower * to_shape_units,
+            upper: range.upper * to_shape_units,
+        };
+
+        let mut error = GrayFImage::new(width, height);
+        let mut total = 0.0;
+        let mut max_deviation = 0.0f64;
+        let mut sign_flips = 0;
+
+        for (x, y, pixel) in field.image().pixels() {
+            // the first row of the image is the top of a y-up shape
+            let row = if self.shape.inverseYAxis {
+                height - 1 - y
+            } else {
+                y
+            };
+            let center = Vector2 {
+                x: (x as f64 + 0.5) / projection.scale.x - projection.translation.x,
+                y: (row as f64 + 0.5) / projection.scale.y - projection.translation.y,
+            };
+
+            let decoded = decode(median(pixel.channels()), &range);
+            let expected = self.signed_distance(center).clamp(range.lower, range.upper);
+            let difference = decoded - expected;
+
+            error.put_pixel(x, y, [difference as f32].into());
+            total += difference.abs();
+            max_deviation = max_deviation.max(difference.abs());
+            if (decoded > 0.0) != (expected > 0.0) {
+                sign_flips += 1;
+            }
+        }
+
+        QualityReport {
+            error,
+            mean_error: total / (width as f64 * height as f64).max(1.0),
+            max_deviation,
+            sign_flips,
+        }
+    }
+}
+
+impl ColoredShape {
+    /// See [Shape::measure_quality].
+    pub fn measure_quality<T>(&self, field: &T, projection: &Projection) -> QualityReport
+    where
+        T: SDFTrait,
+        <T::Image as GenericImageView>::Pixel: Pixel<Subpixel = f32>,
+    {
+        self.0.measure_quality(field, projection)
+    }
+}
+
+/// Converts a stored value to a signed distance in shape units, positive inside the shape.
+fn decode(value: f32, range: &DistanceRange) -> f64 {
+    ((value - range.mid_value()) as f64 * range.width()).clamp(range.lower, range.upper)
+}
+
+/// Median of the first three channels, or the only channel of a single-channel field.
+fn median(channels: &[f32]) -> f32 {
+    match channels {
+        [r, g, b, ..] => r.min(*g).max(r.max(*g).min(*b)),
+        [value, ..] => *value,
+        [] => 0.0,
+    }
+}
@@ -0,0 +1,723 @@
+use crate::{
+    Atlas, AtlasField, AtlasGlyph, AtlasRect, AtlasType, Bounds, FieldFileError, FontMetrics,
+    GrayFImage, KerningPair, SDFTrait, MSDF, MTSDF, SDF,
+};
+use image::{ImageBuffer, Pixel, Rgb32FImage, Rgba32FImage};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use ttf_parser::GlyphId;
+
+/// Tag at the start of every Artery Font file.
+const TAG: &[u8; 16] = b"ARTERY/FONT\0\0\0\0\0";
+const HEADER_MAGIC: u32 = 0x4d276a5c;
+const FOOTER_MAGIC: u32 = 0x55ccb363;
+const VERSION: u32 = 1;
+/// Reals are stored as 32-bit floats.
+const REAL_TYPE_FLOAT32: u32 = 0x14;
+const METADATA_PLAINTEXT: u32 = 1;
+
+const CODEPOINT_UNICODE: u32 = 1;
+const CODEPOINT_INDEXED: u32 = 2;
+
+const ENCODING_RAW_BINARY: u32 = 1;
+const ENCODING_PNG: u32 = 8;
+const PIXEL_FORMAT_UNSIGNED8: u32 = 8;
+const PIXEL_FORMAT_FLOAT32: u32 = 32;
+const ORIENTATION_TOP_DOWN: i32 = 1;
+const ORIENTATION_BOTTOM_UP: i32 = -1;
+
+const HEADER_SIZE: usize = 112;
+const FOOTER_SIZE: usize = 32;
+const VARIANT_HEADER_SIZE: usize = 192;
+const IMAGE_HEADER_SIZE: usize = 64;
+const GLYPH_SIZE: usize = 48;
+const KERN_PAIR_SIZE: usize = 16;
+
+/// Variant metadata key listing the glyph index of every glyph, for variants indexed by
+/// codepoint.
+const GLYPH_IDS_KEY: &str = "msdf:glyph_ids";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Encoding of the atlas image in an Artery Font file.
+pub enum ArteryEncoding {
+    /// 8-bit PNG, which most readers support.
+    Png,
+    /// Uncompressed 32-bit floats, keeping the exact distances.
+    RawFloat,
+}
+
+impl Default for ArteryEncoding {
+    fn default() -> Self {
+        ArteryEncoding::Png
+    }
+}
+
+impl AtlasType {
+    fn artery_image_type(self) -> u32 {
+        match self {
+            AtlasType::SDF => 4,
+            AtlasType::PsuedoSDF => 5,
+            AtlasType::MSDF => 6,
+            AtlasType::MTSDF => 7,
+        }
+    }
+
+    fn from_artery_image_type(image_type: u32) -> Option<Self> {
+        [
+            AtlasType::SDF,
+            AtlasType::PsuedoSDF,
+            AtlasType::MSDF,
+            AtlasType::MTSDF,
+        ]
+        .into_iter()
+        .find(|t| t.artery_image_type() == image_type)
+    }
+
+    fn channels(self) -> u32 {
+        match self {
+            AtlasType::SDF | AtlasType::PsuedoSDF => 1,
+            AtlasType::MSDF => 3,
+            AtlasType::MTSDF => 4,
+        }
+    }
+}
+
+impl Atlas {
+    /// Writes the atlas as an [Artery Font](https://github.com/Chlumsky/artery-font-format) file
+    /// with a single variant and image. Glyphs are identified by codepoint if all of them have
+    /// one, and by glyph index otherwise. Image bounds are in pixels with y pointing up, like
+    /// msdf-atlas-gen writes them. Color layers aren't stored.
+    pub fn write_arfont<W: Write>(
+        &self,
+        mut writer: W,
+        encoding: ArteryEncoding,
+    ) -> Result<(), FieldFileError> {
+        let by_codepoint = self.glyphs.iter().all(|glyph| glyph.codepoint.is_some());
+        let (_, height) = self.field.dimensions();
+        let variant = self.encode_variant(by_codepoint, height);
+        let image = encode_image(&self.field, encoding)?;
+
+        let mut data = Vec::with_capacity(HEADER_SIZE + variant.len() + image.len() + FOOTER_SIZE);
+        data.extend_from_slice(TAG);
+        for value in [HEADER_MAGIC, VERSION, 0, REAL_TYPE_FLOAT32, 0, 0, 0, 0] {
+            push_u32(&mut data, value);
+        }
+        for value in [
+            METADATA_PLAINTEXT,
+            0, // no font metadata
+            1,
+            variant.len() as u32,
+            1,
+            image.len() as u32,
+            0, // no appendices
+            0,
+        ] {
+            push_u32(&mut data, value);
+        }
+        data.extend_from_slice(&[0; 32]);
+        data.extend_from_slice(&variant);
+        data.extend_from_slice(&image);
+
+        for value in [0, FOOTER_MAGIC, 0, 0, 0, 0] {
+            push_u32(&mut data, value);
+        }
+        let total_length = data.len() + 8;
+        push_u32(&mut data, total_length as u32);
+        let checksum = crc32fast::hash(&data);
+        push_u32(&mut data, checksum);
+
+        writer.write_all(&data)?;
+        Ok(())
+    }
+
+    fn encode_variant(&self, by_codepoint: bool, height: u32) -> Vec<u8> {
+        let id = |glyph: &AtlasGlyph| match glyph.codepoint {
+            Some(c) if by_codepoint => c as u32,
+            _ => glyph.glyph_id.0 as u32,
+        };
+        // kerning pairs refer to glyph indices, which several characters may share
+        let mut ids = HashMap::new();
+        for glyph in self.glyphs.iter().rev() {
+            ids.insert(glyph.glyph_id, id(glyph));
+        }
+        let kerning: Vec<(u32, u32, f64)> = self
+            .kerning
+            .iter()
+            .filter_map(|pair| Some((*ids.get(&pair.left)?, *ids.get(&pair.right)?, pair.advance)))
+            .collect();
+
+        let metadata = if by_codepoint {
+            let ids: Vec<String> = self
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.glyph_id.0.to_string())
+                .collect();
+            format!("{}={}", GLYPH_IDS_KEY, ids.join(" "))
+        } else {
+            String::new()
+        };
+        let codepoint_type = if by_codepoint {
+            CODEPOINT_UNICODE
+        } else {
+            CODEPOINT_INDEXED
+        };
+
+        let mut data = vec![];
+        for value in [
+            0, // flags
+            0, // weight
+            codepoint_type,
+            self.field.atlas_type().artery_image_type(),
+            0, // fallback variant
+            0, // fallback glyph
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ] {
+            push_u32(&mut data, value);
+        }
+
+        let metrics = &self.metrics;
+        let mut reals = [0.0; 32];
+        reals[..8].copy_from_slice(&[
+            self.em_size,
+            self.field.pixel_range(),
+            1.0, // metrics are in ems
+            metrics.ascender,
+            metrics.descender,
+            metrics.line_height,
+            metrics.underline_y,
+            metrics.underline_thickness,
+        ]);
+        for real in reals {
+            push_f32(&mut data, real);
+        }
+
+        for value in [
+            0, // no name
+            metadata.len(),
+            self.glyphs.len(),
+            kerning.len(),
+        ] {
+            push_u32(&mut data, value as u32);
+        }
+        push_string(&mut data, &metadata);
+
+        for glyph in &self.glyphs {
+            push_u32(&mut data, id(glyph));
+            push_u32(&mut data, 0); // image
+            let plane = glyph.plane_bounds.unwrap_or(Bounds {
+                left: 0.0,
+                bottom: 0.0,
+                right: 0.0,
+                top: 0.0,
+            });
+            let image = glyph.atlas_rect.map_or([0.0; 4], |rect| {
+                [
+                    rect.x as f64,
+                    (height - rect.y - rect.height) as f64,
+                    (rect.x + rect.width) as f64,
+                    (height - rect.y) as f64,
+                ]
+            });
+            for real in [plane.left, plane.bottom, plane.right, plane.top] {
+                push_f32(&mut data, real);
+            }
+            for real in image {
+                push_f32(&mut data, real);
+            }
+            push_f32(&mut data, glyph.advance);
+            push_f32(&mut data, 0.0);
+        }
+
+        for (left, right, advance) in kerning {
+            push_u32(&mut data, left);
+            push_u32(&mut data, right);
+            push_f32(&mut data, advance);
+            push_f32(&mut data, 0.0);
+        }
+
+        data
+    }
+
+    /// Reads the first variant and image of an Artery Font file, such as one written by
+    /// [Atlas::write_arfont] or msdf-atlas-gen. The checksum is verified. Bounds inset by half a
+    /// pixel, as msdf-atlas-gen writes them, are widened to whole pixels. Files without the glyph
+    /// indices written by this crate leave [AtlasGlyph::glyph_id] of glyphs identified by
+    /// codepoint at 0, and kerning pairs refering to glyphs missing from the variant are dropped.
+    pub fn read_arfont<R: Read>(mut reader: R) -> Result<Atlas, FieldFileError> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        if data.len() < HEADER_SIZE + FOOTER_SIZE || &data[..16] != TAG {
+            return Err(FieldFileError::Malformed("missing Artery Font tag"));
+        }
+        let footer = data.len() - FOOTER_SIZE;
+        if read_u32(&data, 16)? != HEADER_MAGIC || read_u32(&data, footer + 4)? != FOOTER_MAGIC {
+            return Err(FieldFileError::Malformed("wrong Artery Font magic number"));
+        }
+        if read_u32(&data, footer + 24)? as usize != data.len() {
+            return Err(FieldFileError::Malformed(
+                "length does not match the footer",
+            ));
+        }
+        if read_u32(&data, footer + 28)? != crc32fast::hash(&data[..footer + 28]) {
+            return Err(FieldFileError::Malformed("checksum mismatch"));
+        }
+        if read_u32(&data, 28)? != REAL_TYPE_FLOAT32 {
+            return Err(FieldFileError::Malformed("only 32-bit reals are supported"));
+        }
+
+        let metadata_length = read_u32(&data, 52)? as usize;
+        let variant_count = read_u32(&data, 56)?;
+        let variants_length = read_u32(&data, 60)? as usize;
+        let image_count = read_u32(&data, 64)?;
+        if variant_count == 0 || image_count == 0 {
+            return Err(FieldFileError::Malformed("missing variant or image"));
+        }
+
+        let variant_offset = HEADER_SIZE + string_size(metadata_length);
+        let variant = read_variant(&data, variant_offset)?;
+        let image = read_image(&data, variant_offset + variants_length)?;
+
+        let (width, height) = image.dimensions();
+        let glyph_ids = variant.glyph_ids();
+        let mut glyphs = vec![];
+        for (i, record) in variant.glyphs.iter().enumerate() {
+            let (codepoint, glyph_id) = if variant.codepoint_type == CODEPOINT_UNICODE {
+                let codepoint = char::from_u32(record.id)
+                    .ok_or(FieldFileError::Malformed("invalid codepoint"))?;
+                let glyph_id = glyph_ids
+                    .as_ref()
+                    .and_then(|ids| ids.get(i).copied())
+                    .unwrap_or_default();
+                (Some(codepoint), glyph_id)
+            } else {
+                (None, GlyphId(record.id as u16))
+            };
+
+            let [left, bottom, right, top] = record.image;
+            let [plane_left, plane_bottom, plane_right, plane_top] = record.plane;
+            let (atlas_rect, plane_bounds) = if right > left && top > bottom {
+                // msdf-atlas-gen insets both bounds by half a pixel, to the centers of the outer
+                // pixels of the glyph, where this crate uses the edges of the pixels
+                let inset = if (left.fract() - 0.5).abs() < 1e-3 {
+                    0.5
+                } else {
+                    0.0
+                };
+                let x_inset = inset * (plane_right - plane_left) / (right - left);
+                let y_inset = inset * (plane_top - plane_bottom) / (top - bottom);
+                let (left, bottom, right, top) = (
+                    (left - inset).round() as u32,
+                    (bottom - inset).round() as u32,
+                    (right + inset).round() as u32,
+                    (top + inset).round() as u32,
+                );
+                let outside = FieldFileError::Malformed("glyph outside of the image");
+                if right > width {
+                    return Err(outside);
+                }
+                let rect = AtlasRect {
+                    x: left,
+                    y: height.checked_sub(top).ok_or(outside)?,
+                    width: right.saturating_sub(left),
+                    height: top.saturating_sub(bottom),
+                };
+                let bounds = Bounds {
+                    left: plane_left - x_inset,
+                    bottom: plane_bottom - y_inset,
+                    right: plane_right + x_inset,
+                    top: plane_top + y_inset,
+                };
+                (Some(rect), Some(bounds))
+            } else {
+                (None, None)
+            };
+
+            glyphs.push(AtlasGlyph {
+                codepoint,
+                glyph_id,
+                advance: record.advance,
+                plane_bounds,
+                atlas_rect,
+            });
+        }
+
+        let glyph_id = |id: u32| {
+            variant
+                .glyphs
+                .iter()
+                .position(|record| record.id == id)
+                .map(|i| glyphs[i].glyph_id)
+        };
+        let kerning = variant
+            .kern_pairs
+            .iter()
+            .filter_map(|&(left, right, advance)| {
+                Some(KerningPair {
+                    left: glyph_id(left)?,
+                    right: glyph_id(right)?,
+                    advance,
+                })
+            })
+            .collect();
+
+        let range = variant.metrics[1];
+        let field = match image {
+            ArteryImage::Gray(image, AtlasType::PsuedoSDF) => {
+                AtlasField::PsuedoSDF(SDF::from_image(image, range, 0.5))
+            }
+            ArteryImage::Gray(image, _) => AtlasField::SDF(SDF::from_image(image, range, 0.5)),
+            ArteryImage::Rgb(image) => AtlasField::MSDF(MSDF::from_image(image, range, 0.5)),
+            ArteryImage::Rgba(image) => AtlasField::MTSDF(MTSDF::from_image(image, range, 0.5)),
+        };
+
+        let [em_size, _, em_units, ascender, descender, line_height, underline_y, underline_thickness] =
+            variant.metrics;
+        // metrics are stored relative to the em size, which is usually 1
+        let em_units = if em_units > 0.0 { em_units } else { 1.0 };
+        let glyphs = glyphs
+            .into_iter()
+            .map(|glyph| scale_glyph(glyph, 1.0 / em_units))
+            .collect();
+        let kerning = scale_kerning(kerning, 1.0 / em_units);
+
+        Ok(Atlas {
+            field,
+            glyphs,
+            kerning,
+            layers: vec![],
+            metrics: FontMetrics {
+                ascender: ascender / em_units,
+                descender: descender / em_units,
+                line_height: line_height / em_units,
+                underline_y: underline_y / em_units,
+                underline_thickness: underline_thickness / em_units,
+            },
+            em_size,
+        })
+    }
+}
+
+struct GlyphRecord {
+    id: u32,
+    plane: [f64; 4],
+    image: [f64; 4],
+    advance: f64,
+}
+
+struct Variant {
+    codepoint_type: u32,
+    /// The named metrics, in order: font size, distance range, em size, ascender, descender,
+    /// line height, underline position and underline thickness.
+    metrics: [f64; 8],
+    metadata: String,
+    glyphs: Vec<GlyphRecord>,
+    kern_pairs: Vec<(u32, u32, f64)>,
+}
+
+impl Variant {
+    fn glyph_ids(&self) -> Option<Vec<GlyphId>> {
+        let line = self
+            .metadata
+            .lines()
+            .find_map(|line| line.strip_prefix(GLYPH_IDS_KEY)?.strip_prefix('='))?;
+        let ids: Option<Vec<GlyphId>> = line
+            .split_whitespace()
+            .map(|id| id.parse().ok().map(GlyphId))
+            .collect();
+        ids.filter(|ids| ids.len() == self.glyphs.len())
+    }
+}
+
+enum ArteryImage {
+    Gray(GrayFImage, AtlasType),
+    Rgb(Rgb32FImage),
+    Rgba(Rgba32FImage),
+}
+
+impl ArteryImage {
+    fn dimensions(&self) -> (u32, u32) {
+        match self {
+            ArteryImage::Gray(image, _) => image.dimensions(),
+            ArteryImage::Rgb(image) => image.dimensions(),
+            ArteryImage::Rgba(image) => image.dimensions(),
+        }
+    }
+}
+
+fn read_variant(data: &[u8], offset: usize) -> Result<Variant, FieldFileError> {
+    let codepoint_type = read_u32(data, offset + 8)?;
+    let mut metrics = [0.0; 8];
+    for (i, metric) in metrics.iter_mut().enumerate() {
+        *metric = read_f32(data, offset + 48 + 4 * i)?;
+    }
+
+    let counts = offset + VARIANT_HEADER_SIZE - 16;
+    let name_length = read_u32(data, counts)? as usize;
+    let metadata_length = read_u32(data, counts + 4)? as usize;
+    let glyph_count = read_u32(data, counts + 8)? as usize;
+    let kern_pair_count = read_u32(data, counts + 12)? as usize;
+
+    let metadata_offset = offset + VARIANT_HEADER_SIZE + string_size(name_length);
+    let metadata = slice(data, metadata_offset, metadata_length)?;
+    let metadata = String::from_utf8_lossy(metadata).into_owned();
+
+    let mut position = metadata_offset + string_size(metadata_length);
+    let mut glyphs = Vec::with_capacity(glyph_count.min(data.len() / GLYPH_SIZE));
+    for _ in 0..glyph_count {
+        let mut reals = [0.0; 10];
+        for (i, real) in reals.iter_mut().enumerate() {
+            *real = read_f32(data, position + 8 + 4 * i)?;
+        }
+        glyphs.push(GlyphRecord {
+            id: read_u32(data, position)?,
+            plane: [reals[0], reals[1], reals[2], reals[3]],
+            image: [reals[4], reals[5], reals[6], reals[7]],
+            advance: reals[8],
+        });
+        position += GLYPH_SIZE;
+    }
+
+    let mut kern_pairs = Vec::with_capacity(kern_pair_count.min(data.len() / KERN_PAIR_SIZE));
+    for _ in 0..kern_pair_count {
+        kern_pairs.push((
+            read_u32(data, position)?,
+            read_u32(data, position + 4)?,
+            read_f32(data, position + 8)?,
+        ));
+        position += KERN_PAIR_SIZE;
+    }
+
+    Ok(Variant {
+        codepoint_type,
+        metrics,
+        metadata,
+        glyphs,
+        kern_pairs,
+    })
+}
+
+fn encode_image(field: &AtlasField, encoding: ArteryEncoding) -> Result<Vec<u8>, FieldFileError> {
+    let atlas_type = field.atlas_type();
+    let (width, height) = field.dimensions();
+    let channels = atlas_type.channels();
+    let samples = match field {
+        AtlasField::SDF(field) | AtlasField::PsuedoSDF(field) => field.image().as_raw(),
+        AtlasField::MSDF(field) => field.image().as_raw(),
+        AtlasField::MTSDF(field) => field.image().as_raw(),
+    };
+
+    let row = (width * channels) as usize;
+    let (pixel_format, orientation, bytes) = match encoding {
+        ArteryEncoding::Png => {
+            let color = match channels {
+                1 => png::ColorType::Grayscale,
+                3 => png::ColorType::Rgb,
+                _ => png::ColorType::Rgba,
+            };
+            let quantized: Vec<u8> = samples
+                .iter()
+                .map(|sample| (sample.clamp(0.0, 1.0) * 255.0).round() as u8)
+                .collect();
+
+            let mut bytes = vec![];
+            let mut encoder = png::Encoder::new(&mut bytes, width, height);
+            encoder.set_color(color);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&quantized)?;
+            writer.finish()?;
+            (PIXEL_FORMAT_UNSIGNED8, ORIENTATION_TOP_DOWN, bytes)
+        }
+        ArteryEncoding::RawFloat => {
+            let mut bytes = Vec::with_capacity(samples.len() * 4);
+            // rows are stored bottom to top, matching the image bounds of glyphs
+            for row in samples.chunks(row.max(1)).rev() {
+                for sample in row {
+                    bytes.extend_from_slice(&sample.to_le_bytes());
+                }
+            }
+            (PIXEL_FORMAT_FLOAT32, ORIENTATION_BOTTOM_UP, bytes)
+        }
+    };
+    let encoding = match encoding {
+        ArteryEncoding::Png => ENCODING_PNG,
+        ArteryEncoding::RawFloat => ENCODING_RAW_BINARY,
+    };
+
+    let mut data = Vec::with_capacity(IMAGE_HEADER_SIZE + padded(bytes.len()));
+    for value in [
+        0, // flags
+        encoding,
+        width,
+        height,
+        channels,
+        pixel_format,
+        atlas_type.artery_image_type(),
+        row as u32 * pixel_format / 8,
+        orientation as u32,
+        0, // child images
+        0, // texture flags
+        0,
+        0,
+        0,
+        0, // no metadata
+        bytes.len() as u32,
+    ] {
+        push_u32(&mut data, value);
+    }
+    data.extend_from_slice(&bytes);
+    data.resize(padded(data.len()), 0);
+    Ok(data)
+}
+
+fn read_image(data: &[u8], offset: usize) -> Result<ArteryImage, FieldFileError> {
+    let encoding = read_u32(data, offset + 4)?;
+    let width = read_u32(data, offset + 8)?;
+    let height = read_u32(data, offset + 12)?;
+    let channels = read_u32(data, offset + 16)?;
+    let pixel_format = read_u32(data, offset + 20)?;
+    let image_type = read_u32(data, offset + 24)?;
+    let orientation = read_u32(data, offset + 32)? as i32;
+    let metadata_length = read_u32(data, offset + 56)? as usize;
+    let data_length = read_u32(data, offset + 60)? as usize;
+    let bytes = slice(
+        data,
+        offset + IMAGE_HEADER_SIZE + string_size(metadata_length),
+        data_length,
+    )?;
+
+    let atlas_type = AtlasType::from_artery_image_type(image_type)
+        .ok_or(FieldFileError::UnsupportedPixelFormat)?;
+    if channels != atlas_type.channels() {
+        return Err(FieldFileError::ChannelMismatch {
+            expected: atlas_type.channels() as u8,
+            found: channels as u8,
+        });
+    }
+
+    let row = width as usize * channels as usize;
+    let mut samples: Vec<f32> = match (encoding, pixel_format) {
+        (ENCODING_RAW_BINARY, PIXEL_FORMAT_UNSIGNED8) => {
+            bytes.iter().map(|&v| v as f32 / 255.0).collect()
+        }
+        (ENCODING_RAW_BINARY, PIXEL_FORMAT_FLOAT32) => bytes
+            .chunks_exact(4)
+            .map(|v| f32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+            .collect(),
+        (ENCODING_PNG, _) => {
+            let mut decoder = png::Decoder::new(bytes);
+            decoder.set_transformations(png::Transformations::EXPAND);
+            let mut reader = decoder.read_info()?;
+            let mut buffer = vec![0; reader.output_buffer_size()];
+            let frame = reader.next_frame(&mut buffer)?;
+            if frame.bit_depth != png::BitDepth::Eight
+                || frame.width != width
+                || frame.height != height
+                || frame.color_type.samples() != channels as usize
+            {
+                return Err(FieldFileError::UnsupportedPixelFormat);
+            }
+            buffer.truncate(frame.buffer_size());
+            buffer.iter().map(|&v| v as f32 / 255.0).collect()
+        }
+        _ => return Err(FieldFileError::UnsupportedPixelFormat),
+    };
+    if samples.len() != row * height as usize {
+        return Err(FieldFileError::Malformed(
+            "image size does not match the dimensions",
+        ));
+    }
+    if orientation == ORIENTATION_BOTTOM_UP {
+        samples = samples
+            .chunks(row.max(1))
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+    }
+
+    Ok(match atlas_type {
+        AtlasType::SDF | AtlasType::PsuedoSDF => {
+            ArteryImage::Gray(from_samples(width, height, samples), atlas_type)
+        }
+        AtlasType::MSDF => ArteryImage::Rgb(from_samples(width, height, samples)),
+        AtlasType::MTSDF => ArteryImage::Rgba(from_samples(width, height, samples)),
+    })
+}
+
+fn from_samples<P>(width: u32, height: u32, samples: Vec<f32>) -> ImageBuffer<P, Vec<f32>>
+where
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    // the sample count always matches the dimensions
+    ImageBuffer::from_raw(width, height, samples).unwrap()
+}
+
+fn scale_glyph(mut glyph: AtlasGlyph, scale: f64) -> AtlasGlyph {
+    glyph.advance *= scale;
+    if let Some(bounds) = &mut glyph.plane_bounds {
+        bounds.left *= scale;
+        bounds.bottom *= scale;
+        bounds.right *= scale;
+        bounds.top *= scale;
+    }
+    glyph
+}
+
+fn scale_kerning(mut kerning: Vec<KerningPair>, scale: f64) -> Vec<KerningPair> {
+    for pair in &mut kerning {
+        pair.advance *= scale;
+    }
+    kerning
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_f32(data: &mut Vec<u8>, value: f64) {
+    data.extend_from_slice(&(value as f32).to_le_bytes());
+}
+
+/// Writes a null-terminated string padded to 4 bytes. Empty strings are omitted entirely.
+fn push_string(data: &mut Vec<u8>, string: &str) {
+    let start = data.len();
+    data.extend_from_slice(string.as_bytes());
+    data.resize(start + string_size(string.len()), 0);
+}
+
+/// Size of a string of `length` bytes once written with [push_string].
+fn string_size(length: usize) -> usize {
+    if length == 0 {
+        0
+    } else {
+        padded(length + 1)
+    }
+}
+
+/// Rounds `length` up to a multiple of 4.
+fn padded(length: usize) -> usize {
+    (length + 3) & !3
+}
+
+fn slice(data: &[u8], offset: usize, length: usize) -> Result<&[u8], FieldFileError> {
+    offset
+        .checked_add(length)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(FieldFileError::Malformed("unexpected end of file"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, FieldFileError> {
+    let bytes = slice(data, offset, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_f32(data: &[u8], offset: usize) -> Result<f64, FieldFileError> {
+    Ok(f32::from_bits(read_u32(data, offset)?) as f64)
+}
@@ -0,0 +1,669 @@
+use crate::{
+    Bounds, ColorGlyphLoader, ColoredShape, ColoringStrategy, DistanceRange, EdgeColoringConfig,
+    GlyphLoader, GrayFImage, LayerColor, MSDFConfig, Projection, SDFConfig, SDFTrait, Shape, MSDF,
+    MTSDF, SDF,
+};
+use image::{ImageBuffer, Pixel, Rgb32FImage, Rgba32FImage};
+use mint::Vector2;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use ttf_parser::gpos::{PairAdjustment, PositioningSubtable};
+use ttf_parser::opentype_layout::Lookup;
+use ttf_parser::{kern, Face, GlyphId, Tag};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Type of distance field stored in an [Atlas].
+pub enum AtlasType {
+    SDF,
+    PsuedoSDF,
+    MSDF,
+    MTSDF,
+}
+
+impl Default for AtlasType {
+    fn default() -> Self {
+        AtlasType::MSDF
+    }
+}
+
+#[derive(Copy, Clone)]
+/// Configuration for atlas generation.
+pub struct AtlasConfig {
+    pub atlas_type: AtlasType,
+    /// Size of an em in pixels.
+    pub em_size: f64,
+    /// Distance range in pixels. Every glyph is surrounded by half of it.
+    pub pixel_range: f64,
+    /// Empty pixels between glyphs and around the edge of the atlas.
+    pub padding: u32,
+    /// Maximum width of the atlas in pixels. Glyphs that don't fit in a row start a new one, and
+    /// glyphs wider than this widen the atlas.
+    pub max_width: u32,
+    /// Round the width and height of the atlas up to powers of two.
+    pub power_of_two: bool,
+    /// Coloring used for multi-channel atlases.
+    pub coloring_strategy: ColoringStrategy,
+    pub coloring_config: EdgeColoringConfig,
+    /// Configuration of the generator. Error correction only applies to multi-channel atlases.
+    pub msdf_config: MSDFConfig,
+}
+
+impl Default for AtlasConfig {
+    fn default() -> Self {
+        AtlasConfig {
+            atlas_type: Default::default(),
+            em_size: 32.0,
+            pixel_range: 2.0,
+            padding: 0,
+            max_width: 512,
+            power_of_two: false,
+            coloring_strategy: Default::default(),
+            coloring_config: Default::default(),
+            msdf_config: Default::default(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Rectangle of pixels in an atlas, relative to its top left corner.
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Placement of a glyph in an [Atlas].
+pub struct AtlasGlyph {
+    /// Character the glyph was added for, if any.
+    pub codepoint: Option<char>,
+    pub glyph_id: GlyphId,
+    /// Horizontal advance, in ems.
+    pub advance: f64,
+    /// Quad covered by the glyph's pixels relative to its origin, in ems with y pointing up.
+    /// [None] for glyphs without an outline, like spaces.
+    pub plane_bounds: Option<Bounds>,
+    /// Pixels of the glyph in the atlas, matching `plane_bounds`.
+    pub atlas_rect: Option<AtlasRect>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Placement of one layer of a color glyph in an [Atlas].
+pub struct AtlasLayer {
+    /// Index of the glyph in [Atlas::glyphs].
+    pub glyph: usize,
+    pub color: LayerColor,
+    /// Quad covered by the layer's pixels relative to the glyph's origin, like
+    /// [AtlasGlyph::plane_bounds].
+    pub plane_bounds: Option<Bounds>,
+    /// Pixels of the layer in the atlas, matching `plane_bounds`.
+    pub atlas_rect: Option<AtlasRect>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Adjustment of the advance between two glyphs of an [Atlas].
+pub struct KerningPair {
+    pub left: GlyphId,
+    pub right: GlyphId,
+    /// Added to the advance of `left`, in ems. Usually negative.
+    pub advance: f64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+/// Vertical metrics of a font, in ems with y pointing up.
+pub struct FontMetrics {
+    pub ascender: f64,
+    pub descender: f64,
+    pub line_height: f64,
+    pub underline_y: f64,
+    pub underline_thickness: f64,
+}
+
+impl FontMetrics {
+    /// Reads the metrics of a face.
+    pub fn from_face(face: &Face) -> Self {
+        let units_per_em = face.units_per_em() as f64;
+        let underline = face.underline_metrics();
+
+        FontMetrics {
+            ascender: face.ascender() as f64 / units_per_em,
+            descender: face.descender() as f64 / units_per_em,
+            line_height: (face.ascender() - face.descender() + face.line_gap()) as f64
+                / units_per_em,
+            underline_y: underline.map_or(0.0, |m| m.position as f64 / units_per_em),
+            underline_thickness: underline.map_or(0.0, |m| m.thickness as f64 / units_per_em),
+        }
+    }
+}
+
+/// Distance field image of an [Atlas].
+pub enum AtlasField {
+    SDF(SDF),
+    PsuedoSDF(SDF),
+    MSDF(MSDF),
+    MTSDF(MTSDF),
+}
+
+impl AtlasField {
+    /// Type of the field.
+    pub fn atlas_type(&self) -> AtlasType {
+        match self {
+            AtlasField::SDF(_) => AtlasType::SDF,
+            AtlasField::PsuedoSDF(_) => AtlasType::PsuedoSDF,
+            AtlasField::MSDF(_) => AtlasType::MSDF,
+            AtlasField::MTSDF(_) => AtlasType::MTSDF,
+        }
+    }
+
+    /// Width and height of the field in pixels.
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            AtlasField::SDF(field) | AtlasField::PsuedoSDF(field) => field.image().dimensions(),
+            AtlasField::MSDF(field) => field.image().dimensions(),
+            AtlasField::MTSDF(field) => field.image().dimensions(),
+        }
+    }
+
+    /// Distance range in pixels.
+    pub fn pixel_range(&self) -> f64 {
+        match self {
+            AtlasField::SDF(field) | AtlasField::PsuedoSDF(field) => field.pixel_range(),
+            AtlasField::MSDF(field) => field.pixel_range(),
+            AtlasField::MTSDF(field) => field.pixel_range(),
+        }
+    }
+}
+
+/// Glyphs of a font packed into a single distance field, with the metrics needed to lay out
+/// text with them.
+pub struct Atlas {
+    pub field: AtlasField,
+    pub glyphs: Vec<AtlasGlyph>,
+    pub kerning: Vec<KerningPair>,
+    /// Layers of the color glyphs added with [Atlas::from_color_glyphs]. Layers of a glyph are
+    /// consecutive and listed bottom first. Color glyphs have no bounds of their own.
+    pub layers: Vec<AtlasLayer>,
+    pub metrics: FontMetrics,
+    /// Size of an em in pixels.
+    pub em_size: f64,
+}
+
+impl Atlas {
+    /// Generates an atlas containing the glyphs of `chars`, such as a [crate::Charset].
+    /// Characters without a glyph in `face` and repeated characters are skipped.
+    pub fn from_face<I>(face: &Face, chars: I, config: &AtlasConfig) -> Atlas
+    where
+        I: IntoIterator<Item = char>,
+    {
+        let mut glyphs = vec![];
+        let mut seen = HashSet::new();
+        for c in chars {
+            if !seen.insert(c) {
+                continue;
+            }
+            if let Some(glyph_id) = face.glyph_index(c) {
+                glyphs.push((Some(c), glyph_id));
+            }
+        }
+
+        Atlas::from_glyphs(face, glyphs, config)
+    }
+
+    /// Generates an atlas containing the given glyphs, each with the character it stands for if
+    /// any.
+    pub fn from_glyphs<I>(face: &Face, glyphs: I, config: &AtlasConfig) -> Atlas
+    where
+        I: IntoIterator<Item = (Option<char>, GlyphId)>,
+    {
+        Atlas::build(face, glyphs, None, config)
+    }
+
+    /// Like [Atlas::from_glyphs], but glyphs with color layers in the `COLR` table are added as
+    /// their layers, colored with the given `CPAL` palette and listed in [Atlas::layers].
+    pub fn from_color_glyphs<I>(face: &Face, glyphs: I, palette: u16, config: &AtlasConfig) -> Atlas
+    where
+        I: IntoIterator<Item = (Option<char>, GlyphId)>,
+    {
+        Atlas::build(face, glyphs, Some(palette), config)
+    }
+
+    fn build<I>(face: &Face, glyphs: I, palette: Option<u16>, config: &AtlasConfig) -> Atlas
+    where
+        I: IntoIterator<Item = (Option<char>, GlyphId)>,
+    {
+        let units_per_em = face.units_per_em() as f64;
+        let scale = config.em_size / units_per_em;
+
+        let mut atlas_glyphs = vec![];
+        let mut layers = vec![];
+        // every outline to place, with the glyph or layer it belongs to
+        let mut shapes = vec![];
+        for (codepoint, glyph_id) in glyphs {
+            let index = atlas_glyphs.len();
+            let advance = face.glyph_hor_advance(glyph_id).unwrap_or(0);
+            atlas_glyphs.push(AtlasGlyph {
+                codepoint,
+                glyph_id,
+                advance: advance as f64 / units_per_em,
+                plane_bounds: None,
+                atlas_rect: None,
+            });
+
+            match palette.and_then(|palette| face.load_color_glyph(glyph_id, palette)) {
+                Some(color_glyph) => {
+                    for layer in color_glyph.layers {
+                        shapes.push((Placement::Layer(layers.len()), layer.shape));
+                        layers.push(AtlasLayer {
+                            glyph: index,
+                            color: layer.color,
+                            plane_bounds: None,
+                            atlas_rect: None,
+                        });
+                    }
+                }
+                None => {
+                    if let Some(shape) = face.load_shape(glyph_id) {
+                        shapes.push((Placement::Glyph(index), shape));
+                    }
+                }
+            }
+        }
+
+        let shapes: Vec<_> = shapes
+            .into_iter()
+            .map(|(placement, shape)| {
+                let cell = GlyphCell::new(&shape, scale, config);
+                let shape = shape.color_edges(config.coloring_strategy, &config.coloring_config);
+                (placement, shape, cell)
+            })
+            .collect();
+
+        let cells: Vec<_> = shapes.iter().map(|(_, _, cell)| cell.as_ref()).collect();
+        let (width, height, rects) = pack(&cells, config);
+        for ((placement, _, cell), rect) in shapes.iter().zip(&rects) {
+            let plane_bounds = cell.as_ref().map(|cell| {
+                let translation = cell.projection.translation;
+                Bounds {
+                    left: -translation.x / units_per_em,
+                    bottom: -translation.y / units_per_em,
+                    right: (cell.width as f64 / scale - translation.x) / units_per_em,
+                    top: (cell.height as f64 / scale - translation.y) / units_per_em,
+                }
+            });
+            match *placement {
+                Placement::Glyph(index) => {
+                    atlas_glyphs[index].plane_bounds = plane_bounds;
+                    atlas_glyphs[index].atlas_rect = *rect;
+                }
+                Placement::Layer(index) => {
+                    layers[index].plane_bounds = plane_bounds;
+                    layers[index].atlas_rect = *rect;
+                }
+            }
+        }
+
+        let range = DistanceRange::symmetric(config.pixel_range / scale);
+        let sdf_config = SDFConfig {
+            overlap_support: config.msdf_config.overlap_support,
+            generator: config.msdf_config.generator,
+        };
+        let placed = || {
+            shapes
+                .iter()
+                .zip(&rects)
+                .filter_map(|((_, shape, cell), rect)| Some((shape, cell.as_ref()?, (*rect)?)))
+        };
+
+        let field = match config.atlas_type {
+            AtlasType::SDF | AtlasType::PsuedoSDF => {
+                let mut image = GrayFImage::new(width, height);
+                for (shape, cell, rect) in placed() {
+                    let (w, h, projection) = (cell.width, cell.height, &cell.projection);
+                    let glyph = if config.atlas_type == AtlasType::SDF {
+                        shape.generate_sdf(w, h, range, projection, &sdf_config)
+                    } else {
+                        shape.generate_psuedo_sdf(w, h, range, projection, &sdf_config)
+                    };
+                    blit(&mut image, glyph.image(), rect);
+                }
+
+                let field = SDF::from_image(image, config.pixel_range, 0.5);
+                if config.atlas_type == AtlasType::SDF {
+                    AtlasField::SDF(field)
+                } else {
+                    AtlasField::PsuedoSDF(field)
+                }
+            }
+            AtlasType::MSDF => {
+                let mut image = Rgb32FImage::new(width, height);
+                for (shape, cell, rect) in placed() {
+                    let glyph = shape.generate_msdf(
+                        cell.width,
+                        cell.height,
+                        range,
+                        &cell.projection,
+                        &config.msdf_config,
+                    );
+                    blit(&mut image, glyph.image(), rect);
+                }
+                AtlasField::MSDF(MSDF::from_image(image, config.pixel_range, 0.5))
+            }
+            AtlasType::MTSDF => {
+                let mut image = Rgba32FImage::new(width, height);
+                for (shape, cell, rect) in placed() {
+                    let glyph = shape.generate_mtsdf(
+                        cell.width,
+                        cell.height,
+                        range,
+                        &cell.projection,
+                        &config.msdf_config,
+                    );
+                    blit(&mut image, glyph.image(), rect);
+                }
+                AtlasField::MTSDF(MTSDF::from_image(image, config.pixel_range, 0.5))
+            }
+        };
+
+        let kerning = kerning(face, &atlas_glyphs);
+
+        Atlas {
+            field,
+            glyphs: atlas_glyphs,
+            kerning,
+            layers,
+            metrics: FontMetrics::from_face(face),
+            em_size: config.em_size,
+        }
+    }
+
+    /// Finds the glyph added for a character.
+    pub fn glyph(&self, codepoint: char) -> Option<&AtlasGlyph> {
+        self.glyphs
+            .iter()
+            .find(|glyph| glyph.codepoint == Some(codepoint))
+    }
+}
+
+/// What an outline placed in an atlas belongs to.
+enum Placement {
+    /// Index into the glyphs of the atlas.
+    Glyph(usize),
+    /// Index into the layers of the atlas.
+    Layer(usize),
+}
+
+/// Generates a field of the type set in `config` for a single shape.
+pub(crate) fn generate_field(
+    shape: &ColoredShape,
+    width: u32,
+    height: u32,
+    range: DistanceRange,
+    projection: &Projection,
+    config: &AtlasConfig,
+) -> AtlasField {
+    let sdf_config = SDFConfig {
+        overlap_support: config.msdf_config.overlap_support,
+        generator: config.msdf_config.generator,
+    };
+    match config.atlas_type {
+        AtlasType::SDF => {
+            AtlasField::SDF(shape.generate_sdf(width, height, range, projection, &sdf_config))
+        }
+        AtlasType::PsuedoSDF => AtlasField::PsuedoSDF(shape.generate_psuedo_sdf(
+            width,
+            height,
+            range,
+            projection,
+            &sdf_config,
+        )),
+        AtlasType::MSDF => AtlasField::MSDF(shape.generate_msdf(
+            width,
+            height,
+            range,
+            projection,
+            &config.msdf_config,
+        )),
+        AtlasType::MTSDF => AtlasField::MTSDF(shape.generate_mtsdf(
+            width,
+            height,
+            range,
+            projection,
+            &config.msdf_config,
+        )),
+    }
+}
+
+/// Size and projection of the pixels generated for a glyph.
+struct GlyphCell {
+    width: u32,
+    height: u32,
+    projection: Projection,
+}
+
+impl GlyphCell {
+    fn new(shape: &Shape, scale: f64, config: &AtlasConfig) -> Option<GlyphCell> {
+        let bounds = shape.bounds()?;
+
+        let width = (bounds.width() * scale + config.pixel_range).ceil();
+        let height = (bounds.height() * scale + config.pixel_range).ceil();
+        // center the glyph in the rounded up cell
+        let margin_x = (width - bounds.width() * scale) / 2.0 / scale;
+        let margin_y = (height - bounds.height() * scale) / 2.0 / scale;
+
+        Some(GlyphCell {
+            width: width as u32,
+            height: height as u32,
+            projection: Projection {
+                scale: Vector2 { x: scale, y: scale },
+                translation: Vector2 {
+                    x: margin_x - bounds.left,
+                    y: margin_y - bounds.bottom,
+                },
+            },
+        })
+    }
+}
+
+/// Packs cells into rows, tallest first. Returns the size of the atlas and the placement of
+/// every cell.
+fn pack(cells: &[Option<&GlyphCell>], config: &AtlasConfig) -> (u32, u32, Vec<Option<AtlasRect>>) {
+    let mut order: Vec<usize> = (0..cells.len()).filter(|&i| cells[i].is_some()).collect();
+    order.sort_by_key(|&i| Reverse(cells[i].as_ref().map_or(0, |cell| cell.height)));
+
+    let padding = config.padding;
+    let mut rects = vec![None; cells.len()];
+    let (mut x, mut y, mut row_height) = (padding, padding, 0);
+    let mut width = 0;
+
+    for i in order {
+        let cell = cells[i].unwrap();
+        if x > padding && x + cell.width + padding > config.max_width {
+            x = padding;
+            y += row_height + padding;
+            row_height = 0;
+        }
+
+        rects[i] = Some(AtlasRect {
+            x,
+            y,
+            width: cell.width,
+            height: cell.height,
+        });
+        x += cell.width + padding;
+        width = width.max(x);
+        row_height = row_height.max(cell.height);
+    }
+
+    let mut height = y + row_height + padding;
+    if config.power_of_two {
+        width = width.next_power_of_two();
+        height = height.next_power_of_two();
+    }
+
+    (width, height, rects)
+}
+
+fn blit<P>(atlas: &mut ImageBuffer<P, Vec<f32>>, glyph: &ImageBuffer<P, Vec<f32>>, rect: AtlasRect)
+where
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    for (x, y, pixel) in glyph.enumerate_pixels() {
+        atlas.put_pixel(rect.x + x, rect.y + y, *pixel);
+    }
+}
+
+/// Kerning between all pairs of glyphs, from the pair adjustments of the `kern` feature of the
+/// `GPOS` table, or from the horizontal subtables of the `kern` table in fonts without it.
+/// Contextual kerning and device adjustments aren't applied.
+pub(crate) fn kerning(face: &Face, glyphs: &[AtlasGlyph]) -> Vec<KerningPair> {
+    let mut ids = vec![];
+    let mut indices = HashMap::new();
+    for glyph in glyphs {
+        indices.entry(glyph.glyph_id).or_insert_with(|| {
+            ids.push(glyph.glyph_id);
+            ids.len() - 1
+        });
+    }
+
+    let mut units = HashMap::new();
+    let lookups = kerning_lookups(face);
+    if !lookups.is_empty() {
+        for lookup in &lookups {
+            lookup_kerning(lookup, &ids, &mut units);
+        }
+    } else if let Some(table) = face.tables().kern {
+        for subtable in table.subtables {
+            if !subtable.horizontal || subtable.variable {
+                continue;
+            }
+            match &subtable.format {
+                kern::Format::Format0(pairs) => {
+                    for pair in pairs.pairs {
+                        if let (Some(&left), Some(&right)) =
+                            (indices.get(&pair.left()), indices.get(&pair.right()))
+                        {
+                            *units.entry((left, right)).or_insert(0) += i32::from(pair.value);
+                        }
+                    }
+                }
+                // Class-based subtables have no list of pairs to walk.
+                _ => {
+                    for (left, &left_id) in ids.iter().enumerate() {
+                        for (right, &right_id) in ids.iter().enumerate() {
+                            if let Some(value) = subtable.glyphs_kerning(left_id, right_id) {
+                                *units.entry((left, right)).or_insert(0) += i32::from(value);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut pairs: Vec<_> = units.into_iter().filter(|&(_, units)| units != 0).collect();
+    pairs.sort_unstable_by_key(|&(pair, _)| pair);
+    pairs
+        .into_iter()
+        .map(|((left, right), units)| KerningPair {
+            left: ids[left],
+            right: ids[right],
+            advance: units as f64 / face.units_per_em() as f64,
+        })
+        .collect()
+}
+
+/// Lookups of the `kern` feature of the `GPOS` table, in the order they apply.
+fn kerning_lookups<'a>(face: &Face<'a>) -> Vec<Lookup<'a>> {
+    let gpos = match face.tables().gpos {
+        Some(gpos) => gpos,
+        None => return vec![],
+    };
+
+    let mut indices: Vec<u16> = gpos
+        .features
+        .into_iter()
+        .filter(|feature| feature.tag == Tag::from_bytes(b"kern"))
+        .flat_map(|feature| feature.lookup_indices)
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+        .into_iter()
+        .filter_map(|index| gpos.lookups.get(index))
+        .collect()
+}
+
+/// Adds the horizontal advance adjustments of a lookup between the glyphs `ids` to `units`, keyed
+/// by their indices. Only left glyphs in the coverage of a subtable are paired up, and the first
+/// subtable that applies to a pair is used.
+fn lookup_kerning(lookup: &Lookup, ids: &[GlyphId], units: &mut HashMap<(usize, usize), i32>) {
+    let subtables: Vec<_> = lookup
+        .subtables
+        .into_iter::<PositioningSubtable>()
+        .filter_map(|subtable| match subtable {
+            PositioningSubtable::Pair(subtable) => Some(subtable),
+            _ => None,
+        })
+        .collect();
+    // The glyphs grouped by their second class, for class-based subtables.
+    let second_classes: Vec<HashMap<u16, Vec<usize>>> = subtables
+        .iter()
+        .map(|subtable| {
+            let mut groups = HashMap::new();
+            if let PairAdjustment::Format2 { classes, .. } = subtable {
+                for (right, &right_id) in ids.iter().enumerate() {
+                    groups
+                        .entry(classes.1.get(right_id))
+                        .or_insert_with(Vec::new)
+                        .push(right);
+                }
+            }
+            groups
+        })
+        .collect();
+
+    for (left, &left_id) in ids.iter().enumerate() {
+        let mut applied = HashSet::new();
+        for (subtable, groups) in subtables.iter().zip(&second_classes) {
+            match *subtable {
+                PairAdjustment::Format1 { coverage, sets } => {
+                    let set = match coverage.get(left_id).and_then(|index| sets.get(index)) {
+                        Some(set) => set,
+                        None => continue,
+                    };
+                    for (right, &right_id) in ids.iter().enumerate() {
+                        if let Some((first, _)) = set.get(right_id) {
+                            if applied.insert(right) {
+                                *units.entry((left, right)).or_insert(0) +=
+                                    i32::from(first.x_advance);
+                            }
+                        }
+                    }
+                }
+                PairAdjustment::Format2 {
+                    coverage,
+                    classes,
+                    matrix,
+                } => {
+                    if !coverage.contains(left_id) {
+                        continue;
+                    }
+                    let class = classes.0.get(left_id);
+                    for (&second_class, rights) in groups {
+                        let (first, _) = match matrix.get((class, second_class)) {
+                            Some(records) => records,
+                            None => continue,
+                        };
+                        for &right in rights {
+                            if applied.insert(right) {
+                                *units.entry((left, right)).or_insert(0) +=
+                                    i32::from(first.x_advance);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
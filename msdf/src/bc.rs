@@ -0,0 +1,300 @@
+//! CPU encoders and decoders for BC4 and BC7 blocks. Every block holds 4x4 pixels, indexed row by
+//! row.
+
+/// Interpolation weights of 4-bit BC7 indices, out of 64.
+const BC7_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// Encodes a BC4 block from values in `[0, 255]`.
+pub(crate) fn encode_bc4(values: &[f32; 16]) -> [u8; 8] {
+    let max = values.iter().copied().fold(0.0f32, f32::max).round() as u8;
+    let min = values.iter().copied().fold(255.0f32, f32::min).round() as u8;
+
+    // the first endpoint being larger selects the mode with 6 interpolated values, rather than 4
+    // and the constants 0 and 255
+    let palette = bc4_palette(max, min);
+    let mut indices = 0u64;
+    for (i, &value) in values.iter().enumerate() {
+        let (index, _) = nearest(palette.iter().map(|&entry| (entry - value).abs()));
+        indices |= (index as u64) << (3 * i);
+    }
+
+    let mut block = [0; 8];
+    block[0] = max;
+    block[1] = min;
+    block[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
+
+/// Decodes a BC4 block to values in `[0, 255]`.
+pub(crate) fn decode_bc4(block: &[u8; 8]) -> [f32; 16] {
+    let palette = bc4_palette(block[0], block[1]);
+    let mut index_bytes = [0; 8];
+    index_bytes[..6].copy_from_slice(&block[2..]);
+    let indices = u64::from_le_bytes(index_bytes);
+
+    let mut values = [0.0; 16];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[(indices >> (3 * i) & 0x7) as usize];
+    }
+    values
+}
+
+fn bc4_palette(red0: u8, red1: u8) -> [f32; 8] {
+    let (red0, red1) = (red0 as f32, red1 as f32);
+    let mut palette = [red0, red1, 0.0, 0.0, 0.0, 0.0, 0.0, 255.0];
+    if red0 > red1 {
+        for (i, entry) in palette.iter_mut().enumerate().skip(2) {
+            *entry = ((8 - i) as f32 * red0 + (i - 1) as f32 * red1) / 7.0;
+        }
+    } else {
+        for (i, entry) in palette.iter_mut().enumerate().skip(2).take(4) {
+            *entry = ((6 - i) as f32 * red0 + (i - 1) as f32 * red1) / 5.0;
+        }
+    }
+    palette
+}
+
+/// Encodes a BC7 block from RGBA pixels in `[0, 255]`. Only mode 6 is used, which has a single
+/// pair of 7-bit endpoints with a p-bit each and 4-bit indices. Endpoints start on the principal
+/// axis of the pixels and are refined by least squares.
+pub(crate) fn encode_bc7(pixels: &[[f32; 4]; 16]) -> [u8; 16] {
+    let mut endpoints = initial_endpoints(pixels);
+    let mut best = (u32::MAX, [[0; 4]; 2], [0; 16]);
+
+    for _ in 0..3 {
+        let quantized = [
+            quantize_endpoint(endpoints[0]),
+            quantize_endpoint(endpoints[1]),
+        ];
+        let (indices, error) = bc7_indices(pixels, &quantized);
+        if error < best.0 {
+            best = (error, quantized, indices);
+        }
+
+        match refine_endpoints(pixels, &indices) {
+            Some(refined) => endpoints = refined,
+            None => break,
+        }
+    }
+
+    let (_, mut endpoints, mut indices) = best;
+    // the first index has an implicit leading zero bit
+    if indices[0] >= 8 {
+        endpoints.swap(0, 1);
+        for index in indices.iter_mut() {
+            *index = 15 - *index;
+        }
+    }
+
+    let mut bits = BitWriter::default();
+    bits.write(1 << 6, 7);
+    for channel in 0..4 {
+        for endpoint in &endpoints {
+            bits.write(endpoint[channel] as u128 >> 1, 7);
+        }
+    }
+    for endpoint in &endpoints {
+        bits.write(endpoint[0] as u128 & 1, 1);
+    }
+    for (i, &index) in indices.iter().enumerate() {
+        bits.write(index as u128, if i == 0 { 3 } else { 4 });
+    }
+    bits.0.to_le_bytes()
+}
+
+/// Decodes a BC7 block to RGBA pixels in `[0, 255]`. Returns [None] for blocks that don't use
+/// mode 6.
+pub(crate) fn decode_bc7(block: &[u8; 16]) -> Option<[[f32; 4]; 16]> {
+    let mut bits = BitReader(u128::from_le_bytes(*block));
+    if bits.read(7) != 1 << 6 {
+        return None;
+    }
+
+    let mut endpoints = [[0u32; 4]; 2];
+    for channel in 0..4 {
+        for endpoint in endpoints.iter_mut() {
+            endpoint[channel] = (bits.read(7) as u32) << 1;
+        }
+    }
+    for endpoint in endpoints.iter_mut() {
+        let p_bit = bits.read(1) as u32;
+        for value in endpoint.iter_mut() {
+            *value |= p_bit;
+        }
+    }
+
+    let mut pixels = [[0.0; 4]; 16];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let index = bits.read(if i == 0 { 3 } else { 4 }) as usize;
+        *pixel = bc7_interpolate(&endpoints, index);
+    }
+    Some(pixels)
+}
+
+fn bc7_interpolate(endpoints: &[[u32; 4]; 2], index: usize) -> [f32; 4] {
+    let weight = BC7_WEIGHTS[index];
+    let mut pixel = [0.0; 4];
+    for (channel, value) in pixel.iter_mut().enumerate() {
+        let mixed = (64 - weight) * endpoints[0][channel] + weight * endpoints[1][channel] + 32;
+        *value = (mixed >> 6) as f32;
+    }
+    pixel
+}
+
+/// Picks the nearest interpolated color for every pixel, returning the indices and the total
+/// squared error.
+fn bc7_indices(pixels: &[[f32; 4]; 16], endpoints: &[[u32; 4]; 2]) -> ([u8; 16], u32) {
+    let palette: Vec<[f32; 4]> = (0..16)
+        .map(|index| bc7_interpolate(endpoints, index))
+        .collect();
+
+    let mut indices = [0; 16];
+    let mut total = 0.0;
+    for (index, pixel) in indices.iter_mut().zip(pixels) {
+        let (nearest, error) = nearest(palette.iter().map(|entry| distance_squared(entry, pixel)));
+        *index = nearest as u8;
+        total += error;
+    }
+    (indices, total as u32)
+}
+
+/// Endpoints spanning the pixels along the direction of their largest variance.
+fn initial_endpoints(pixels: &[[f32; 4]; 16]) -> [[f32; 4]; 2] {
+    let mut mean = [0.0; 4];
+    for pixel in pixels {
+        for channel in 0..4 {
+            mean[channel] += pixel[channel] / 16.0;
+        }
+    }
+
+    let mut covariance = [[0.0f32; 4]; 4];
+    for pixel in pixels {
+        for a in 0..4 {
+            for b in 0..4 {
+                covariance[a][b] += (pixel[a] - mean[a]) * (pixel[b] - mean[b]);
+            }
+        }
+    }
+
+    // power iteration, starting from the covariance of the channel that varies most, since
+    // channels of multi-channel fields are often anti-correlated
+    let widest = (0..4)
+        .max_by(|&a, &b| covariance[a][a].total_cmp(&covariance[b][b]))
+        .unwrap();
+    let mut axis = covariance[widest];
+    for _ in 0..8 {
+        let mut next = [0.0; 4];
+        for a in 0..4 {
+            for b in 0..4 {
+                next[a] += covariance[a][b] * axis[b];
+            }
+        }
+        let length = dot(&next, &next).sqrt();
+        if length < 1e-6 {
+            break;
+        }
+        axis = next.map(|value| value / length);
+    }
+
+    let (mut min, mut max) = (f32::MAX, f32::MIN);
+    for pixel in pixels {
+        let offset = [0, 1, 2, 3].map(|channel| pixel[channel] - mean[channel]);
+        let projected = dot(&offset, &axis);
+        min = min.min(projected);
+        max = max.max(projected);
+    }
+
+    let endpoint =
+        |t: f32| [0, 1, 2, 3].map(|channel| (mean[channel] + axis[channel] * t).clamp(0.0, 255.0));
+    [endpoint(min), endpoint(max)]
+}
+
+/// Solves for the endpoints that best reproduce the pixels with the given indices.
+fn refine_endpoints(pixels: &[[f32; 4]; 16], indices: &[u8; 16]) -> Option<[[f32; 4]; 2]> {
+    let (mut a, mut b, mut c) = (0.0, 0.0, 0.0);
+    let mut first = [0.0; 4];
+    let mut second = [0.0; 4];
+    for (pixel, &index) in pixels.iter().zip(indices) {
+        let weight = BC7_WEIGHTS[index as usize] as f32 / 64.0;
+        a += (1.0 - weight) * (1.0 - weight);
+        b += (1.0 - weight) * weight;
+        c += weight * weight;
+        for channel in 0..4 {
+            first[channel] += (1.0 - weight) * pixel[channel];
+            second[channel] += weight * pixel[channel];
+        }
+    }
+
+    let determinant = a * c - b * b;
+    if determinant.abs() < 1e-6 {
+        return None;
+    }
+
+    let solve = |x: f32, y: f32, z: f32, w: f32| ((x * y - z * w) / determinant).clamp(0.0, 255.0);
+    Some([
+        [0, 1, 2, 3].map(|channel| solve(c, first[channel], b, second[channel])),
+        [0, 1, 2, 3].map(|channel| solve(a, second[channel], b, first[channel])),
+    ])
+}
+
+/// Rounds an endpoint to 7 bits per channel and a shared p-bit, choosing the p-bit that keeps
+/// it closest. The p-bit ends up as the lowest bit of every channel.
+fn quantize_endpoint(endpoint: [f32; 4]) -> [u32; 4] {
+    let candidates = [0, 1].map(|p_bit| {
+        endpoint.map(|value| {
+            let quantized = ((value - p_bit as f32) / 2.0).round().clamp(0.0, 127.0) as u32;
+            quantized << 1 | p_bit
+        })
+    });
+
+    let error = |candidate: &[u32; 4]| {
+        let candidate = candidate.map(|value| value as f32);
+        distance_squared(&candidate, &endpoint)
+    };
+    if error(&candidates[0]) <= error(&candidates[1]) {
+        candidates[0]
+    } else {
+        candidates[1]
+    }
+}
+
+/// Index and value of the smallest error.
+fn nearest<I: Iterator<Item = f32>>(errors: I) -> (usize, f32) {
+    errors
+        .enumerate()
+        .fold((0, f32::MAX), |best, (index, error)| {
+            if error < best.1 {
+                (index, error)
+            } else {
+                best
+            }
+        })
+}
+
+fn dot(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn distance_squared(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+#[derive(Default)]
+struct BitWriter(u128, u32);
+
+impl BitWriter {
+    fn write(&mut self, value: u128, bits: u32) {
+        self.0 |= value << self.1;
+        self.1 += bits;
+    }
+}
+
+struct BitReader(u128);
+
+impl BitReader {
+    fn read(&mut self, bits: u32) -> u128 {
+        let value = self.0 & ((1 << bits) - 1);
+        self.0 >>= bits;
+        value
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{DistanceRange, RangeUnits};
 use image::{GenericImage, ImageBuffer, Luma, Pixel, Rgb32FImage, Rgba32FImage};
 use msdf_sys::*;
 use std::os::raw::c_int;
@@ -19,17 +20,32 @@ pub trait SDFTrait {
     type Image: GenericImage;
     type ColoredOutput: GenericImage;
 
-    /// Create an SDF from an image.
+    /// Create an SDF from an image. `range` is in pixels of the image, see
+    /// [SDFTrait::with_range_units] for other units.
     fn from_image(image: Self::Image, range: f64, mid_value: f32) -> Self;
+    /// Change the units the range of this SDF is measured in.
+    fn with_range_units(self, units: RangeUnits) -> Self;
     /// Convert an SDF to an image.
     fn to_image(self) -> Self::Image;
 
     /// Get SDF image.
     fn image(&self) -> &Self::Image;
-    /// Get SDF range.
+    /// Get SDF range, in [SDFTrait::range_units].
     fn range(&self) -> f64;
+    /// Get the units of the SDF range.
+    fn range_units(&self) -> RangeUnits;
     /// Get SDF mid-value.
     fn mid_value(&self) -> f32;
+    /// Get the signed distances stored as 0 and 1, derived from the range and mid-value. In the
+    /// same units as [SDFTrait::range].
+    fn distance_range(&self) -> DistanceRange {
+        DistanceRange::from_mid_value(self.range(), self.mid_value())
+    }
+    /// Get SDF range in pixels of the SDF image. Shaders need this to compute the screen pixel
+    /// range, by multiplying it with the ratio of the rendered size to the SDF size.
+    fn pixel_range(&self) -> f64 {
+        self.range() * self.range_units().pixels_per_unit()
+    }
 
     /// Render this SDF to a single-channel float image of specified size.
     fn render(&self, width: u32, height: u32) -> GrayFImage;
@@ -42,6 +58,7 @@ pub trait SDFTrait {
 pub struct SDF {
     image: GrayFImage,
     range: f64,
+    units: RangeUnits,
     mid_value: f32,
 }
 
@@ -49,6 +66,7 @@ pub struct SDF {
 pub struct MSDF {
     image: Rgb32FImage,
     range: f64,
+    units: RangeUnits,
     mid_value: f32,
 }
 
@@ -56,6 +74,7 @@ pub struct MSDF {
 pub struct MTSDF {
     image: Rgba32FImage,
     range: f64,
+    units: RangeUnits,
     mid_value: f32,
 }
 
@@ -67,10 +86,15 @@ impl SDFTrait for SDF {
         SDF {
             image,
             range,
+            units: RangeUnits::Pixels,
             mid_value,
         }
     }
 
+    fn with_range_units(self, units: RangeUnits) -> Self {
+        SDF { units, ..self }
+    }
+
     fn to_image(self) -> Self::Image {
         self.image
     }
@@ -83,6 +107,10 @@ impl SDFTrait for SDF {
         self.range
     }
 
+    fn range_units(&self) -> RangeUnits {
+        self.units
+    }
+
     fn mid_value(&self) -> f32 {
         self.mid_value
     }
@@ -97,7 +125,7 @@ impl SDFTrait for SDF {
             msdfgen_renderSDF(
                 &output as *const msdfgen_Bitmap<_> as *const _,
                 &input as *const msdfgen_Bitmap<_> as *const _,
-                self.range(),
+                self.pixel_range(),
                 self.mid_value(),
             );
         }
@@ -115,7 +143,7 @@ impl SDFTrait for SDF {
             msdfgen_renderSDF1(
                 &output as *const msdfgen_Bitmap<_> as *const _,
                 &input as *const msdfgen_Bitmap<_> as *const _,
-                self.range(),
+                self.pixel_range(),
                 self.mid_value(),
             );
         }
@@ -132,10 +160,15 @@ impl SDFTrait for MSDF {
         MSDF {
             image,
             range,
+            units: RangeUnits::Pixels,
             mid_value,
         }
     }
 
+    fn with_range_units(self, units: RangeUnits) -> Self {
+        MSDF { units, ..self }
+    }
+
     fn to_image(self) -> Self::Image {
         self.image
     }
@@ -148,6 +181,10 @@ impl SDFTrait for MSDF {
         self.range
     }
 
+    fn range_units(&self) -> RangeUnits {
+        self.units
+    }
+
     fn mid_value(&self) -> f32 {
         self.mid_value
     }
@@ -162,7 +199,7 @@ impl SDFTrait for MSDF {
             msdfgen_renderSDF2(
                 &output as *const msdfgen_Bitmap<_> as *const _,
                 &input as *const msdfgen_Bitmap<_> as *const _,
-                self.range(),
+                self.pixel_range(),
                 self.mid_value(),
             );
         }
@@ -180,7 +217,7 @@ impl SDFTrait for MSDF {
             msdfgen_renderSDF3(
                 &output as *const msdfgen_Bitmap<_> as *const _,
                 &input as *const msdfgen_Bitmap<_> as *const _,
-                self.range(),
+                self.pixel_range(),
                 self.mid_value(),
             );
         }
@@ -197,10 +234,15 @@ impl SDFTrait for MTSDF {
         MTSDF {
             image,
             range,
+            units: RangeUnits::Pixels,
             mid_value,
         }
     }
 
+    fn with_range_units(self, units: RangeUnits) -> Self {
+        MTSDF { units, ..self }
+    }
+
     fn to_image(self) -> Self::Image {
         self.image
     }
@@ -213,6 +255,10 @@ impl SDFTrait for MTSDF {
         self.range
     }
 
+    fn range_units(&self) -> RangeUnits {
+        self.units
+    }
+
     fn mid_value(&self) -> f32 {
         self.mid_value
     }
@@ -227,7 +273,7 @@ impl SDFTrait for MTSDF {
             msdfgen_renderSDF4(
                 &output as *const msdfgen_Bitmap<_> as *const _,
                 &input as *const msdfgen_Bitmap<_> as *const _,
-                self.range(),
+                self.pixel_range(),
                 self.mid_value(),
             );
         }
@@ -245,7 +291,7 @@ impl SDFTrait for MTSDF {
             msdfgen_renderSDF5(
                 &output as *const msdfgen_Bitmap<_> as *const _,
                 &input as *const msdfgen_Bitmap<_> as *const _,
-                self.range(),
+                self.pixel_range(),
                 self.mid_value(),
             );
         }
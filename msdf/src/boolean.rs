@@ -0,0 +1,159 @@
+use crate::geometry::{intersections, Vec2};
+use crate::{Segment, Shape};
+
+impl Shape {
+    /// Produces a new shape covering the same area with all overlapping contours merged, similar
+    /// to msdfgen's `resolveShapeGeometry`. Contours are split where they cross and only the
+    /// pieces that separate filled from empty space (using the non-zero fill rule) are kept.
+    /// Fields of the resolved shape can be generated with `overlap_support` disabled, which is
+    /// both faster and free of artifacts where contours used to overlap. The resulting shape is
+    /// uncolored.
+    pub fn resolve_overlaps(&self) -> Shape {
+        let size = match self.bounds() {
+            Some(bounds) => bounds.width().max(bounds.height()),
+            None => return Shape::from_contours(&[], self.shape.inverseYAxis),
+        };
+
+        let contours = union_contours(&self.contours(), size, |point| self.winding(point));
+
+        Shape::from_contours(&contours, self.shape.inverseYAxis)
+    }
+}
+
+/// Computes the outline of the area covered by `contours` under the non-zero fill rule.
+/// `winding` returns the winding number of the original contours around a point.
+fn union_contours<F: Fn(Vec2) -> i32>(
+    contours: &[Vec<Segment>],
+    size: f64,
+    winding: F,
+) -> Vec<Vec<Segment>> {
+    let tolerance = size * 1e-7;
+    let epsilon = size * 1e-5;
+
+    let segments = contours
+        .iter()
+        .flatten()
+        .filter(|segment| !segment.is_degenerate())
+        .copied()
+        .collect::<Vec<_>>();
+
+    // find where segments cross each other
+    let mut splits = vec![vec![]; segments.len()];
+    for i in 0..segments.len() {
+        for j in i + 1..segments.len() {
+            for (ti, tj) in intersections(&segments[i], &segments[j], tolerance) {
+                splits[i].push(ti);
+                splits[j].push(tj);
+            }
+        }
+    }
+
+    // keep the pieces with filled space on exactly one side, oriented with the fill on the left
+    let mut pieces = vec![];
+    let mut fill_on_right = 0i64;
+    for (segment, mut params) in segments.iter().zip(splits) {
+        params.retain(|&t| t > 1e-9 && t < 1.0 - 1e-9);
+        params.sort_by(f64::total_cmp);
+        params.dedup_by(|a, b| (*a - *b).abs() <= 1e-9);
+
+        let bounds = [0.0]
+            .into_iter()
+            .chain(params)
+            .chain([1.0])
+            .collect::<Vec<_>>();
+
+        for range in bounds.windows(2) {
+            let piece = segment.section(range[0], range[1]);
+            if piece.is_degenerate() {
+                continue;
+            }
+
+            let point = Vec2::from(piece.point(0.5));
+            let normal = Vec2::from(piece.direction(0.5)).normalize().perpendicular();
+            let left = winding(point + normal * epsilon) != 0;
+            let right = winding(point - normal * epsilon) != 0;
+
+            if left && !right {
+                fill_on_right -= 1;
+                pieces.push(piece);
+            } else if right && !left {
+                fill_on_right += 1;
+                pieces.push(piece.reversed());
+            }
+        }
+    }
+
+    dedup_pieces(&mut pieces, tolerance.max(epsilon));
+
+    // keep the orientation convention most of the original edges were using
+    if fill_on_right > 0 {
+        pieces = pieces.iter().map(Segment::reversed).collect();
+    }
+
+    chain_pieces(pieces, epsilon)
+}
+
+/// Removes pieces that retrace another piece, which happens where contours share an edge.
+fn dedup_pieces(pieces: &mut Vec<Segment>, tolerance: f64) {
+    let close = |a: &Segment, b: &Segment| {
+        [0.0, 0.5, 1.0]
+            .iter()
+            .all(|&t| (Vec2::from(a.point(t)) - Vec2::from(b.point(t))).length() <= tolerance)
+    };
+
+    let mut index = 0;
+    while index < pieces.len() {
+        if pieces[..index]
+            .iter()
+            .any(|other| close(other, &pieces[index]))
+        {
+            pieces.remove(index);
+        } else {
+            index += 1;
+        }
+    }
+}
+
+/// Links pieces end to start into closed contours.
+fn chain_pieces(mut pieces: Vec<Segment>, tolerance: f64) -> Vec<Vec<Segment>> {
+    let mut contours = vec![];
+
+    while let Some(first) = pieces.pop() {
+        let start = Vec2::from(first.start());
+        let mut contour = vec![first];
+
+        let closed = loop {
+            let end = Vec2::from(contour[contour.len() - 1].end());
+            if (end - start).length() <= tolerance {
+                break true;
+            }
+
+            let next = pieces
+                .iter()
+                .enumerate()
+                .map(|(index, piece)| (index, (Vec2::from(piece.start()) - end).length()))
+                .filter(|&(_, distance)| distance <= tolerance)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+
+            match next {
+                Some((index, _)) => contour.push(pieces.swap_remove(index)),
+                None => break false,
+            }
+        };
+
+        // a dangling chain is most likely a sliver that lost a piece to rounding
+        if !closed {
+            continue;
+        }
+
+        // close tiny gaps left by splitting, so that the contour is watertight
+        for index in 0..contour.len() {
+            let previous = contour[(index + contour.len() - 1) % contour.len()].end();
+            contour[index] = contour[index].with_start(previous);
+        }
+
+        contours.push(contour);
+    }
+
+    contours
+}
@@ -0,0 +1,285 @@
+use std::collections::btree_set;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::iter::{Copied, Peekable};
+use std::str::CharIndices;
+use ttf_parser::{Face, GlyphId};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Error returned when a charset or glyph set can't be parsed.
+pub struct ParseCharsetError {
+    /// Byte offset into the input at which parsing failed.
+    pub offset: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseCharsetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.reason, self.offset)
+    }
+}
+
+impl std::error::Error for ParseCharsetError {}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Set of characters to include in an [crate::Atlas], kept in code point order.
+pub struct Charset {
+    chars: BTreeSet<char>,
+}
+
+/// Glyphs of a [Charset] in a face.
+pub struct ResolvedCharset {
+    /// Characters found in the face, with their glyphs.
+    pub glyphs: Vec<(char, GlyphId)>,
+    /// Characters the face has no glyph for.
+    pub missing: Vec<char>,
+}
+
+impl Charset {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Printable ASCII, from space to tilde.
+    pub fn ascii() -> Self {
+        (' '..='~').collect()
+    }
+
+    /// Printable characters of ISO 8859-1: ASCII and the Latin-1 Supplement block.
+    pub fn latin1() -> Self {
+        let mut charset = Charset::ascii();
+        charset.extend('\u{a0}'..='\u{ff}');
+        charset
+    }
+
+    /// Latin-1 with the Latin Extended-A and Latin Extended-B blocks.
+    pub fn latin_extended() -> Self {
+        let mut charset = Charset::latin1();
+        charset.extend('\u{100}'..='\u{24f}');
+        charset
+    }
+
+    /// The Cyrillic block. Combine it with [Charset::ascii] for punctuation and digits.
+    pub fn cyrillic() -> Self {
+        ('\u{400}'..='\u{4ff}').collect()
+    }
+
+    /// The Greek and Coptic block. Combine it with [Charset::ascii] for punctuation and digits.
+    pub fn greek() -> Self {
+        ('\u{370}'..='\u{3ff}').collect()
+    }
+
+    /// Every character used in `text`, except for control characters like line breaks.
+    pub fn from_text(text: &str) -> Self {
+        text.chars().filter(|c| !c.is_control()).collect()
+    }
+
+    /// Parses msdf-atlas-gen's charset syntax: code points (`65` or `0x41`), character literals
+    /// (`'A'`), inclusive ranges of either (`[0x20, 0x7e]`) and strings (`"ABC"`), separated by
+    /// commas or whitespace. Surrogate code points are skipped.
+    pub fn parse(charset: &str) -> Result<Charset, ParseCharsetError> {
+        Ok(parse_values(charset, true, char::MAX as u32)?
+            .into_iter()
+            .filter_map(char::from_u32)
+            .collect())
+    }
+
+    pub fn insert(&mut self, c: char) -> bool {
+        self.chars.insert(c)
+    }
+
+    pub fn remove(&mut self, c: char) -> bool {
+        self.chars.remove(&c)
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.chars.contains(&c)
+    }
+
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    pub fn iter(&self) -> Copied<btree_set::Iter<'_, char>> {
+        self.chars.iter().copied()
+    }
+
+    /// Looks up the glyph of every character in `face`.
+    pub fn resolve(&self, face: &Face) -> ResolvedCharset {
+        let mut resolved = ResolvedCharset {
+            glyphs: vec![],
+            missing: vec![],
+        };
+        for c in self {
+            match face.glyph_index(c) {
+                Some(glyph_id) => resolved.glyphs.push((c, glyph_id)),
+                None => resolved.missing.push(c),
+            }
+        }
+        resolved
+    }
+}
+
+impl Extend<char> for Charset {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        self.chars.extend(iter)
+    }
+}
+
+impl FromIterator<char> for Charset {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        Charset {
+            chars: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Charset {
+    type Item = char;
+    type IntoIter = Copied<btree_set::Iter<'a, char>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Parses a glyph set, msdf-atlas-gen's charset syntax with glyph indices instead of code points
+/// and without strings. Indices are returned in the order they are listed.
+pub fn parse_glyph_set(glyph_set: &str) -> Result<Vec<GlyphId>, ParseCharsetError> {
+    Ok(parse_values(glyph_set, false, u16::MAX as u32)?
+        .into_iter()
+        .map(|index| GlyphId(index as u16))
+        .collect())
+}
+
+/// Parses the values of a charset or glyph set, all of which are at most `max`.
+fn parse_values(text: &str, allow_strings: bool, max: u32) -> Result<Vec<u32>, ParseCharsetError> {
+    let mut parser = CharsetParser {
+        chars: text.char_indices().peekable(),
+        length: text.len(),
+        max,
+    };
+    let mut values = vec![];
+
+    loop {
+        parser.skip_separators();
+        match parser.peek() {
+            None => break,
+            Some('[') => {
+                parser.next();
+                parser.skip_separators();
+                let first = parser.value()?;
+                parser.skip_separators();
+                let last = parser.value()?;
+                parser.skip_separators();
+                parser.expect(']', "expected `]` after a range")?;
+                values.extend(first..=last);
+            }
+            Some('"') if allow_strings => {
+                parser.next();
+                loop {
+                    match parser.next() {
+                        Some('"') => break,
+                        Some('\\') => values.push(parser.escaped()? as u32),
+                        Some(c) => values.push(c as u32),
+                        None => return Err(parser.error("unterminated string")),
+                    }
+                }
+            }
+            Some(_) => values.push(parser.value()?),
+        }
+    }
+
+    Ok(values)
+}
+
+struct CharsetParser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    length: usize,
+    max: u32,
+}
+
+impl CharsetParser<'_> {
+    fn error(&mut self, reason: &'static str) -> ParseCharsetError {
+        let offset = self.chars.peek().map_or(self.length, |&(offset, _)| offset);
+        ParseCharsetError { offset, reason }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.chars.next().map(|(_, c)| c)
+    }
+
+    fn expect(&mut self, expected: char, reason: &'static str) -> Result<(), ParseCharsetError> {
+        if self.peek() == Some(expected) {
+            self.next();
+            Ok(())
+        } else {
+            Err(self.error(reason))
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while matches!(self.peek(), Some(c) if c == ',' || c.is_whitespace()) {
+            self.next();
+        }
+    }
+
+    /// A number or a character literal, at most `max`.
+    fn value(&mut self) -> Result<u32, ParseCharsetError> {
+        let start = self.error("expected a number or a character");
+        let value = if self.peek() == Some('\'') {
+            self.next();
+            let c = match self.next() {
+                Some('\\') => self.escaped()?,
+                Some(c) => c,
+                None => return Err(self.error("unterminated character")),
+            };
+            self.expect('\'', "expected `'` after a character")?;
+            c as u32
+        } else {
+            let mut number = String::new();
+            while let Some(c) = self.peek() {
+                if !c.is_ascii_alphanumeric() {
+                    break;
+                }
+                number.push(c);
+                self.next();
+            }
+            let parsed = match number
+                .strip_prefix("0x")
+                .or_else(|| number.strip_prefix("0X"))
+            {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => number.parse(),
+            };
+            parsed.map_err(|_| start.clone())?
+        };
+
+        if value > self.max {
+            return Err(ParseCharsetError {
+                reason: "value out of range",
+                ..start
+            });
+        }
+        Ok(value)
+    }
+
+    fn escaped(&mut self) -> Result<char, ParseCharsetError> {
+        match self.next() {
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('r') => Ok('\r'),
+            Some('0') => Ok('\0'),
+            Some(c @ ('\\' | '\'' | '"')) => Ok(c),
+            _ => Err(self.error("invalid escape sequence")),
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use crate::atlas::generate_field;
+use crate::{
+    Affine2, AtlasConfig, AtlasField, Bounds, DistanceRange, GlyphLoader, Projection, Shape,
+};
+use image::Rgba;
+use mint::Vector2;
+use std::f64::consts::PI;
+use ttf_parser::{Face, GlyphId, Tag};
+
+/// Palette index that stands for the text color instead of a palette entry.
+const FOREGROUND: u16 = 0xffff;
+
+/// Nesting limit of COLRv1 paints, which guards against cycles in malformed fonts.
+const MAX_PAINT_DEPTH: u8 = 64;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Color of a [ColorLayer].
+pub enum LayerColor {
+    /// An entry of the palette, as non-premultiplied sRGB with alpha.
+    Palette(Rgba<u8>),
+    /// The color of the surrounding text, with the given alpha.
+    Foreground(u8),
+}
+
+#[derive(Clone)]
+/// One layer of a color glyph, filled with a single color.
+pub struct ColorLayer {
+    /// Outline of the layer in font units, with any COLRv1 transforms applied.
+    pub shape: Shape,
+    pub color: LayerColor,
+}
+
+#[derive(Clone)]
+/// Layers of a color glyph from the `COLR` table, drawn over each other bottom first.
+pub struct ColorGlyph {
+    pub layers: Vec<ColorLayer>,
+}
+
+impl ColorGlyph {
+    /// Bounding box of all layers. Returns [None] if no layer has any edges.
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.layers
+            .iter()
+            .filter_map(|layer| layer.shape.bounds())
+            .reduce(|a, b| Bounds {
+                left: a.left.min(b.left),
+                bottom: a.bottom.min(b.bottom),
+                right: a.right.max(b.right),
+                top: a.top.max(b.top),
+            })
+    }
+
+    /// Generates a field of every layer with the same size and projection, so that they line up
+    /// when drawn over each other. Only the type, coloring and generator settings of `config`
+    /// are used.
+    pub fn generate(
+        &self,
+        width: u32,
+        height: u32,
+        range: impl Into<DistanceRange>,
+        projection: &Projection,
+        config: &AtlasConfig,
+    ) -> Vec<AtlasField> {
+        let range = range.into();
+        range.assert_valid();
+        self.layers
+            .iter()
+            .map(|layer| {
+                let shape = layer
+                    .shape
+                    .clone()
+                    .color_edges(config.coloring_strategy, &config.coloring_config);
+                generate_field(&shape, width, height, range, projection, config)
+            })
+            .collect()
+    }
+}
+
+/// Loads color glyphs from the `COLR` and `CPAL` tables. Both versions of `COLR` are supported.
+/// COLRv1 paints are flattened into layers: transforms are applied to the outlines, gradients
+/// become the average color of their stops, nested clip glyphs keep only the outermost outline and
+/// composites draw the backdrop below the source regardless of their mode.
+pub trait ColorGlyphLoader {
+    type Glyph;
+
+    /// Number of palettes in the `CPAL` table.
+    fn palette_count(&self) -> u16;
+
+    /// Loads the layers of a color glyph, with their colors from the given palette. Returns
+    /// [None] if the glyph has no color layers, in which case it can be loaded with
+    /// [GlyphLoader::load_shape]. Layers without an outline are skipped.
+    fn load_color_glyph(&self, glyph: Self::Glyph, palette: u16) -> Option<ColorGlyph>;
+}
+
+impl ColorGlyphLoader for Face<'_> {
+    type Glyph = GlyphId;
+
+    fn palette_count(&self) -> u16 {
+        self.table_data(Tag::from_bytes(b"CPAL"))
+            .and_then(|cpal| u16_at(cpal, 4))
+            .unwrap_or(0)
+    }
+
+    fn load_color_glyph(&self, glyph: GlyphId, palette: u16) -> Option<ColorGlyph> {
+        let colr = self.table_data(Tag::from_bytes(b"COLR"))?;
+        let records = parse_color_layers(colr, glyph)?;
+        let palette = self
+            .table_data(Tag::from_bytes(b"CPAL"))
+            .map(|cpal| parse_palette(cpal, palette))
+            .unwrap_or_default();
+
+        let layers = records
+            .into_iter()
+            .filter_map(|record| {
+                let mut shape = self.load_shape(record.glyph)?;
+                if record.transform != Affine2::IDENTITY {
+                    shape.transform(&record.transform);
+                }
+                Some(ColorLayer {
+                    shape,
+                    color: resolve_color(&record.stops, &palette)?,
+                })
+            })
+            .collect();
+        Some(ColorGlyph { layers })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A glyph outline painted by a `COLR` table, before its colors are looked up.
+pub(crate) struct LayerRecord {
+    pub glyph: GlyphId,
+    pub transform: Affine2,
+    /// Palette index and alpha of every color in the fill. Solid fills have one.
+    pub stops: Vec<(u16, f32)>,
+}
+
+/// Reads the colors of a palette from a `CPAL` table. Returns nothing if the table is malformed
+/// or has no such palette.
+pub(crate) fn parse_palette(cpal: &[u8], palette: u16) -> Vec<Rgba<u8>> {
+    let parse = || -> Option<Vec<Rgba<u8>>> {
+        let entry_count = u16_at(cpal, 2)? as usize;
+        if palette >= u16_at(cpal, 4)? {
+            return None;
+        }
+        let records_offset = u32_at(cpal, 8)? as usize;
+        let first = u16_at(cpal, 12 + palette as usize * 2)? as usize;
+
+        (first..first + entry_count)
+            .map(|record| {
+                // records are stored as BGRA
+                let offset = records_offset + record * 4;
+                let bgra = cpal.get(offset..offset + 4)?;
+                Some(Rgba([bgra[2], bgra[1], bgra[0], bgra[3]]))
+            })
+            .collect()
+    };
+
+    parse().unwrap_or_default()
+}
+
+/// Reads the layers of a glyph from a `COLR` table, bottom first. COLRv1 paints take precedence
+/// over COLRv0 layers. Returns [None] if the glyph isn't a color glyph or the table is malformed.
+pub(crate) fn parse_color_layers(colr: &[u8], glyph: GlyphId) -> Option<Vec<LayerRecord>> {
+    let version = u16_at(colr, 0)?;
+
+    if version >= 1 {
+        let paints = ColrPaints {
+            colr,
+            base_glyphs: offset_at(colr, 14)?,
+            layers: offset_at(colr, 18)?,
+        };
+        if let Some(paint) = paints.base_glyph_paint(glyph) {
+            let mut layers = vec![];
+            paints.paint(paint, Affine2::IDENTITY, 0, &mut layers)?;
+            return Some(layers);
+        }
+    }
+
+    let base_count = u16_at(colr, 2)? as usize;
+    let base_offset = u32_at(colr, 4)? as usize;
+    let layers_offset = u32_at(colr, 8)? as usize;
+    let layer_count = u16_at(colr, 12)? as usize;
+
+    let base = (0..base_count)
+        .map(|record| base_offset + record * 6)
+        .find(|&offset| u16_at(colr, offset) == Some(glyph.0))?;
+    let first = u16_at(colr, base + 2)? as usize;
+    let count = u16_at(colr, base + 4)? as usize;
+    if first + count > layer_count {
+        return None;
+    }
+
+    (first..first + count)
+        .map(|layer| {
+            let offset = layers_offset + layer * 4;
+            Some(LayerRecord {
+                glyph: GlyphId(u16_at(colr, offset)?),
+                transform: Affine2::IDENTITY,
+                stops: vec![(u16_at(colr, offset + 2)?, 1.0)],
+            })
+        })
+        .collect()
+}
+
+/// Paint graph of a COLRv1 table.
+struct ColrPaints<'a> {
+    colr: &'a [u8],
+    /// Offset of the base glyph list, if there is one.
+    base_glyphs: Option<usize>,
+    /// Offset of the layer list, if there is one.
+    layers: Option<usize>,
+}
+
+impl ColrPaints<'_> {
+    fn base_glyph_paint(&self, glyph: GlyphId) -> Option<usize> {
+        let list = self.base_glyphs?;
+        let count = u32_at(self.colr, list)? as usize;
+        (0..count)
+            .map(|record| list + 4 + record * 6)
+            .find(|&offset| u16_at(self.colr, offset) == Some(glyph.0))
+            .and_then(|offset| Some(list + u32_at(self.colr, offset + 2)? as usize))
+    }
+
+    /// Flattens the paint at `offset` into layers, with `transform` applied to every outline.
+    fn paint(
+        &self,
+        offset: usize,
+        transform: Affine2,
+        depth: u8,
+        layers: &mut Vec<LayerRecord>,
+    ) -> Option<()> {
+        if depth > MAX_PAINT_DEPTH {
+            return None;
+        }
+        let colr = self.colr;
+        let child = |at: usize| Some(offset + u24_at(colr, offset + at)? as usize);
+
+        match u8_at(colr, offset)? {
+            // PaintColrLayers
+            1 => {
+                let list = self.layers?;
+                let count = u8_at(colr, offset + 1)? as usize;
+                let first = u32_at(colr, offset + 2)? as usize;
+                if first + count > u32_at(colr, list)? as usize {
+                    return None;
+                }
+                for layer in first..first + count {
+                    let paint = list + u32_at(colr, list + 4 + layer * 4)? as usize;
+                    self.paint(paint, transform, depth + 1, layers)?;
+                }
+            }
+            // PaintGlyph
+            10 => {
+                // layers with fills that can't be flattened are left out
+                if let Some(stops) = self.fill(child(1)?, depth + 1) {
+                    layers.push(LayerRecord {
+                        glyph: GlyphId(u16_at(colr, offset + 4)?),
+                        transform,
+                        stops,
+                    });
+                }
+            }
+            // PaintColrGlyph
+            11 => {
+                let glyph = GlyphId(u16_at(colr, offset + 1)?);
+                let paint = self.base_glyph_paint(glyph)?;
+                self.paint(paint, transform, depth + 1, layers)?;
+            }
+            // PaintComposite
+            32 => {
+                self.paint(child(5)?, transform, depth + 1, layers)?;
+                self.paint(child(1)?, transform, depth + 1, layers)?;
+            }
+            format @ 12..=31 => {
+                let local = self.transform(offset, format)?;
+                self.paint(child(1)?, local.then(&transform), depth + 1, layers)?;
+            }
+            // fills outside of a glyph have no outline to paint
+            _ => {}
+        }
+        Some(())
+    }
+
+    /// Colors of the fill at `offset`. Transforms and nested glyphs are skipped to reach it.
+    fn fill(&self, offset: usize, depth: u8) -> Option<Vec<(u16, f32)>> {
+        if depth > MAX_PAINT_DEPTH {
+            return None;
+        }
+        let colr = self.colr;
+
+        match u8_at(colr, offset)? {
+            // PaintSolid and PaintVarSolid
+            2 | 3 => Some(vec![(
+                u16_at(colr, offset + 1)?,
+                f2dot14_at(colr, offset + 3)?,
+            )]),
+            // linear, radial and sweep gradients
+            format @ 4..=9 => {
+                let line = offset + u24_at(colr, offset + 1)? as usize;
+                // variable color lines have a variation index after every stop
+                let stop_size = if format % 2 == 0 { 6 } else { 10 };
+                let count = u16_at(colr, line + 1)? as usize;
+                (0..count)
+                    .map(|stop| {
+                        let stop = line + 3 + stop * stop_size;
+                        Some((u16_at(colr, stop + 2)?, f2dot14_at(colr, stop + 4)?))
+                    })
+                    .collect()
+            }
+            10 | 12..=31 => {
+                let child = offset + u24_at(colr, offset + 1)? as usize;
+                self.fill(child, depth + 1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Transformation of a transform paint. Variable paints use their default values.
+    fn transform(&self, offset: usize, format: u8) -> Option<Affine2> {
+        let colr = self.colr;
+        let f2dot14 = |at: usize| f2dot14_at(colr, offset + at).map(f64::from);
+        let fword = |at: usize| i16_at(colr, offset + at).map(f64::from);
+        let angle = |at: usize| f2dot14(at).map(|angle| angle * PI);
+        let around = |center_at: usize, transform: Affine2| -> Option<Affine2> {
+            let center = Vector2 {
+                x: fword(center_at)?,
+                y: fword(center_at + 2)?,
+            };
+            let to_origin = Affine2::from_translation(Vector2 {
+                x: -center.x,
+                y: -center.y,
+            });
+            Some(
+                to_origin
+                    .then(&transform)
+                    .then(&Affine2::from_translation(center)),
+            )
+        };
+        let scale = |x: f64, y: f64| Affine2::from_scale(Vector2 { x, y });
+        let skew = |x: f64, y: f64| Affine2 {
+            x_axis: Vector2 { x: 1.0, y: y.tan() },
+            y_axis: Vector2 {
+                x: -x.tan(),
+                y: 1.0,
+            },
+            ..Affine2::IDENTITY
+        };
+
+        // variable formats are odd and laid out like the one before them
+        match format & !1 {
+            12 => {
+                let matrix = offset + u24_at(colr, offset + 4)? as usize;
+                let fixed = |at: usize| {
+                    u32_at(colr, matrix + at * 4).map(|value| value as i32 as f64 / 65536.0)
+                };
+                Some(Affine2 {
+                    x_axis: Vector2 {
+                        x: fixed(0)?,
+                        y: fixed(1)?,
+                    },
+                    y_axis: Vector2 {
+                        x: fixed(2)?,
+                        y: fixed(3)?,
+                    },
+                    translation: Vector2 {
+                        x: fixed(4)?,
+                        y: fixed(5)?,
+                    },
+                })
+            }
+            14 => Some(Affine2::from_translation(Vector2 {
+                x: fword(4)?,
+                y: fword(6)?,
+            })),
+            16 => Some(scale(f2dot14(4)?, f2dot14(6)?)),
+            18 => around(8, scale(f2dot14(4)?, f2dot14(6)?)),
+            20 => Some(scale(f2dot14(4)?, f2dot14(4)?)),
+            22 => around(6, scale(f2dot14(4)?, f2dot14(4)?)),
+            24 => Some(Affine2::from_angle(angle(4)?)),
+            26 => around(6, Affine2::from_angle(angle(4)?)),
+            28 => Some(skew(angle(4)?, angle(6)?)),
+            30 => around(8, skew(angle(4)?, angle(6)?)),
+            _ => None,
+        }
+    }
+}
+
+/// Looks up the colors of a fill and averages them.
+pub(crate) fn resolve_color(stops: &[(u16, f32)], palette: &[Rgba<u8>]) -> Option<LayerColor> {
+    let mut colors = vec![];
+    let mut foreground = None;
+    for &(index, alpha) in stops {
+        let alpha = alpha.clamp(0.0, 1.0);
+        if index == FOREGROUND {
+            foreground.get_or_insert(alpha);
+        } else if let Some(color) = palette.get(index as usize) {
+            colors.push((*color, alpha));
+        }
+    }
+
+    if colors.is_empty() {
+        return foreground.map(|alpha| LayerColor::Foreground((alpha * 255.0).round() as u8));
+    }
+    let mut sum = [0.0f32; 4];
+    for (color, alpha) in &colors {
+        for channel in 0..3 {
+            sum[channel] += color[channel] as f32;
+        }
+        sum[3] += color[3] as f32 * alpha;
+    }
+    let average = sum.map(|channel| (channel / colors.len() as f32).round() as u8);
+    Some(LayerColor::Palette(Rgba(average)))
+}
+
+fn u8_at(data: &[u8], offset: usize) -> Option<u8> {
+    data.get(offset).copied()
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn i16_at(data: &[u8], offset: usize) -> Option<i16> {
+    u16_at(data, offset).map(|value| value as i16)
+}
+
+fn u24_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 3)?;
+    Some(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Reads a 2.14 fixed point number.
+fn f2dot14_at(data: &[u8], offset: usize) -> Option<f32> {
+    i16_at(data, offset).map(|value| value as f32 / 16384.0)
+}
+
+/// Reads an offset that is absent when zero.
+fn offset_at(data: &[u8], offset: usize) -> Option<Option<usize>> {
+    let value = u32_at(data, offset)? as usize;
+    Some(if value == 0 { None } else { Some(value) })
+}
@@ -0,0 +1,283 @@
+use crate::geometry::Vec2;
+use crate::{
+    ColoredShape, ColoringSearchConfig, ColoringStrategy, DistanceRange, EdgeColoringConfig,
+    GrayFImage, Projection, SDFTrait, Shape,
+};
+use msdf_sys::*;
+use std::fmt;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+/// Color of an edge, specifying which channels of a multi-channel distance field it contributes
+/// to.
+pub enum EdgeColor {
+    Black = msdfgen_EdgeColor_BLACK as isize,
+    Red = msdfgen_EdgeColor_RED as isize,
+    Green = msdfgen_EdgeColor_GREEN as isize,
+    Yellow = msdfgen_EdgeColor_YELLOW as isize,
+    Blue = msdfgen_EdgeColor_BLUE as isize,
+    Magenta = msdfgen_EdgeColor_MAGENTA as isize,
+    Cyan = msdfgen_EdgeColor_CYAN as isize,
+    White = msdfgen_EdgeColor_WHITE as isize,
+}
+
+impl EdgeColor {
+    /// Number of channels the color covers.
+    pub fn channel_count(self) -> u32 {
+        (self as u32).count_ones()
+    }
+
+    fn from_msdfgen(color: msdfgen_EdgeColor) -> Self {
+        // msdfgen's colors are bit sets of the red, green and blue channels
+        const COLORS: [EdgeColor; 8] = [
+            EdgeColor::Black,
+            EdgeColor::Red,
+            EdgeColor::Green,
+            EdgeColor::Yellow,
+            EdgeColor::Blue,
+            EdgeColor::Magenta,
+            EdgeColor::Cyan,
+            EdgeColor::White,
+        ];
+
+        COLORS[(color & msdfgen_EdgeColor_WHITE) as usize]
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// A violation of the edge coloring rules of the multi-channel distance field technique.
+pub enum EdgeColoringError {
+    /// An edge is present in fewer than two channels.
+    TooFewChannels {
+        contour: usize,
+        edge: usize,
+        color: EdgeColor,
+    },
+    /// Two consecutive edges meet at a corner but share more than one channel.
+    SharedChannels {
+        contour: usize,
+        edge: usize,
+        next_edge: usize,
+        colors: (EdgeColor, EdgeColor),
+    },
+}
+
+impl fmt::Display for EdgeColoringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeColoringError::TooFewChannels {
+                contour,
+                edge,
+                color,
+            } => write!(
+                f,
+                "edge {} of contour {} is {:?}, but every edge must be present in at least two \
+                 channels",
+                edge, contour, color
+            ),
+            EdgeColoringError::SharedChannels {
+                contour,
+                edge,
+                next_edge,
+                colors,
+            } => write!(
+                f,
+                "edges {} ({:?}) and {} ({:?}) of contour {} meet at a corner, but share more than \
+                 one channel",
+                edge, colors.0, next_edge, colors.1, contour
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EdgeColoringError {}
+
+impl Shape {
+    /// Colors of every edge, grouped by contour in the same order as [Shape::contours].
+    pub fn edge_colors(&self) -> Vec<Vec<EdgeColor>> {
+        // the shim only reads through these pointers
+        let shape = &self.shape as *const msdfgen_Shape as *mut msdfgen_Shape;
+
+        unsafe {
+            (0..msdfsys_shapeContourCount(shape))
+                .map(|contour_index| {
+                    let contour = msdfsys_shapeContour(shape, contour_index);
+                    (0..msdfsys_contourEdgeCount(contour))
+                        .map(|edge_index| {
+                            let edge = msdfsys_contourEdge(contour, edge_index);
+                            EdgeColor::from_msdfgen((*edge).color)
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+    }
+
+    /// Sets the color of a single edge, addressed the same way as in [Shape::contours]. Panics if
+    /// either index is out of bounds.
+    pub fn set_edge_color(&mut self, contour: usize, edge: usize, color: EdgeColor) {
+        unsafe {
+            let contour_count = msdfsys_shapeContourCount(&self.shape) as usize;
+            assert!(
+                contour < contour_count,
+                "contour index {} out of bounds for shape with {} contours",
+                contour,
+                contour_count
+            );
+            let contour = msdfsys_shapeContour(&mut self.shape, contour as _);
+
+            let edge_count = msdfsys_contourEdgeCount(contour) as usize;
+            assert!(
+                edge < edge_count,
+                "edge index {} out of bounds for contour with {} edges",
+                edge,
+                edge_count
+            );
+            let edge = msdfsys_contourEdge(contour, edge as _);
+
+            (*edge).color = color as msdfgen_EdgeColor;
+        }
+    }
+
+    /// Checks that the current edge colors follow the rules of the multi-channel distance field
+    /// technique: every edge is present in at least two channels, and edges meeting at a corner
+    /// share at most one channel. The corners of a contour made of a single edge aren't checked,
+    /// since its edge can only meet itself. `angle` is the corner angle as given to the coloring functions.
+    pub fn validate_coloring(&self, angle: f64) -> Result<(), EdgeColoringError> {
+        let cross_threshold = angle.sin();
+
+        for (contour_index, (edges, colors)) in
+            self.contours().iter().zip(self.edge_colors()).enumerate()
+        {
+            for (edge_index, &color) in colors.iter().enumerate() {
+                if color.channel_count() < 2 {
+                    return Err(EdgeColoringError::TooFewChannels {
+                        contour: contour_index,
+                        edge: edge_index,
+                        color,
+                    });
+                }
+            }
+
+            // a contour of a single edge only meets itself, and an edge can't use different
+            // channels on either side of a corner, so only its channel count is checked above
+            if edges.len() < 2 {
+                continue;
+            }
+
+            for edge_index in 0..edges.len() {
+                let next_index = (edge_index + 1) % edges.len();
+                let incoming = Vec2::from(edges[edge_index].direction(1.0)).normalize();
+                let outgoing = Vec2::from(edges[next_index].direction(0.0)).normalize();
+
+                // same corner test as msdfgen's edge coloring
+                let corner = incoming.dot(outgoing) <= 0.0
+                    || incoming.cross(outgoing).abs() > cross_threshold;
+                let shared = colors[edge_index] as u32 & colors[next_index] as u32;
+
+                if corner && shared.count_ones() > 1 {
+                    return Err(EdgeColoringError::SharedChannels {
+                        contour: contour_index,
+                        edge: edge_index,
+                        next_edge: next_index,
+                        colors: (colors[edge_index], colors[next_index]),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tries every coloring strategy with several seeds and returns the coloring whose MSDF
+    /// renders closest to the exact distance field, along with its score. The score is the mean
+    /// absolute difference between both renders, from 0 for a perfect match to 1. Fields are
+    /// generated with the given size, range and projection, so these should match the intended
+    /// use of the shape.
+    pub fn color_edges_best(
+        self,
+        width: u32,
+        height: u32,
+        range: impl Into<DistanceRange>,
+        projection: &Projection,
+        config: &ColoringSearchConfig,
+    ) -> (ColoredShape, f64) {
+        let range = range.into();
+        range.assert_valid();
+        let render_width = width * config.render_scale;
+        let render_height = height * config.render_scale;
+
+        let exact = self
+            .generate_sdf(width, height, range, projection, &Default::default())
+            .render(render_width, render_height);
+
+        let strategies = [
+            ColoringStrategy::Simple,
+            ColoringStrategy::InkTrap,
+            ColoringStrategy::ByDistance,
+        ];
+
+        let mut best: Option<(ColoredShape, f64)> = None;
+        for strategy in strategies {
+            for seed in 0..config.seeds.max(1) {
+                let coloring = EdgeColoringConfig {
+                    angle: config.angle,
+                    seed,
+                };
+                let candidate = self.clone().color_edges(strategy, &coloring);
+
+                let rendered = candidate
+                    .generate_msdf(width, height, range, projection, &config.msdf_config)
+                    .render(render_width, render_height);
+                let score = render_difference(&rendered, &exact);
+
+                // ties keep the earlier candidate, favoring the cheaper strategies and low seeds
+                let better = match &best {
+                    Some((_, best)) => score < *best,
+                    None => true,
+                };
+                if better {
+                    best = Some((candidate, score));
+                }
+            }
+        }
+
+        best.unwrap()
+    }
+
+    /// Validates manually assigned edge colors and marks the shape as colored, making it usable
+    /// for MSDF and MTSDF generation.
+    pub fn into_colored(self, angle: f64) -> Result<ColoredShape, EdgeColoringError> {
+        self.validate_coloring(angle)?;
+
+        Ok(ColoredShape(self))
+    }
+}
+
+pub(crate) fn render_difference(a: &GrayFImage, b: &GrayFImage) -> f64 {
+    let total = a
+        .pixels()
+        .zip(b.pixels())
+        .map(|(a, b)| (a.0[0].clamp(0.0, 1.0) - b.0[0].clamp(0.0, 1.0)).abs() as f64)
+        .sum::<f64>();
+
+    total / (a.width() as f64 * a.height() as f64).max(1.0)
+}
+
+impl ColoredShape {
+    /// See [Shape::edge_colors].
+    pub fn edge_colors(&self) -> Vec<Vec<EdgeColor>> {
+        self.0.edge_colors()
+    }
+
+    /// See [Shape::set_edge_color]. Overriding colors may break the coloring rules, which can be
+    /// checked with [ColoredShape::validate_coloring].
+    pub fn set_edge_color(&mut self, contour: usize, edge: usize, color: EdgeColor) {
+        self.0.set_edge_color(contour, edge, color)
+    }
+
+    /// See [Shape::validate_coloring].
+    pub fn validate_coloring(&self, angle: f64) -> Result<(), EdgeColoringError> {
+        self.0.validate_coloring(angle)
+    }
+}
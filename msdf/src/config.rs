@@ -2,6 +2,22 @@ use mint::Vector2;
 use msdf_sys::*;
 use std::ptr;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Selects between msdfgen's current generators and the legacy ones kept for compatibility.
+pub enum Generator {
+    /// The current generators, supporting overlapping contours.
+    Modern,
+    /// The legacy generators, which ignore `overlap_support`. Useful for reproducing fields that
+    /// were generated with older versions of msdfgen.
+    Legacy,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Generator::Modern
+    }
+}
+
 #[derive(Copy, Clone)]
 /// Configuration for single-channel SDF calculation.
 pub struct SDFConfig {
@@ -9,12 +25,53 @@ pub struct SDFConfig {
     /// with the same winding. May be set to false to improve performance when no such contours
     /// are present.
     pub overlap_support: bool,
+    /// The generator used to compute the field.
+    pub generator: Generator,
 }
 
 impl Default for SDFConfig {
     fn default() -> Self {
         SDFConfig {
             overlap_support: true,
+            generator: Default::default(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Strategy used to assign edge colors for multi-channel distance field generation.
+pub enum ColoringStrategy {
+    /// See [Shape::color_edges_simple](crate::Shape::color_edges_simple).
+    Simple,
+    /// See [Shape::color_edges_ink_trap](crate::Shape::color_edges_ink_trap).
+    InkTrap,
+    /// See [Shape::color_edges_by_distance](crate::Shape::color_edges_by_distance).
+    ByDistance,
+}
+
+impl Default for ColoringStrategy {
+    fn default() -> Self {
+        ColoringStrategy::Simple
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+/// Configuration for edge coloring.
+pub struct EdgeColoringConfig {
+    /// The maximum angle (in radians) to be considered a corner, for example 3 (~172 degrees).
+    /// Values below 1/2 PI will be treated as the external angle.
+    pub angle: f64,
+    /// Seed for the choice of colors. Colorings only depend on the shape, angle and seed, and are
+    /// identical across platforms, so trying several seeds is a valid way of searching for the
+    /// best coloring of a glyph.
+    pub seed: u64,
+}
+
+impl Default for EdgeColoringConfig {
+    fn default() -> Self {
+        EdgeColoringConfig {
+            angle: 3.0,
+            seed: 0,
         }
     }
 }
@@ -99,6 +156,8 @@ pub struct MSDFConfig {
     pub overlap_support: bool,
     /// The configuration for the MSDF error correction pass.
     pub error_correction_config: ErrorCorrectionConfig,
+    /// The generator used to compute the field.
+    pub generator: Generator,
 }
 
 impl Default for MSDFConfig {
@@ -106,6 +165,7 @@ impl Default for MSDFConfig {
         MSDFConfig {
             overlap_support: true,
             error_correction_config: Default::default(),
+            generator: Default::default(),
         }
     }
 }
@@ -129,6 +189,7 @@ impl MSDFConfig {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 /// Specifies scale and translation for SDF generation.
 pub struct Projection {
     /// Scale for SDF generation.
@@ -160,3 +221,28 @@ impl Default for Projection {
         }
     }
 }
+
+#[derive(Copy, Clone)]
+/// Configuration for [Shape::color_edges_best](crate::Shape::color_edges_best).
+pub struct ColoringSearchConfig {
+    /// The maximum angle (in radians) to be considered a corner, as given to the coloring
+    /// functions.
+    pub angle: f64,
+    /// Number of seeds tried for each coloring strategy, counting up from 0.
+    pub seeds: u64,
+    /// Size of the rendered images used for scoring, as a multiple of the field size.
+    pub render_scale: u32,
+    /// Configuration used to generate the candidate fields.
+    pub msdf_config: MSDFConfig,
+}
+
+impl Default for ColoringSearchConfig {
+    fn default() -> Self {
+        ColoringSearchConfig {
+            angle: 3.0,
+            seeds: 4,
+            render_scale: 4,
+            msdf_config: Default::default(),
+        }
+    }
+}
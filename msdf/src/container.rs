@@ -0,0 +1,459 @@
+use crate::{Projection, RangeUnits, SDFTrait};
+use image::{ImageBuffer, Pixel};
+use mint::Vector2;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// Magic bytes at the start of every container.
+const MAGIC: [u8; 4] = *b"MSDF";
+/// Version written by this crate. Readers accept this and all earlier versions.
+pub const CONTAINER_VERSION: u16 = 1;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// How pixel values are stored in a container.
+pub enum SampleFormat {
+    /// Unmodified 32-bit floats.
+    F32,
+    /// Values clamped to `[0, 1]` and quantized to 8 bits.
+    U8,
+    /// Values clamped to `[0, 1]` and quantized to 16 bits.
+    U16,
+}
+
+impl Default for SampleFormat {
+    fn default() -> Self {
+        SampleFormat::F32
+    }
+}
+
+impl SampleFormat {
+    fn id(self) -> u8 {
+        match self {
+            SampleFormat::F32 => 0,
+            SampleFormat::U8 => 1,
+            SampleFormat::U16 => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(SampleFormat::F32),
+            1 => Some(SampleFormat::U8),
+            2 => Some(SampleFormat::U16),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Everything stored in a container besides the pixels.
+pub struct ContainerHeader {
+    /// Format version the container was written with.
+    pub version: u16,
+    /// Number of channels per pixel: 1 for [SDF](crate::SDF), 3 for [MSDF](crate::MSDF) and 4 for
+    /// [MTSDF](crate::MTSDF).
+    pub channels: u8,
+    /// How the pixels are stored.
+    pub format: SampleFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Range of the field, in `units`.
+    pub range: f64,
+    pub units: RangeUnits,
+    pub mid_value: f32,
+    /// Projection the field was generated with, if it was stored.
+    pub projection: Option<Projection>,
+}
+
+impl ContainerHeader {
+    /// Reads and validates the header at the start of a container.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, ContainerError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(ContainerError::InvalidMagic);
+        }
+
+        let version = u16::from_le_bytes(read_bytes(&mut reader)?);
+        if version == 0 || version > CONTAINER_VERSION {
+            return Err(ContainerError::UnsupportedVersion(version));
+        }
+
+        let [channels, format] = read_bytes(&mut reader)?;
+        let format =
+            SampleFormat::from_id(format).ok_or(ContainerError::InvalidSampleFormat(format))?;
+        let width = u32::from_le_bytes(read_bytes(&mut reader)?);
+        let height = u32::from_le_bytes(read_bytes(&mut reader)?);
+        let range = read_f64(&mut reader)?;
+        let mid_value = f32::from_le_bytes(read_bytes(&mut reader)?);
+
+        let [has_units_scale] = read_bytes(&mut reader)?;
+        let units_scale = read_vector(&mut reader)?;
+        let units = if has_units_scale != 0 {
+            RangeUnits::ShapeUnits { scale: units_scale }
+        } else {
+            RangeUnits::Pixels
+        };
+
+        let [has_projection] = read_bytes(&mut reader)?;
+        let scale = read_vector(&mut reader)?;
+        let translation = read_vector(&mut reader)?;
+        let projection = (has_projection != 0).then_some(Projection { scale, translation });
+
+        Ok(ContainerHeader {
+            version,
+            channels,
+            format,
+            width,
+            height,
+            range,
+            units,
+            mid_value,
+            projection,
+        })
+    }
+
+    fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&self.version.to_le_bytes())?;
+        writer.write_all(&[self.channels, self.format.id()])?;
+        writer.write_all(&self.width.to_le_bytes())?;
+        writer.write_all(&self.height.to_le_bytes())?;
+        writer.write_all(&self.range.to_le_bytes())?;
+        writer.write_all(&self.mid_value.to_le_bytes())?;
+
+        // optional values are always written, so that the header has a fixed size
+        let zero = Vector2 { x: 0.0, y: 0.0 };
+        match self.units {
+            RangeUnits::Pixels => write_optional_vector(&mut writer, None, zero)?,
+            RangeUnits::ShapeUnits { scale } => {
+                write_optional_vector(&mut writer, Some(scale), zero)?
+            }
+        }
+        let projection = self.projection.as_ref();
+        write_optional_vector(&mut writer, projection.map(|p| p.scale), zero)?;
+        write_vector(&mut writer, projection.map_or(zero, |p| p.translation))
+    }
+
+    fn sample_count(&self) -> Result<usize, ContainerError> {
+        (self.width as usize)
+            .checked_mul(self.height as usize)
+            .and_then(|pixels| pixels.checked_mul(self.channels as usize))
+            .ok_or(ContainerError::TooLarge)
+    }
+}
+
+#[derive(Debug)]
+/// Errors emitted while reading a container.
+pub enum ContainerError {
+    Io(io::Error),
+    /// The data does not start with the container's magic bytes.
+    InvalidMagic,
+    /// The container was written by a newer version of this crate.
+    UnsupportedVersion(u16),
+    InvalidSampleFormat(u8),
+    /// The container holds a different type of field than requested.
+    ChannelMismatch {
+        expected: u8,
+        found: u8,
+    },
+    /// The dimensions of the container hold more samples than can be addressed.
+    TooLarge,
+}
+
+impl fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerError::Io(error) => write!(f, "failed to read container: {}", error),
+            ContainerError::InvalidMagic => write!(f, "not a distance field container"),
+            ContainerError::UnsupportedVersion(version) => {
+                write!(f, "unsupported container version {}", version)
+            }
+            ContainerError::InvalidSampleFormat(format) => {
+                write!(f, "unknown sample format {}", format)
+            }
+            ContainerError::ChannelMismatch { expected, found } => write!(
+                f,
+                "expected a field with {} channels, but the container has {}",
+                expected, found
+            ),
+            ContainerError::TooLarge => write!(f, "the container's dimensions are too large"),
+        }
+    }
+}
+
+impl std::error::Error for ContainerError {}
+
+impl From<io::Error> for ContainerError {
+    fn from(error: io::Error) -> Self {
+        ContainerError::Io(error)
+    }
+}
+
+/// Distance fields that can be stored in a self-describing binary container, which keeps the
+/// range, mid-value and optionally the projection together with the pixels. All values are
+/// little-endian.
+pub trait ContainerField: SDFTrait + Sized {
+    /// Writes the field. `projection` is stored alongside it if given.
+    fn write_container<W: Write>(
+        &self,
+        writer: W,
+        format: SampleFormat,
+        projection: Option<&Projection>,
+    ) -> io::Result<()>;
+
+    /// Reads a field written by [ContainerField::write_container], along with its header.
+    fn read_container<R: Read>(reader: R) -> Result<(Self, ContainerHeader), ContainerError>;
+}
+
+impl<T, P> ContainerField for T
+where
+    T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    fn write_container<W: Write>(
+        &self,
+        mut writer: W,
+        format: SampleFormat,
+        projection: Option<&Projection>,
+    ) -> io::Result<()> {
+        let header = ContainerHeader {
+            version: CONTAINER_VERSION,
+            channels: P::CHANNEL_COUNT,
+            format,
+            width: self.image().width(),
+            height: self.image().height(),
+            range: self.range(),
+            units: self.range_units(),
+            mid_value: self.mid_value(),
+            projection: projection.copied(),
+        };
+        header.write(&mut writer)?;
+
+        for &sample in self.image().iter() {
+            match format {
+                SampleFormat::F32 => writer.write_all(&sample.to_le_bytes())?,
+                SampleFormat::U8 => writer.write_all(&[quantize(sample, u8::MAX as f32) as u8])?,
+                SampleFormat::U16 => {
+                    writer.write_all(&(quantize(sample, u16::MAX as f32) as u16).to_le_bytes())?
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_container<R: Read>(mut reader: R) -> Result<(Self, ContainerHeader), ContainerError> {
+        let header = ContainerHeader::read(&mut reader)?;
+        if header.channels != P::CHANNEL_COUNT {
+            return Err(ContainerError::ChannelMismatch {
+                expected: P::CHANNEL_COUNT,
+                found: header.channels,
+            });
+        }
+
+        // don't trust the header with a large allocation, a truncated container fails to read
+        let sample_count = header.sample_count()?;
+        let mut samples = Vec::with_capacity(sample_count.min(1 << 20));
+        for _ in 0..sample_count {
+            samples.push(match header.format {
+                SampleFormat::F32 => f32::from_le_bytes(read_bytes(&mut reader)?),
+                SampleFormat::U8 => u8::from_le_bytes(read_bytes(&mut reader)?) as f32 / 255.0,
+                SampleFormat::U16 => {
+                    u16::from_le_bytes(read_bytes(&mut reader)?) as f32 / u16::MAX as f32
+                }
+            });
+        }
+
+        let image = ImageBuffer::from_raw(header.width, header.height, samples)
+            .ok_or(ContainerError::TooLarge)?;
+        let field =
+            T::from_image(image, header.range, header.mid_value).with_range_units(header.units);
+
+        Ok((field, header))
+    }
+}
+
+fn quantize(sample: f32, max: f32) -> f32 {
+    (sample.clamp(0.0, 1.0) * max).round()
+}
+
+fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_f64<R: Read>(reader: &mut R) -> io::Result<f64> {
+    Ok(f64::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_vector<R: Read>(reader: &mut R) -> io::Result<Vector2<f64>> {
+    Ok(Vector2 {
+        x: read_f64(reader)?,
+        y: read_f64(reader)?,
+    })
+}
+
+fn write_vector<W: Write>(writer: &mut W, vector: Vector2<f64>) -> io::Result<()> {
+    writer.write_all(&vector.x.to_le_bytes())?;
+    writer.write_all(&vector.y.to_le_bytes())
+}
+
+fn write_optional_vector<W: Write>(
+    writer: &mut W,
+    vector: Option<Vector2<f64>>,
+    default: Vector2<f64>,
+) -> io::Result<()> {
+    writer.write_all(&[vector.is_some() as u8])?;
+    write_vector(writer, vector.unwrap_or(default))
+}
+
+#[cfg(feature = "serde")]
+/// A field together with the projection it was generated with. Serializes like a field, with the
+/// projection added the way the binary container stores it.
+pub struct ProjectedField<T> {
+    pub field: T,
+    pub projection: Option<Projection>,
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::*;
+    use crate::{MSDF, MTSDF, SDF};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serialized form of a field, mirroring the binary container with float samples.
+    #[derive(Serialize, Deserialize)]
+    struct SerializedField {
+        width: u32,
+        height: u32,
+        channels: u8,
+        range: f64,
+        /// Projection scale of a range in shape units, absent for a range in pixels.
+        units_scale: Option<[f64; 2]>,
+        mid_value: f32,
+        /// Scale and translation of the projection, if one was stored.
+        #[serde(default)]
+        projection: Option<[[f64; 2]; 2]>,
+        samples: Vec<f32>,
+    }
+
+    fn serialize<T, P, S>(
+        field: &T,
+        projection: Option<&Projection>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+        P: Pixel<Subpixel = f32> + 'static,
+        S: Serializer,
+    {
+        SerializedField {
+            width: field.image().width(),
+            height: field.image().height(),
+            channels: P::CHANNEL_COUNT,
+            range: field.range(),
+            units_scale: match field.range_units() {
+                RangeUnits::Pixels => None,
+                RangeUnits::ShapeUnits { scale } => Some([scale.x, scale.y]),
+            },
+            mid_value: field.mid_value(),
+            projection: projection
+                .map(|p| [[p.scale.x, p.scale.y], [p.translation.x, p.translation.y]]),
+            samples: field.image().as_raw().clone(),
+        }
+        .serialize(serializer)
+    }
+
+    fn deserialize<'de, T, P, D>(deserializer: D) -> Result<ProjectedField<T>, D::Error>
+    where
+        T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+        P: Pixel<Subpixel = f32> + 'static,
+        D: Deserializer<'de>,
+    {
+        let field = SerializedField::deserialize(deserializer)?;
+        if field.channels != P::CHANNEL_COUNT {
+            return Err(D::Error::custom(ContainerError::ChannelMismatch {
+                expected: P::CHANNEL_COUNT,
+                found: field.channels,
+            }));
+        }
+
+        let image = ImageBuffer::from_raw(field.width, field.height, field.samples)
+            .ok_or_else(|| D::Error::custom("sample count does not match the dimensions"))?;
+        let units = match field.units_scale {
+            None => RangeUnits::Pixels,
+            Some([x, y]) => RangeUnits::ShapeUnits {
+                scale: Vector2 { x, y },
+            },
+        };
+
+        let projection = field.projection.map(|[[sx, sy], [tx, ty]]| Projection {
+            scale: Vector2 { x: sx, y: sy },
+            translation: Vector2 { x: tx, y: ty },
+        });
+
+        Ok(ProjectedField {
+            field: T::from_image(image, field.range, field.mid_value).with_range_units(units),
+            projection,
+        })
+    }
+
+    impl<T, P> Serialize for ProjectedField<T>
+    where
+        T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+        P: Pixel<Subpixel = f32> + 'static,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(&self.field, self.projection.as_ref(), serializer)
+        }
+    }
+
+    impl<'de, T, P> Deserialize<'de> for ProjectedField<T>
+    where
+        T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+        P: Pixel<Subpixel = f32> + 'static,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize(deserializer)
+        }
+    }
+
+    impl Serialize for SDF {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self, None, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SDF {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(deserialize(deserializer)?.field)
+        }
+    }
+
+    impl Serialize for MSDF {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self, None, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for MSDF {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(deserialize(deserializer)?.field)
+        }
+    }
+
+    impl Serialize for MTSDF {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self, None, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for MTSDF {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(deserialize(deserializer)?.field)
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use crate::bc::{decode_bc4, decode_bc7, encode_bc4, encode_bc7};
+use crate::{FieldFileError, SDFTrait};
+use half::f16;
+use image::{ImageBuffer, Pixel};
+use std::io::{Read, Write};
+
+/// Magic bytes at the start of every DDS file.
+const MAGIC: [u8; 4] = *b"DDS ";
+/// Size of `DDS_HEADER`, excluding the magic bytes.
+const HEADER_SIZE: usize = 124;
+/// Size of `DDS_HEADER_DXT10`, which follows the header.
+const DX10_HEADER_SIZE: usize = 20;
+const DATA_OFFSET: usize = 4 + HEADER_SIZE + DX10_HEADER_SIZE;
+
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PITCH: u32 = 0x8;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_LINEARSIZE: u32 = 0x80000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Pixel formats a DDS file can be written with. Channels of the field are stored in order,
+/// missing color channels are set to 0 and a missing alpha channel to 1.
+pub enum DdsFormat {
+    /// `DXGI_FORMAT_R8_UNORM`, for [SDF](crate::SDF)s.
+    R8,
+    /// `DXGI_FORMAT_R8G8B8A8_UNORM`.
+    RGBA8,
+    /// `DXGI_FORMAT_R16_FLOAT`, for [SDF](crate::SDF)s.
+    R16F,
+    /// `DXGI_FORMAT_R16G16B16A16_FLOAT`.
+    RGBA16F,
+    /// `DXGI_FORMAT_R32_FLOAT`, for [SDF](crate::SDF)s.
+    R32F,
+    /// `DXGI_FORMAT_R32G32B32A32_FLOAT`.
+    RGBA32F,
+    /// `DXGI_FORMAT_BC4_UNORM`, a block compressed format for [SDF](crate::SDF)s with 3-bit
+    /// indices between two 8-bit endpoints per 4x4 block.
+    BC4,
+    /// `DXGI_FORMAT_BC7_UNORM`, a block compressed format for [MSDF](crate::MSDF)s and
+    /// [MTSDF](crate::MTSDF)s. Blocks are always encoded with a single pair of endpoints and
+    /// 4-bit indices (mode 6).
+    BC7,
+}
+
+impl Default for DdsFormat {
+    fn default() -> Self {
+        DdsFormat::RGBA8
+    }
+}
+
+impl DdsFormat {
+    /// Number of channels per pixel.
+    pub fn channels(self) -> usize {
+        match self {
+            DdsFormat::R8 | DdsFormat::R16F | DdsFormat::R32F | DdsFormat::BC4 => 1,
+            DdsFormat::RGBA8 | DdsFormat::RGBA16F | DdsFormat::RGBA32F | DdsFormat::BC7 => 4,
+        }
+    }
+
+    /// The `DXGI_FORMAT` stored in the header.
+    pub fn dxgi_format(self) -> u32 {
+        match self {
+            DdsFormat::R8 => 61,
+            DdsFormat::RGBA8 => 28,
+            DdsFormat::R16F => 54,
+            DdsFormat::RGBA16F => 10,
+            DdsFormat::R32F => 41,
+            DdsFormat::RGBA32F => 2,
+            DdsFormat::BC4 => 80,
+            DdsFormat::BC7 => 98,
+        }
+    }
+
+    /// Whether the format is block compressed.
+    pub fn is_compressed(self) -> bool {
+        matches!(self, DdsFormat::BC4 | DdsFormat::BC7)
+    }
+
+    fn from_dxgi_format(format: u32) -> Option<Self> {
+        [
+            DdsFormat::R8,
+            DdsFormat::RGBA8,
+            DdsFormat::R16F,
+            DdsFormat::RGBA16F,
+            DdsFormat::R32F,
+            DdsFormat::RGBA32F,
+            DdsFormat::BC4,
+            DdsFormat::BC7,
+        ]
+        .into_iter()
+        .find(|f| f.dxgi_format() == format)
+    }
+
+    /// Size of a single channel of an uncompressed format in bytes.
+    fn sample_size(self) -> usize {
+        match self {
+            DdsFormat::R8 | DdsFormat::RGBA8 => 1,
+            DdsFormat::R16F | DdsFormat::RGBA16F => 2,
+            DdsFormat::R32F | DdsFormat::RGBA32F => 4,
+            DdsFormat::BC4 | DdsFormat::BC7 => 0,
+        }
+    }
+
+    /// Size of the pixels of an image, and of a row of pixels or blocks. `None` if the size
+    /// overflows.
+    fn data_size(self, width: u32, height: u32) -> Option<(usize, usize)> {
+        let (width, height) = (width as usize, height as usize);
+        let (row, rows) = match self {
+            DdsFormat::BC4 => (blocks(width).checked_mul(8)?, blocks(height)),
+            DdsFormat::BC7 => (blocks(width).checked_mul(16)?, blocks(height)),
+            _ => (
+                width.checked_mul(self.channels() * self.sample_size())?,
+                height,
+            ),
+        };
+        Some((row.checked_mul(rows)?, row))
+    }
+}
+
+/// Distance fields that can be written as DDS textures, uncompressed or block compressed.
+/// DDS files can't store the range and mid-value of a field, so they have to be given again when
+/// reading, like for [SDFTrait::from_image].
+pub trait DdsField: SDFTrait + Sized {
+    /// Writes the field as a DDS texture with a `DX10` header. Fails if `format` has fewer
+    /// channels than the field.
+    fn write_dds<W: Write>(&self, writer: W, format: DdsFormat) -> Result<(), FieldFileError>;
+
+    /// Reads a texture written by [DdsField::write_dds], decompressing it if needed. `range` is
+    /// in pixels.
+    fn read_dds<R: Read>(reader: R, range: f64, mid_value: f32) -> Result<Self, FieldFileError>;
+}
+
+impl<T, P> DdsField for T
+where
+    T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    fn write_dds<W: Write>(&self, mut writer: W, format: DdsFormat) -> Result<(), FieldFileError> {
+        if format.channels() < P::CHANNEL_COUNT as usize {
+            return Err(FieldFileError::UnsupportedPixelFormat);
+        }
+
+        let (width, height) = self.image().dimensions();
+        let (data_size, pitch) = format
+            .data_size(width, height)
+            .ok_or(FieldFileError::Malformed("image too large for a DDS file"))?;
+        let (size_flag, pitch_or_linear_size) = if format.is_compressed() {
+            (DDSD_LINEARSIZE, data_size)
+        } else {
+            (DDSD_PITCH, pitch)
+        };
+        let pitch_or_linear_size = u32::try_from(pitch_or_linear_size)
+            .map_err(|_| FieldFileError::Malformed("image too large for a DDS file"))?;
+
+        let mut header = Vec::with_capacity(DATA_OFFSET);
+        header.extend_from_slice(&MAGIC);
+        for value in [
+            HEADER_SIZE as u32,
+            DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT | size_flag,
+            height,
+            width,
+            pitch_or_linear_size,
+            0, // depth
+            0, // mipmap count
+        ] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        header.extend_from_slice(&[0; 44]);
+        // pixel format, deferring to the DX10 header
+        for value in [32, DDPF_FOURCC, u32::from_le_bytes(*b"DX10"), 0, 0, 0, 0, 0] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [DDSCAPS_TEXTURE, 0, 0, 0, 0] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [
+            format.dxgi_format(),
+            D3D10_RESOURCE_DIMENSION_TEXTURE2D,
+            0, // flags
+            1, // array size
+            0, // alpha mode
+        ] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+
+        writer.write_all(&header)?;
+        writer.write_all(&encode(self.image(), format))?;
+        Ok(())
+    }
+
+    fn read_dds<R: Read>(
+        mut reader: R,
+        range: f64,
+        mid_value: f32,
+    ) -> Result<Self, FieldFileError> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        if data.get(..4) != Some(&MAGIC[..]) {
+            return Err(FieldFileError::Malformed("missing DDS magic"));
+        }
+        if read_u32(&data, 4 + 80)? != u32::from_le_bytes(*b"DX10") {
+            return Err(FieldFileError::UnsupportedPixelFormat);
+        }
+
+        let height = read_u32(&data, 4 + 8)?;
+        let width = read_u32(&data, 4 + 12)?;
+        let format = DdsFormat::from_dxgi_format(read_u32(&data, 4 + HEADER_SIZE)?)
+            .ok_or(FieldFileError::UnsupportedPixelFormat)?;
+        if format.channels() < P::CHANNEL_COUNT as usize {
+            return Err(FieldFileError::ChannelMismatch {
+                expected: P::CHANNEL_COUNT,
+                found: format.channels() as u8,
+            });
+        }
+
+        let (data_size, _) = format
+            .data_size(width, height)
+            .ok_or(FieldFileError::Malformed("image too large"))?;
+        let pixels = DATA_OFFSET
+            .checked_add(data_size)
+            .and_then(|end| data.get(DATA_OFFSET..end))
+            .ok_or(FieldFileError::Malformed("unexpected end of file"))?;
+        let samples = decode(pixels, width, height, format, P::CHANNEL_COUNT as usize)?;
+
+        // the sample count always matches the dimensions
+        let image = ImageBuffer::from_raw(width, height, samples).unwrap();
+        Ok(T::from_image(image, range, mid_value))
+    }
+}
+
+/// Value of channel `channel` of a pixel, after padding it to 4 channels.
+fn padded_channel(channels: &[f32], channel: usize) -> f32 {
+    let default = if channel == 3 { 1.0 } else { 0.0 };
+    channels.get(channel).copied().unwrap_or(default)
+}
+
+fn encode<P>(image: &ImageBuffer<P, Vec<f32>>, format: DdsFormat) -> Vec<u8>
+where
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    let (width, height) = image.dimensions();
+    // the size was checked before writing the header
+    let (data_size, _) = format.data_size(width, height).unwrap_or_default();
+    let mut output = Vec::with_capacity(data_size);
+
+    if !format.is_compressed() {
+        for pixel in image.pixels() {
+            for channel in 0..format.channels() {
+                let value = padded_channel(pixel.channels(), channel);
+                match format.sample_size() {
+                    1 => output.push((value.clamp(0.0, 1.0) * 255.0).round() as u8),
+                    2 => output.extend_from_slice(&f16::from_f32(value).to_bits().to_le_bytes()),
+                    _ => output.extend_from_slice(&value.to_le_bytes()),
+                }
+            }
+        }
+        return output;
+    }
+
+    for block_y in 0..blocks(height as usize) as u32 {
+        for block_x in 0..blocks(width as usize) as u32 {
+            // blocks past the edge of the image repeat its last row and column
+            let mut pixels = [[0.0; 4]; 16];
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                let x = (block_x * 4 + i as u32 % 4).min(width - 1);
+                let y = (block_y * 4 + i as u32 / 4).min(height - 1);
+                let channels = image.get_pixel(x, y).channels();
+                for (channel, value) in pixel.iter_mut().enumerate() {
+                    *value = padded_channel(channels, channel).clamp(0.0, 1.0) * 255.0;
+                }
+            }
+
+            match format {
+                DdsFormat::BC4 => output.extend_from_slice(&encode_bc4(&pixels.map(|p| p[0]))),
+                _ => output.extend_from_slice(&encode_bc7(&pixels)),
+            }
+        }
+    }
+    output
+}
+
+fn decode(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    format: DdsFormat,
+    channels: usize,
+) -> Result<Vec<f32>, FieldFileError> {
+    let (width, height) = (width as usize, height as usize);
+    let sample_count = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or(FieldFileError::Malformed("image too large"))?;
+    let mut samples = vec![0.0; sample_count];
+
+    if !format.is_compressed() {
+        let sample_size = format.sample_size();
+        for (i, texel) in data
+            .chunks_exact(format.channels() * sample_size)
+            .enumerate()
+        {
+            for (channel, bytes) in texel.chunks_exact(sample_size).take(channels).enumerate() {
+                samples[i * channels + channel] = match sample_size {
+                    1 => bytes[0] as f32 / 255.0,
+                    2 => f16::from_bits(u16::from_le_bytes([bytes[0], bytes[1]])).to_f32(),
+                    _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                };
+            }
+        }
+        return Ok(samples);
+    }
+
+    let block_size = if format == DdsFormat::BC4 { 8 } else { 16 };
+    for (i, block) in data.chunks_exact(block_size).enumerate() {
+        let pixels = match format {
+            DdsFormat::BC4 => decode_bc4(block.try_into().unwrap()).map(|v| [v, 0.0, 0.0, 0.0]),
+            _ => decode_bc7(block.try_into().unwrap())
+                .ok_or(FieldFileError::Malformed("unsupported BC7 block mode"))?,
+        };
+
+        let (block_x, block_y) = (i % blocks(width) * 4, i / blocks(width) * 4);
+        for (j, pixel) in pixels.iter().enumerate() {
+            let (x, y) = (block_x + j % 4, block_y + j / 4);
+            if x >= width || y >= height {
+                continue;
+            }
+            for channel in 0..channels {
+                samples[(y * width + x) * channels + channel] = pixel[channel] / 255.0;
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// Number of 4x4 blocks covering `pixels` pixels.
+fn blocks(pixels: usize) -> usize {
+    (pixels + 3) >> 2
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, FieldFileError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(FieldFileError::Malformed("unexpected end of file"))
+}
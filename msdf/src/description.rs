@@ -0,0 +1,299 @@
+use crate::geometry::Vec2;
+use crate::{EdgeColor, Segment, Shape};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Error returned when a textual shape, such as a shape description or an SVG path, can't be
+/// parsed.
+pub struct ParseShapeError {
+    /// Byte offset into the input at which parsing failed.
+    pub offset: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.reason, self.offset)
+    }
+}
+
+impl std::error::Error for ParseShapeError {}
+
+/// Contours parsed from text, with the color of every segment.
+pub(crate) type ParsedContours = Vec<Vec<(Segment, EdgeColor)>>;
+
+impl Shape {
+    /// Parses msdfgen's shape description format, for example
+    /// `{ -1, -1; m; -1, 1; y; 1, 1; m; 1, -1; y; # }`. Points are separated by semicolons, with
+    /// optional control points in parentheses and edge colors (`c`, `m`, `y` or `w`) between them,
+    /// and `#` closes a contour at its first point. A leading `@invert-y` marks y-up coordinates.
+    /// Edges without a color are white, so shapes that specify no colors still need coloring.
+    pub fn from_description(description: &str) -> Result<Shape, ParseShapeError> {
+        let (contours, inverse_y_axis) = parse_description(description)?;
+        Ok(Shape::from_parsed(&contours, inverse_y_axis))
+    }
+
+    pub(crate) fn from_parsed(contours: &ParsedContours, inverse_y_axis: bool) -> Shape {
+        let segments: Vec<Vec<Segment>> = contours
+            .iter()
+            .map(|contour| contour.iter().map(|&(segment, _)| segment).collect())
+            .collect();
+        let mut shape = Shape::from_contours(&segments, inverse_y_axis);
+
+        for (contour_index, contour) in contours.iter().enumerate() {
+            for (edge_index, &(_, color)) in contour.iter().enumerate() {
+                if color != EdgeColor::White {
+                    shape.set_edge_color(contour_index, edge_index, color);
+                }
+            }
+        }
+
+        shape
+    }
+}
+
+pub(crate) fn parse_description(
+    description: &str,
+) -> Result<(ParsedContours, bool), ParseShapeError> {
+    let mut parser = Parser::new(description);
+    let inverse_y_axis = parser.eat_str("@invert-y");
+
+    let mut contours = vec![];
+    if parser.peek() == Some(b'{') {
+        while parser.eat(b'{') {
+            let contour = parser.contour(Some(b'}'))?;
+            if !contour.is_empty() {
+                contours.push(contour);
+            }
+        }
+    } else {
+        // a single contour may be given without braces
+        let contour = parser.contour(None)?;
+        if !contour.is_empty() {
+            contours.push(contour);
+        }
+    }
+
+    match parser.peek() {
+        None => Ok((contours, inverse_y_axis)),
+        Some(_) => Err(parser.error("unexpected character after contours")),
+    }
+}
+
+pub(crate) struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Parser {
+            input: input.as_bytes(),
+            position: 0,
+        }
+    }
+
+    pub(crate) fn error(&self, reason: &'static str) -> ParseShapeError {
+        ParseShapeError {
+            offset: self.position,
+            reason,
+        }
+    }
+
+    /// Next non-whitespace byte, without consuming it.
+    pub(crate) fn peek(&mut self) -> Option<u8> {
+        while matches!(self.input.get(self.position), Some(c) if c.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+        self.input.get(self.position).copied()
+    }
+
+    pub(crate) fn next(&mut self) -> Option<u8> {
+        let c = self.peek();
+        if c.is_some() {
+            self.position += 1;
+        }
+        c
+    }
+
+    pub(crate) fn eat(&mut self, expected: u8) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, expected: &str) -> bool {
+        self.peek();
+        if self.input[self.position..].starts_with(expected.as_bytes()) {
+            self.position += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: u8, reason: &'static str) -> Result<(), ParseShapeError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(reason))
+        }
+    }
+
+    /// Parses a number if one starts at the current position.
+    pub(crate) fn number(&mut self) -> Option<f64> {
+        self.peek();
+        let start = self.position;
+        let mut end = start;
+        let at = |i: usize| self.input.get(i).copied().unwrap_or(0);
+
+        if at(end) == b'+' || at(end) == b'-' {
+            end += 1;
+        }
+        let digits_start = end;
+        while at(end).is_ascii_digit() {
+            end += 1;
+        }
+        if at(end) == b'.' {
+            end += 1;
+            while at(end).is_ascii_digit() {
+                end += 1;
+            }
+        }
+        if end == digits_start || (end == digits_start + 1 && at(digits_start) == b'.') {
+            return None;
+        }
+        if at(end) == b'e' || at(end) == b'E' {
+            let mut exponent = end + 1;
+            if at(exponent) == b'+' || at(exponent) == b'-' {
+                exponent += 1;
+            }
+            if at(exponent).is_ascii_digit() {
+                end = exponent;
+                while at(end).is_ascii_digit() {
+                    end += 1;
+                }
+            }
+        }
+
+        // the scanned bytes are always ASCII
+        let number = std::str::from_utf8(&self.input[start..end]).unwrap();
+        let number = number.parse().ok()?;
+        self.position = end;
+        Some(number)
+    }
+
+    /// Parses an `x, y` pair if one starts at the current position.
+    fn point(&mut self) -> Result<Option<Vec2>, ParseShapeError> {
+        let x = match self.number() {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        self.expect(b',', "expected a comma between coordinates")?;
+        match self.number() {
+            Some(y) => Ok(Some(Vec2::new(x, y))),
+            None => Err(self.error("expected a y coordinate")),
+        }
+    }
+
+    fn control_points(&mut self) -> Result<Vec<Vec2>, ParseShapeError> {
+        let mut points = vec![];
+        while let Some(point) = self.point()? {
+            points.push(point);
+            if points.len() == 2 || !self.eat(b';') {
+                break;
+            }
+        }
+        self.expect(b')', "expected `)` after control points")?;
+        Ok(points)
+    }
+
+    /// Parses the edges of a contour up to `terminator`, or the end of the input.
+    fn contour(
+        &mut self,
+        terminator: Option<u8>,
+    ) -> Result<Vec<(Segment, EdgeColor)>, ParseShapeError> {
+        let mut contour = vec![];
+        let first = match self.point()? {
+            Some(point) => point,
+            None => {
+                return match self.next() {
+                    c if c == terminator => Ok(contour),
+                    _ => Err(self.error("expected a point")),
+                }
+            }
+        };
+
+        let mut last = first;
+        loop {
+            match self.next() {
+                c if c == terminator => return Ok(contour),
+                Some(b';') => {}
+                _ => return Err(self.error("expected `;` after a point")),
+            }
+
+            if let Some(point) = self.point()? {
+                contour.push((line(last, point), EdgeColor::White));
+                last = point;
+                continue;
+            }
+
+            let mut color = EdgeColor::White;
+            let mut control = vec![];
+            match self.next() {
+                Some(b'#') => {
+                    contour.push((line(last, first), EdgeColor::White));
+                    last = first;
+                    continue;
+                }
+                Some(b';') => {}
+                Some(b'(') => {
+                    control = self.control_points()?;
+                    self.expect(b';', "expected `;` after control points")?;
+                }
+                Some(c) if color_from_char(c).is_some() => {
+                    color = color_from_char(c).unwrap();
+                    if self.eat(b'(') {
+                        control = self.control_points()?;
+                    }
+                    self.expect(b';', "expected `;` after an edge color")?;
+                }
+                // a trailing semicolon before the end of the contour
+                c if c == terminator => return Ok(contour),
+                _ => return Err(self.error("expected a point, color or control points")),
+            }
+
+            let end = match self.point()? {
+                Some(point) => point,
+                None if self.eat(b'#') => first,
+                None => return Err(self.error("expected the end point of an edge")),
+            };
+
+            let segment = match control[..] {
+                [] => line(last, end),
+                [p1] => Segment::Quadratic([last.into(), p1.into(), end.into()]),
+                [p1, p2] => Segment::Cubic([last.into(), p1.into(), p2.into(), end.into()]),
+                _ => unreachable!(),
+            };
+            contour.push((segment, color));
+            last = end;
+        }
+    }
+}
+
+fn line(start: Vec2, end: Vec2) -> Segment {
+    Segment::Linear([start.into(), end.into()])
+}
+
+fn color_from_char(c: u8) -> Option<EdgeColor> {
+    match c.to_ascii_lowercase() {
+        b'c' => Some(EdgeColor::Cyan),
+        b'm' => Some(EdgeColor::Magenta),
+        b'y' => Some(EdgeColor::Yellow),
+        b'w' => Some(EdgeColor::White),
+        _ => None,
+    }
+}
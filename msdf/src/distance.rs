@@ -0,0 +1,86 @@
+use crate::geometry::Vec2;
+use crate::{ColoredShape, Shape};
+use mint::Vector2;
+use msdf_sys::*;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// The point of a shape's outline nearest to a query point.
+pub struct NearestEdge {
+    /// Index of the contour, as in [Shape::contours].
+    pub contour: usize,
+    /// Index of the edge within its contour.
+    pub edge: usize,
+    /// Parameter of the nearest point along the edge, in `[0, 1]`.
+    pub param: f64,
+    /// Signed distance to the nearest point. Its sign follows the orientation of the nearest edge
+    /// like in generated fields, so it is positive inside shapes wound the way msdfgen expects and
+    /// negative inside reversed ones.
+    pub distance: f64,
+}
+
+impl Shape {
+    /// Finds the edge nearest to `point`, given in shape units. Returns [None] if the shape has no
+    /// edges.
+    pub fn nearest_edge(&self, point: Vector2<f64>) -> Option<NearestEdge> {
+        let mut contour = 0;
+        let mut edge = 0;
+        let mut param = 0.0;
+        let mut distance = 0.0;
+
+        let found = unsafe {
+            msdfsys_shapeNearestEdge(
+                &self.shape,
+                point.x,
+                point.y,
+                &mut contour,
+                &mut edge,
+                &mut param,
+                &mut distance,
+            )
+        };
+
+        if !found {
+            return None;
+        }
+
+        Some(NearestEdge {
+            contour: contour as usize,
+            edge: edge as usize,
+            param: param.clamp(0.0, 1.0),
+            distance,
+        })
+    }
+
+    /// Signed distance from `point` to the nearest edge of the shape, in shape units, with the sign
+    /// of generated fields (see [NearestEdge::distance]). Negative infinity for a shape without
+    /// edges. This is the exact distance to the outline unless contours overlap, in which case
+    /// the nearest edge may lie inside another contour; use [Shape::resolve_overlaps] first to
+    /// measure to the outline of the filled area.
+    pub fn signed_distance(&self, point: Vector2<f64>) -> f64 {
+        self.nearest_edge(point)
+            .map_or(f64::NEG_INFINITY, |nearest| nearest.distance)
+    }
+
+    /// Whether `point` lies inside the shape, using the non-zero winding rule. Unlike the sign of
+    /// [Shape::signed_distance], this doesn't depend on the orientation of the contours.
+    pub fn contains(&self, point: Vector2<f64>) -> bool {
+        self.winding(Vec2::from(point)) != 0
+    }
+}
+
+impl ColoredShape {
+    /// See [Shape::nearest_edge].
+    pub fn nearest_edge(&self, point: Vector2<f64>) -> Option<NearestEdge> {
+        self.0.nearest_edge(point)
+    }
+
+    /// See [Shape::signed_distance].
+    pub fn signed_distance(&self, point: Vector2<f64>) -> f64 {
+        self.0.signed_distance(point)
+    }
+
+    /// See [Shape::contains].
+    pub fn contains(&self, point: Vector2<f64>) -> bool {
+        self.0.contains(point)
+    }
+}
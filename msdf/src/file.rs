@@ -0,0 +1,531 @@
+use crate::{RangeUnits, SDFTrait};
+use image::{ImageBuffer, Pixel};
+use mint::Vector2;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tiff::encoder::colortype::{Gray32Float, RGB32Float, RGBA32Float};
+use tiff::encoder::TiffEncoder;
+use tiff::tags::Tag;
+
+/// Keys of the metadata stored alongside the pixels.
+pub(crate) const TYPE_KEY: &str = "msdf:type";
+const RANGE_KEY: &str = "msdf:range";
+const RANGE_UNITS_KEY: &str = "msdf:range_units";
+const MID_VALUE_KEY: &str = "msdf:mid_value";
+/// Keys of the dimensions in the sidecar header of raw files.
+const WIDTH_KEY: &str = "width";
+const HEIGHT_KEY: &str = "height";
+
+#[derive(Debug)]
+/// Errors emitted while saving or loading a field as an image file.
+pub enum FieldFileError {
+    Io(io::Error),
+    /// The image could not be encoded or decoded.
+    Format(Box<dyn std::error::Error + Send + Sync>),
+    /// The file has no value for a metadata key, so it was not saved by this crate.
+    MissingMetadata(&'static str),
+    /// A metadata value could not be parsed.
+    InvalidMetadata {
+        key: &'static str,
+        value: String,
+    },
+    /// The file holds a different type of field than requested.
+    ChannelMismatch {
+        expected: u8,
+        found: u8,
+    },
+    /// The pixel format of the file can't be converted to a field.
+    UnsupportedPixelFormat,
+    /// The file is truncated or its structure is invalid.
+    Malformed(&'static str),
+}
+
+impl fmt::Display for FieldFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldFileError::Io(error) => write!(f, "failed to access file: {}", error),
+            FieldFileError::Format(error) => write!(f, "invalid image: {}", error),
+            FieldFileError::MissingMetadata(key) => write!(f, "missing metadata `{}`", key),
+            FieldFileError::InvalidMetadata { key, value } => {
+                write!(f, "invalid value `{}` for metadata `{}`", value, key)
+            }
+            FieldFileError::ChannelMismatch { expected, found } => write!(
+                f,
+                "expected a field with {} channels, but the file has {}",
+                expected, found
+            ),
+            FieldFileError::UnsupportedPixelFormat => write!(f, "unsupported pixel format"),
+            FieldFileError::Malformed(reason) => write!(f, "malformed file: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for FieldFileError {}
+
+impl From<io::Error> for FieldFileError {
+    fn from(error: io::Error) -> Self {
+        FieldFileError::Io(error)
+    }
+}
+
+impl From<png::EncodingError> for FieldFileError {
+    fn from(error: png::EncodingError) -> Self {
+        FieldFileError::Format(Box::new(error))
+    }
+}
+
+impl From<png::DecodingError> for FieldFileError {
+    fn from(error: png::DecodingError) -> Self {
+        FieldFileError::Format(Box::new(error))
+    }
+}
+
+impl From<tiff::TiffError> for FieldFileError {
+    fn from(error: tiff::TiffError) -> Self {
+        FieldFileError::Format(Box::new(error))
+    }
+}
+
+/// Distance fields that can be saved as ordinary image files, with the field type, range and
+/// mid-value embedded as metadata so the field can be loaded again without knowing them.
+///
+/// PNG files store 8-bit pixels and the metadata in `tEXt` chunks. TIFF files store 32-bit float
+/// pixels and the metadata in the `ImageDescription` tag, one `key=value` pair per line. Raw
+/// files store 32-bit float pixels only, with the metadata in a sidecar text file. OpenEXR files
+/// store 32-bit float pixels and the metadata as string attributes, and need the `exr` feature.
+pub trait FieldFile: SDFTrait + Sized {
+    /// Saves the field as an 8-bit PNG. Values are clamped to `[0, 1]`.
+    fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), FieldFileError>;
+
+    /// Loads a field saved by [FieldFile::save_png]. 16-bit PNGs with the same metadata are
+    /// accepted too, and `iTXt` chunks are read as well as `tEXt` chunks.
+    fn load_png<P: AsRef<Path>>(path: P) -> Result<Self, FieldFileError>;
+
+    /// Saves the field as a 32-bit float TIFF, without losing precision.
+    fn save_tiff<P: AsRef<Path>>(&self, path: P) -> Result<(), FieldFileError>;
+
+    /// Loads a field saved by [FieldFile::save_tiff].
+    fn load_tiff<P: AsRef<Path>>(path: P) -> Result<Self, FieldFileError>;
+
+    /// Saves the samples of the field as little-endian 32-bit floats, like msdfgen's
+    /// `-format binfloat`, with rows from top to bottom and channels interleaved. The dimensions
+    /// and metadata are written to the text file at [raw_header_path], as `key=value` lines.
+    fn save_raw<P: AsRef<Path>>(&self, path: P) -> Result<(), FieldFileError>;
+
+    /// Loads a field saved by [FieldFile::save_raw], along with its sidecar header.
+    fn load_raw<P: AsRef<Path>>(path: P) -> Result<Self, FieldFileError>;
+
+    /// Saves the field as an OpenEXR image with 32-bit float channels. [SDF](crate::SDF)s are
+    /// stored in a `Y` channel and other fields in `R`, `G`, `B` and `A`.
+    #[cfg(feature = "exr")]
+    fn save_exr<P: AsRef<Path>>(&self, path: P) -> Result<(), FieldFileError>;
+
+    /// Loads a field saved by [FieldFile::save_exr], from the first layer of the image.
+    #[cfg(feature = "exr")]
+    fn load_exr<P: AsRef<Path>>(path: P) -> Result<Self, FieldFileError>;
+}
+
+/// Path of the sidecar header of a raw file, which is `path` with `.txt` appended.
+pub fn raw_header_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut header = path.as_ref().as_os_str().to_owned();
+    header.push(".txt");
+    header.into()
+}
+
+impl<T, P> FieldFile for T
+where
+    T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    fn save_png<Q: AsRef<Path>>(&self, path: Q) -> Result<(), FieldFileError> {
+        let color = match P::CHANNEL_COUNT {
+            1 => png::ColorType::Grayscale,
+            3 => png::ColorType::Rgb,
+            4 => png::ColorType::Rgba,
+            _ => return Err(FieldFileError::UnsupportedPixelFormat),
+        };
+
+        let writer = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(writer, self.image().width(), self.image().height());
+        encoder.set_color(color);
+        encoder.set_depth(png::BitDepth::Eight);
+        for (key, value) in metadata(self) {
+            encoder.add_text_chunk(key.to_string(), value)?;
+        }
+
+        let data: Vec<u8> = self
+            .image()
+            .iter()
+            .map(|sample| (sample.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn load_png<Q: AsRef<Path>>(path: Q) -> Result<Self, FieldFileError> {
+        let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info()?;
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut buffer)?;
+        buffer.truncate(frame.buffer_size());
+
+        let channels = match frame.color_type {
+            png::ColorType::Grayscale => 1,
+            png::ColorType::GrayscaleAlpha => 2,
+            png::ColorType::Rgb => 3,
+            png::ColorType::Rgba => 4,
+            png::ColorType::Indexed => return Err(FieldFileError::UnsupportedPixelFormat),
+        };
+        check_channels::<P>(channels)?;
+
+        let samples = match frame.bit_depth {
+            png::BitDepth::Eight => buffer.iter().map(|&v| v as f32 / 255.0).collect(),
+            png::BitDepth::Sixteen => buffer
+                .chunks_exact(2)
+                .map(|v| u16::from_be_bytes([v[0], v[1]]) as f32 / u16::MAX as f32)
+                .collect(),
+            _ => return Err(FieldFileError::UnsupportedPixelFormat),
+        };
+
+        let info = reader.info();
+        let mut entries: Vec<(String, String)> = info
+            .uncompressed_latin1_text
+            .iter()
+            .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+            .collect();
+        for chunk in &info.utf8_text {
+            entries.push((chunk.keyword.clone(), chunk.get_text()?));
+        }
+
+        // the sample count always matches the dimensions
+        let image = ImageBuffer::from_raw(frame.width, frame.height, samples).unwrap();
+        from_metadata::<T, P>(image, &entries)
+    }
+
+    fn save_tiff<Q: AsRef<Path>>(&self, path: Q) -> Result<(), FieldFileError> {
+        let description = describe(&metadata(self));
+
+        let mut encoder = TiffEncoder::new(BufWriter::new(File::create(path)?))?;
+        let (width, height) = self.image().dimensions();
+        let samples = self.image().as_raw();
+
+        macro_rules! write_image {
+            ($color:ty) => {{
+                let mut image = encoder.new_image::<$color>(width, height)?;
+                image
+                    .encoder()
+                    .write_tag(Tag::ImageDescription, description.as_str())?;
+                image.write_data(samples)?;
+            }};
+        }
+
+        match P::CHANNEL_COUNT {
+            1 => write_image!(Gray32Float),
+            3 => write_image!(RGB32Float),
+            4 => write_image!(RGBA32Float),
+            _ => return Err(FieldFileError::UnsupportedPixelFormat),
+        }
+
+        Ok(())
+    }
+
+    fn load_tiff<Q: AsRef<Path>>(path: Q) -> Result<Self, FieldFileError> {
+        let mut decoder = tiff::decoder::Decoder::new(BufReader::new(File::open(path)?))?;
+
+        let channels = match decoder.colortype()? {
+            tiff::ColorType::Gray(32) => 1,
+            tiff::ColorType::GrayA(32) => 2,
+            tiff::ColorType::RGB(32) => 3,
+            tiff::ColorType::RGBA(32) => 4,
+            _ => return Err(FieldFileError::UnsupportedPixelFormat),
+        };
+        check_channels::<P>(channels)?;
+
+        let description = decoder
+            .get_tag_ascii_string(Tag::ImageDescription)
+            .map_err(|_| FieldFileError::MissingMetadata(TYPE_KEY))?;
+        let entries = parse_description(&description);
+
+        let (width, height) = decoder.dimensions()?;
+        let samples = match decoder.read_image()? {
+            tiff::decoder::DecodingResult::F32(samples) => samples,
+            _ => return Err(FieldFileError::UnsupportedPixelFormat),
+        };
+
+        let image = ImageBuffer::from_raw(width, height, samples)
+            .ok_or(FieldFileError::UnsupportedPixelFormat)?;
+        from_metadata::<T, P>(image, &entries)
+    }
+
+    fn save_raw<Q: AsRef<Path>>(&self, path: Q) -> Result<(), FieldFileError> {
+        let (width, height) = self.image().dimensions();
+        let mut entries = vec![
+            (WIDTH_KEY, width.to_string()),
+            (HEIGHT_KEY, height.to_string()),
+        ];
+        entries.extend(metadata(self));
+        fs::write(raw_header_path(&path), describe(&entries) + "\n")?;
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        for sample in self.image().iter() {
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn load_raw<Q: AsRef<Path>>(path: Q) -> Result<Self, FieldFileError> {
+        let entries = parse_description(&fs::read_to_string(raw_header_path(&path))?);
+        let dimension = |key: &'static str| -> Result<u32, FieldFileError> {
+            let value = entries
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value)
+                .ok_or(FieldFileError::MissingMetadata(key))?;
+            value.parse().map_err(|_| FieldFileError::InvalidMetadata {
+                key,
+                value: value.clone(),
+            })
+        };
+        let width = dimension(WIDTH_KEY)?;
+        let height = dimension(HEIGHT_KEY)?;
+
+        let data = fs::read(path)?;
+        let samples: Vec<f32> = data
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        let expected = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(P::CHANNEL_COUNT as usize))
+            .and_then(|samples| samples.checked_mul(4))
+            .ok_or(FieldFileError::Malformed("image too large"))?;
+        if data.len() != expected {
+            return Err(FieldFileError::Malformed(
+                "sample count does not match the dimensions",
+            ));
+        }
+
+        let image = ImageBuffer::from_raw(width, height, samples).ok_or(
+            FieldFileError::Malformed("sample count does not match the dimensions"),
+        )?;
+        from_metadata::<T, P>(image, &entries)
+    }
+
+    #[cfg(feature = "exr")]
+    fn save_exr<Q: AsRef<Path>>(&self, path: Q) -> Result<(), FieldFileError> {
+        use exr::prelude::*;
+
+        let (width, height) = self.image().dimensions();
+        let names = exr_channel_names(P::CHANNEL_COUNT);
+        let channels = (0..names.len())
+            .map(|channel| {
+                let samples = self
+                    .image()
+                    .pixels()
+                    .map(|pixel| pixel.channels()[channel])
+                    .collect();
+                AnyChannel::new(names[channel], FlatSamples::F32(samples))
+            })
+            .collect::<Vec<_>>();
+
+        let mut attributes = LayerAttributes::default();
+        for (key, value) in metadata(self) {
+            attributes.other.insert(
+                Text::from(key),
+                AttributeValue::Text(Text::from(value.as_str())),
+            );
+        }
+
+        let layer = Layer::new(
+            (width as usize, height as usize),
+            attributes,
+            Encoding::FAST_LOSSLESS,
+            AnyChannels::sort(channels.into()),
+        );
+        Image::from_layer(layer).write().to_file(path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "exr")]
+    fn load_exr<Q: AsRef<Path>>(path: Q) -> Result<Self, FieldFileError> {
+        use exr::prelude::*;
+
+        let image = read()
+            .no_deep_data()
+            .largest_resolution_level()
+            .all_channels()
+            .first_valid_layer()
+            .all_attributes()
+            .from_file(path)?;
+        let layer = image.layer_data;
+
+        let channels = &layer.channel_data.list;
+        check_channels::<P>(channels.len() as u8)?;
+        let names = exr_channel_names(P::CHANNEL_COUNT);
+        let channels = names
+            .iter()
+            .map(|&name| {
+                channels
+                    .iter()
+                    .find(|channel| channel.name.eq(name))
+                    .map(|channel| channel.sample_data.values_as_f32().collect::<Vec<_>>())
+                    .ok_or(FieldFileError::UnsupportedPixelFormat)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let (width, height) = (layer.size.x(), layer.size.y());
+        let mut samples = Vec::with_capacity(width * height * channels.len());
+        for i in 0..width * height {
+            samples.extend(channels.iter().map(|channel| channel[i]));
+        }
+
+        let entries: Vec<(String, String)> = layer
+            .attributes
+            .other
+            .iter()
+            .filter_map(|(key, value)| match value {
+                AttributeValue::Text(text) => Some((key.to_string(), text.to_string())),
+                _ => None,
+            })
+            .collect();
+
+        // the sample count always matches the dimensions
+        let image = ImageBuffer::from_raw(width as u32, height as u32, samples).unwrap();
+        from_metadata::<T, P>(image, &entries)
+    }
+}
+
+#[cfg(feature = "exr")]
+impl From<exr::error::Error> for FieldFileError {
+    fn from(error: exr::error::Error) -> Self {
+        FieldFileError::Format(Box::new(error))
+    }
+}
+
+/// Names of the OpenEXR channels of a field with the given number of channels.
+#[cfg(feature = "exr")]
+fn exr_channel_names(channels: u8) -> &'static [&'static str] {
+    match channels {
+        1 => &["Y"],
+        _ => &["R", "G", "B", "A"][..channels as usize],
+    }
+}
+
+/// Joins metadata into `key=value` lines.
+fn describe(entries: &[(&str, String)]) -> String {
+    entries
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn parse_description(description: &str) -> Vec<(String, String)> {
+    description
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Name of the field type stored for a channel count.
+fn type_name(channels: u8) -> &'static str {
+    match channels {
+        1 => "sdf",
+        3 => "msdf",
+        4 => "mtsdf",
+        _ => "unknown",
+    }
+}
+
+fn check_channels<P: Pixel>(found: u8) -> Result<(), FieldFileError> {
+    if found != P::CHANNEL_COUNT {
+        return Err(FieldFileError::ChannelMismatch {
+            expected: P::CHANNEL_COUNT,
+            found,
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn metadata<T, P>(field: &T) -> [(&'static str, String); 4]
+where
+    T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    let units = match field.range_units() {
+        RangeUnits::Pixels => "pixels".to_string(),
+        RangeUnits::ShapeUnits { scale } => format!("shape {} {}", scale.x, scale.y),
+    };
+
+    [
+        (TYPE_KEY, type_name(P::CHANNEL_COUNT).to_string()),
+        (RANGE_KEY, field.range().to_string()),
+        (RANGE_UNITS_KEY, units),
+        (MID_VALUE_KEY, field.mid_value().to_string()),
+    ]
+}
+
+pub(crate) fn from_metadata<T, P>(
+    image: ImageBuffer<P, Vec<f32>>,
+    entries: &[(String, String)],
+) -> Result<T, FieldFileError>
+where
+    T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    let get = |key: &'static str| {
+        entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+            .ok_or(FieldFileError::MissingMetadata(key))
+    };
+    let invalid = |key: &'static str, value: &str| FieldFileError::InvalidMetadata {
+        key,
+        value: value.to_string(),
+    };
+
+    let field_type = get(TYPE_KEY)?;
+    if field_type != type_name(P::CHANNEL_COUNT) {
+        return Err(invalid(TYPE_KEY, field_type));
+    }
+
+    let range = get(RANGE_KEY)?;
+    let range = range.parse().map_err(|_| invalid(RANGE_KEY, range))?;
+    let mid_value = get(MID_VALUE_KEY)?;
+    let mid_value = mid_value
+        .parse()
+        .map_err(|_| invalid(MID_VALUE_KEY, mid_value))?;
+
+    // fields saved without units are in pixels, like fields created with `from_image`
+    let units = match get(RANGE_UNITS_KEY) {
+        Err(_) => RangeUnits::Pixels,
+        Ok("pixels") => RangeUnits::Pixels,
+        Ok(value) => {
+            let scale: Vec<f64> = value
+                .strip_prefix("shape ")
+                .ok_or_else(|| invalid(RANGE_UNITS_KEY, value))?
+                .split(' ')
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid(RANGE_UNITS_KEY, value))?;
+            match scale[..] {
+                [x, y] => RangeUnits::ShapeUnits {
+                    scale: Vector2 { x, y },
+                },
+                _ => return Err(invalid(RANGE_UNITS_KEY, value)),
+            }
+        }
+    };
+
+    Ok(T::from_image(image, range, mid_value).with_range_units(units))
+}
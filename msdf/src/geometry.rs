@@ -0,0 +1,771 @@
+use crate::Shape;
+use mint::Vector2;
+use msdf_sys::*;
+use std::alloc::{alloc, Layout};
+use std::ops::{Add, Mul, Neg, Sub};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Axis-aligned bounding box of a shape, in shape units.
+pub struct Bounds {
+    pub left: f64,
+    pub bottom: f64,
+    pub right: f64,
+    pub top: f64,
+}
+
+impl Bounds {
+    /// Width of the bounding box.
+    pub fn width(&self) -> f64 {
+        self.right - self.left
+    }
+
+    /// Height of the bounding box.
+    pub fn height(&self) -> f64 {
+        self.top - self.bottom
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// A 2D affine transformation. A point `p` is mapped to
+/// `x_axis * p.x + y_axis * p.y + translation`.
+pub struct Affine2 {
+    /// Image of the x basis vector.
+    pub x_axis: Vector2<f64>,
+    /// Image of the y basis vector.
+    pub y_axis: Vector2<f64>,
+    /// Translation applied after the linear part.
+    pub translation: Vector2<f64>,
+}
+
+impl Affine2 {
+    /// The transformation that leaves every point unchanged.
+    pub const IDENTITY: Affine2 = Affine2 {
+        x_axis: Vector2 { x: 1.0, y: 0.0 },
+        y_axis: Vector2 { x: 0.0, y: 1.0 },
+        translation: Vector2 { x: 0.0, y: 0.0 },
+    };
+
+    /// Creates a non-uniform scale around the origin.
+    pub fn from_scale(scale: Vector2<f64>) -> Self {
+        Affine2 {
+            x_axis: Vector2 { x: scale.x, y: 0.0 },
+            y_axis: Vector2 { x: 0.0, y: scale.y },
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Creates a counterclockwise rotation around the origin. `angle` is in radians.
+    pub fn from_angle(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Affine2 {
+            x_axis: Vector2 { x: cos, y: sin },
+            y_axis: Vector2 { x: -sin, y: cos },
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Creates a horizontal shear that slants the positive y axis towards positive x by `angle`
+    /// radians.
+    pub fn from_skew_x(angle: f64) -> Self {
+        Affine2 {
+            y_axis: Vector2 {
+                x: angle.tan(),
+                y: 1.0,
+            },
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Creates a translation.
+    pub fn from_translation(translation: Vector2<f64>) -> Self {
+        Affine2 {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Returns the transformation that applies `self` followed by `other`.
+    pub fn then(&self, other: &Affine2) -> Affine2 {
+        Affine2 {
+            x_axis: other.transform_vector(self.x_axis),
+            y_axis: other.transform_vector(self.y_axis),
+            translation: other.transform_point(self.translation),
+        }
+    }
+
+    /// Applies the full transformation to a point.
+    pub fn transform_point(&self, point: Vector2<f64>) -> Vector2<f64> {
+        let v = self.transform_vector(point);
+        Vector2 {
+            x: v.x + self.translation.x,
+            y: v.y + self.translation.y,
+        }
+    }
+
+    /// Applies only the linear part of the transformation to a vector.
+    pub fn transform_vector(&self, vector: Vector2<f64>) -> Vector2<f64> {
+        Vector2 {
+            x: self.x_axis.x * vector.x + self.y_axis.x * vector.y,
+            y: self.x_axis.y * vector.x + self.y_axis.y * vector.y,
+        }
+    }
+}
+
+impl Default for Affine2 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Shape {
+    /// Computes the bounding box of the shape's outline. Returns [None] if the shape has no edges.
+    pub fn bounds(&self) -> Option<Bounds> {
+        let mut bounds = Bounds {
+            left: f64::MAX,
+            bottom: f64::MAX,
+            right: -f64::MAX,
+            top: -f64::MAX,
+        };
+
+        unsafe {
+            self.shape.bound(
+                &mut bounds.left,
+                &mut bounds.bottom,
+                &mut bounds.right,
+                &mut bounds.top,
+            );
+        }
+
+        if bounds.left <= bounds.right && bounds.bottom <= bounds.top {
+            Some(bounds)
+        } else {
+            None
+        }
+    }
+
+    /// Applies an affine transformation to every control point of every edge, in place. Since
+    /// Bézier curves are closed under affine maps, the transformed shape is exact. Edge colors are
+    /// kept, so transformations that change corner angles (such as skews) are best applied before
+    /// coloring.
+    pub fn transform(&mut self, transform: &Affine2) {
+        unsafe {
+            for contour_index in 0..msdfsys_shapeContourCount(&self.shape) {
+                let contour = msdfsys_shapeContour(&mut self.shape, contour_index);
+
+                for edge_index in 0..msdfsys_contourEdgeCount(contour) {
+                    let edge = msdfsys_contourEdge(contour, edge_index);
+                    let count = msdfsys_edgePointCount(edge) as usize;
+                    let points = msdfsys_edgePoints(edge);
+
+                    for point in std::slice::from_raw_parts_mut(points, count) {
+                        let transformed = transform.transform_point(Vector2 {
+                            x: point.x,
+                            y: point.y,
+                        });
+                        point.x = transformed.x;
+                        point.y = transformed.y;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Rotates the shape counterclockwise around the origin by `angle` radians, as it appears in
+    /// generated fields.
+    pub fn rotate(&mut self, angle: f64) {
+        self.transform(&Affine2::from_angle(self.visual_angle(angle)));
+    }
+
+    /// Slants the shape by `angle` radians around the baseline (`y = 0`), as it appears in
+    /// generated fields. Positive angles lean the top of the shape to the right, producing an
+    /// oblique style.
+    pub fn skew_x(&mut self, angle: f64) {
+        self.transform(&Affine2::from_skew_x(self.visual_angle(angle)));
+    }
+
+    /// Reverses the direction of every contour, which flips the sign of generated fields. Useful
+    /// for shapes whose outer contours wind the wrong way, as SVG paths often do. Edge colors are
+    /// kept.
+    pub fn reverse(&mut self) {
+        let colors = self.edge_colors();
+        let contours: Vec<Vec<Segment>> = self
+            .contours()
+            .iter()
+            .map(|contour| contour.iter().rev().map(Segment::reversed).collect())
+            .collect();
+
+        let mut shape = Shape::from_contours(&contours, self.shape.inverseYAxis);
+        for (contour_index, contour) in contours.iter().enumerate() {
+            let colors = colors[contour_index].iter().rev();
+            for (edge_index, &color) in colors.enumerate().take(contour.len()) {
+                shape.set_edge_color(contour_index, edge_index, color);
+            }
+        }
+
+        *self = shape;
+    }
+
+    // Loaded glyphs use y-up coordinates and set `inverseYAxis` so that generated fields come out
+    // upright. Without the flag the y axis points down in the output, mirroring every angle.
+    fn visual_angle(&self, angle: f64) -> f64 {
+        if self.shape.inverseYAxis {
+            angle
+        } else {
+            -angle
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// A single edge of a contour, given by its Bézier control points in contour order.
+pub enum Segment {
+    Linear([Vector2<f64>; 2]),
+    Quadratic([Vector2<f64>; 3]),
+    Cubic([Vector2<f64>; 4]),
+}
+
+impl Segment {
+    /// Control points of the segment, from its start point to its end point.
+    pub fn points(&self) -> &[Vector2<f64>] {
+        match self {
+            Segment::Linear(points) => points,
+            Segment::Quadratic(points) => points,
+            Segment::Cubic(points) => points,
+        }
+    }
+
+    /// First point of the segment.
+    pub fn start(&self) -> Vector2<f64> {
+        self.points()[0]
+    }
+
+    /// Last point of the segment.
+    pub fn end(&self) -> Vector2<f64> {
+        self.points()[self.points().len() - 1]
+    }
+
+    /// Evaluates the segment at parameter `t` in `[0, 1]`.
+    pub fn point(&self, t: f64) -> Vector2<f64> {
+        let (mut points, count) = self.control();
+        for level in (1..count).rev() {
+            for i in 0..level {
+                points[i] = Vec2::lerp(points[i], points[i + 1], t);
+            }
+        }
+        points[0].into()
+    }
+
+    /// Direction of the segment at parameter `t`. Like msdfgen, this falls back to a neighbouring
+    /// control point when the derivative vanishes at an endpoint.
+    pub fn direction(&self, t: f64) -> Vector2<f64> {
+        let (p, _) = self.control();
+        let direction = match self {
+            Segment::Linear(_) => p[1] - p[0],
+            Segment::Quadratic(_) => {
+                let tangent = Vec2::lerp(p[1] - p[0], p[2] - p[1], t);
+                if tangent.is_zero() {
+                    p[2] - p[0]
+                } else {
+                    tangent
+                }
+            }
+            Segment::Cubic(_) => {
+                let tangent = Vec2::lerp(
+                    Vec2::lerp(p[1] - p[0], p[2] - p[1], t),
+                    Vec2::lerp(p[2] - p[1], p[3] - p[2], t),
+                    t,
+                );
+                if !tangent.is_zero() {
+                    tangent
+                } else if t == 0.0 {
+                    p[2] - p[0]
+                } else if t == 1.0 {
+                    p[3] - p[1]
+                } else {
+                    tangent
+                }
+            }
+        };
+        direction.into()
+    }
+
+    /// Splits the segment at parameter `t` into two segments of the same degree.
+    pub fn split(&self, t: f64) -> (Segment, Segment) {
+        let (mut points, count) = self.control();
+        let mut first = [Vec2::default(); 4];
+        let mut second = [Vec2::default(); 4];
+
+        first[0] = points[0];
+        second[count - 1] = points[count - 1];
+        for level in (1..count).rev() {
+            for i in 0..level {
+                points[i] = Vec2::lerp(points[i], points[i + 1], t);
+            }
+            first[count - level] = points[0];
+            second[level - 1] = points[level - 1];
+        }
+
+        (
+            Segment::from_control(&first[..count]),
+            Segment::from_control(&second[..count]),
+        )
+    }
+
+    /// Returns the same segment traversed in the opposite direction.
+    pub fn reversed(&self) -> Segment {
+        let (mut points, count) = self.control();
+        points[..count].reverse();
+        Segment::from_control(&points[..count])
+    }
+
+    /// Returns the portion of the segment between parameters `from` and `to`.
+    pub(crate) fn section(&self, from: f64, to: f64) -> Segment {
+        if to <= from {
+            let p = Vec2::from(self.point(from));
+            return Segment::Linear([p.into(), p.into()]);
+        }
+        let tail = if from > 0.0 {
+            self.split(from).1
+        } else {
+            *self
+        };
+        if to < 1.0 {
+            tail.split((to - from) / (1.0 - from)).0
+        } else {
+            tail
+        }
+    }
+
+    /// Returns the same segment with its start point moved, keeping the remaining control points.
+    pub(crate) fn with_start(&self, start: Vector2<f64>) -> Segment {
+        let mut segment = *self;
+        match &mut segment {
+            Segment::Linear(p) => p[0] = start,
+            Segment::Quadratic(p) => p[0] = start,
+            Segment::Cubic(p) => p[0] = start,
+        }
+        segment
+    }
+
+    /// Exact derivative with respect to `t`.
+    pub(crate) fn derivative(&self, t: f64) -> Vec2 {
+        let (p, count) = self.control();
+        let mut d = [Vec2::default(); 3];
+        for i in 0..count - 1 {
+            d[i] = (p[i + 1] - p[i]) * (count - 1) as f64;
+        }
+        for level in (1..count - 1).rev() {
+            for i in 0..level {
+                d[i] = Vec2::lerp(d[i], d[i + 1], t);
+            }
+        }
+        d[0]
+    }
+
+    /// Converts the segment into an equivalent cubic segment.
+    pub(crate) fn to_cubic(self) -> Segment {
+        let (p, _) = self.control();
+        match self {
+            Segment::Linear(_) => Segment::Cubic([
+                p[0].into(),
+                Vec2::lerp(p[0], p[1], 1.0 / 3.0).into(),
+                Vec2::lerp(p[0], p[1], 2.0 / 3.0).into(),
+                p[1].into(),
+            ]),
+            Segment::Quadratic(_) => Segment::Cubic([
+                p[0].into(),
+                Vec2::lerp(p[0], p[1], 2.0 / 3.0).into(),
+                Vec2::lerp(p[2], p[1], 2.0 / 3.0).into(),
+                p[2].into(),
+            ]),
+            Segment::Cubic(_) => self,
+        }
+    }
+
+    /// Whether all control points coincide.
+    pub(crate) fn is_degenerate(&self) -> bool {
+        let start = self.start();
+        self.points()
+            .iter()
+            .all(|p| p.x == start.x && p.y == start.y)
+    }
+
+    /// Whether the inner control points lie within `tolerance` of the chord.
+    pub(crate) fn is_flat(&self, tolerance: f64) -> bool {
+        let (p, count) = self.control();
+        let chord = p[count - 1] - p[0];
+        let length = chord.length();
+        p[1..count - 1].iter().all(|&q| {
+            let offset = q - p[0];
+            if length == 0.0 {
+                offset.length() <= tolerance
+            } else {
+                chord.cross(offset).abs() / length <= tolerance
+            }
+        })
+    }
+
+    /// Bounding box of the control points, which contains the whole segment.
+    pub(crate) fn hull_bounds(&self) -> Bounds {
+        self.points().iter().fold(
+            Bounds {
+                left: f64::MAX,
+                bottom: f64::MAX,
+                right: -f64::MAX,
+                top: -f64::MAX,
+            },
+            |b, p| Bounds {
+                left: b.left.min(p.x),
+                bottom: b.bottom.min(p.y),
+                right: b.right.max(p.x),
+                top: b.top.max(p.y),
+            },
+        )
+    }
+
+    pub(crate) fn control(&self) -> ([Vec2; 4], usize) {
+        let mut control = [Vec2::default(); 4];
+        for (c, p) in control.iter_mut().zip(self.points()) {
+            *c = (*p).into();
+        }
+        (control, self.points().len())
+    }
+
+    pub(crate) fn from_control(points: &[Vec2]) -> Segment {
+        match *points {
+            [p0, p1] => Segment::Linear([p0.into(), p1.into()]),
+            [p0, p1, p2] => Segment::Quadratic([p0.into(), p1.into(), p2.into()]),
+            [p0, p1, p2, p3] => Segment::Cubic([p0.into(), p1.into(), p2.into(), p3.into()]),
+            _ => unreachable!("segments have between 2 and 4 control points"),
+        }
+    }
+}
+
+/// Finds the parameter pairs at which two segments intersect. Collinear overlaps are not
+/// reported.
+pub(crate) fn intersections(a: &Segment, b: &Segment, tolerance: f64) -> Vec<(f64, f64)> {
+    let mut found = vec![];
+    intersect_sections(a, (0.0, 1.0), b, (0.0, 1.0), tolerance, 0, &mut found);
+
+    let mut result: Vec<(f64, f64)> = vec![];
+    for (ta, tb) in found {
+        let (ta, tb) = refine_intersection(a, b, ta, tb);
+        let point = Vec2::from(a.point(ta));
+        let duplicate = result
+            .iter()
+            .any(|&(ra, _)| (Vec2::from(a.point(ra)) - point).length() <= tolerance);
+        if !duplicate {
+            result.push((ta, tb));
+        }
+    }
+    result
+}
+
+fn intersect_sections(
+    a: &Segment,
+    a_range: (f64, f64),
+    b: &Segment,
+    b_range: (f64, f64),
+    tolerance: f64,
+    depth: u32,
+    found: &mut Vec<(f64, f64)>,
+) {
+    let ba = a.hull_bounds();
+    let bb = b.hull_bounds();
+    if ba.left > bb.right + tolerance
+        || bb.left > ba.right + tolerance
+        || ba.bottom > bb.top + tolerance
+        || bb.bottom > ba.top + tolerance
+    {
+        return;
+    }
+
+    let a_flat = a.is_flat(tolerance);
+    let b_flat = b.is_flat(tolerance);
+    if (a_flat && b_flat) || depth >= 32 {
+        let (p, _) = a.control();
+        let (q, _) = b.control();
+        let (pa, pb) = (p[0], p[a.points().len() - 1]);
+        let (qa, qb) = (q[0], q[b.points().len() - 1]);
+        if let Some((s, u)) = line_intersection(pa, pb, qa, qb) {
+            found.push((
+                a_range.0 + s * (a_range.1 - a_range.0),
+                b_range.0 + u * (b_range.1 - b_range.0),
+            ));
+        }
+        return;
+    }
+
+    let a_size = ba.width().max(ba.height());
+    let b_size = bb.width().max(bb.height());
+    if !a_flat && (b_flat || a_size >= b_size) {
+        let mid = (a_range.0 + a_range.1) / 2.0;
+        let (first, second) = a.split(0.5);
+        intersect_sections(
+            &first,
+            (a_range.0, mid),
+            b,
+            b_range,
+            tolerance,
+            depth + 1,
+            found,
+        );
+        intersect_sections(
+            &second,
+            (mid, a_range.1),
+            b,
+            b_range,
+            tolerance,
+            depth + 1,
+            found,
+        );
+    } else {
+        let mid = (b_range.0 + b_range.1) / 2.0;
+        let (first, second) = b.split(0.5);
+        intersect_sections(
+            a,
+            a_range,
+            &first,
+            (b_range.0, mid),
+            tolerance,
+            depth + 1,
+            found,
+        );
+        intersect_sections(
+            a,
+            a_range,
+            &second,
+            (mid, b_range.1),
+            tolerance,
+            depth + 1,
+            found,
+        );
+    }
+}
+
+fn line_intersection(p0: Vec2, p1: Vec2, q0: Vec2, q1: Vec2) -> Option<(f64, f64)> {
+    let r = p1 - p0;
+    let s = q1 - q0;
+    let denominator = r.cross(s);
+    if denominator.abs() <= f64::EPSILON * r.length() * s.length() {
+        return None;
+    }
+    let offset = q0 - p0;
+    let t = offset.cross(s) / denominator;
+    let u = offset.cross(r) / denominator;
+    const SLACK: f64 = 1e-9;
+    if (-SLACK..=1.0 + SLACK).contains(&t) && (-SLACK..=1.0 + SLACK).contains(&u) {
+        Some((t.clamp(0.0, 1.0), u.clamp(0.0, 1.0)))
+    } else {
+        None
+    }
+}
+
+/// Polishes an approximate intersection with a few Newton iterations, keeping the original
+/// estimate if they do not converge.
+fn refine_intersection(a: &Segment, b: &Segment, ta: f64, tb: f64) -> (f64, f64) {
+    let error = |ta: f64, tb: f64| (Vec2::from(a.point(ta)) - Vec2::from(b.point(tb))).length();
+
+    let (mut ra, mut rb) = (ta, tb);
+    for _ in 0..4 {
+        let difference = Vec2::from(a.point(ra)) - Vec2::from(b.point(rb));
+        let da = a.derivative(ra);
+        let db = b.derivative(rb);
+        let determinant = db.cross(da);
+        if determinant.abs() <= f64::EPSILON {
+            break;
+        }
+        ra = (ra - db.cross(difference) / determinant).clamp(0.0, 1.0);
+        rb = (rb - da.cross(difference) / determinant).clamp(0.0, 1.0);
+    }
+
+    if error(ra, rb) <= error(ta, tb) {
+        (ra, rb)
+    } else {
+        (ta, tb)
+    }
+}
+
+impl Shape {
+    /// Reads the outline of the shape back as a list of contours.
+    pub fn contours(&self) -> Vec<Vec<Segment>> {
+        // the shim only reads through these pointers
+        let shape = &self.shape as *const msdfgen_Shape as *mut msdfgen_Shape;
+
+        unsafe {
+            (0..msdfsys_shapeContourCount(shape))
+                .map(|contour_index| {
+                    let contour = msdfsys_shapeContour(shape, contour_index);
+                    (0..msdfsys_contourEdgeCount(contour))
+                        .filter_map(|edge_index| {
+                            let edge = msdfsys_contourEdge(contour, edge_index);
+                            let count = msdfsys_edgePointCount(edge) as usize;
+                            if count == 0 {
+                                return None;
+                            }
+                            let points =
+                                std::slice::from_raw_parts(msdfsys_edgePoints(edge), count);
+                            let points = points
+                                .iter()
+                                .map(|p| Vec2 { x: p.x, y: p.y })
+                                .collect::<Vec<_>>();
+                            Some(Segment::from_control(&points))
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+    }
+
+    /// Builds an uncolored shape from a list of contours. Each contour should be closed, with
+    /// every segment starting where the previous one ends. `inverse_y_axis` should be set for
+    /// y-up coordinates, such as those of loaded glyphs, so that generated fields come out upright.
+    pub fn from_contours(contours: &[Vec<Segment>], inverse_y_axis: bool) -> Shape {
+        unsafe {
+            let mut shape = msdfgen_Shape::new();
+            shape.inverseYAxis = inverse_y_axis;
+
+            for contour in contours {
+                let msdfgen_contour = shape.addContour1();
+                for segment in contour {
+                    add_edge(msdfgen_contour, segment, msdfgen_EdgeColor_WHITE);
+                }
+            }
+
+            Shape { shape }
+        }
+    }
+
+    /// Winding number of the shape around a point.
+    pub(crate) fn winding(&self, point: Vec2) -> i32 {
+        unsafe { msdfsys_shapeWinding(&self.shape, point.x, point.y) }
+    }
+}
+
+fn to_msdfgen_point(point: Vector2<f64>) -> msdfgen_Point2 {
+    msdfgen_Point2 {
+        x: point.x,
+        y: point.y,
+    }
+}
+
+/// Appends a segment to an msdfgen contour.
+pub(crate) unsafe fn add_edge(
+    contour: *mut msdfgen_Contour,
+    segment: &Segment,
+    color: msdfgen_EdgeColor,
+) {
+    let layout = Layout::new::<msdfgen_EdgeHolder>();
+    let ptr = alloc(layout) as *mut msdfgen_EdgeHolder;
+    *ptr = match segment {
+        Segment::Linear([p0, p1]) => {
+            msdfgen_EdgeHolder::new2(to_msdfgen_point(*p0), to_msdfgen_point(*p1), color)
+        }
+        Segment::Quadratic([p0, p1, p2]) => msdfgen_EdgeHolder::new3(
+            to_msdfgen_point(*p0),
+            to_msdfgen_point(*p1),
+            to_msdfgen_point(*p2),
+            color,
+        ),
+        Segment::Cubic([p0, p1, p2, p3]) => msdfgen_EdgeHolder::new4(
+            to_msdfgen_point(*p0),
+            to_msdfgen_point(*p1),
+            to_msdfgen_point(*p2),
+            to_msdfgen_point(*p3),
+            color,
+        ),
+    };
+    contour.as_mut().unwrap().addEdge(ptr);
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+/// Plain 2D vector used internally for geometry computations.
+pub(crate) struct Vec2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vec2 {
+    pub fn new(x: f64, y: f64) -> Self {
+        Vec2 { x, y }
+    }
+
+    pub fn lerp(a: Vec2, b: Vec2, t: f64) -> Vec2 {
+        a + (b - a) * t
+    }
+
+    pub fn dot(self, other: Vec2) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn cross(self, other: Vec2) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.x == 0.0 && self.y == 0.0
+    }
+
+    /// Unit vector in the same direction, or zero for the zero vector.
+    pub fn normalize(self) -> Vec2 {
+        let length = self.length();
+        if length == 0.0 {
+            self
+        } else {
+            self * (1.0 / length)
+        }
+    }
+
+    /// The vector rotated by 90 degrees counterclockwise.
+    pub fn perpendicular(self) -> Vec2 {
+        Vec2::new(-self.y, self.x)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<f64> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, scale: f64) -> Vec2 {
+        Vec2::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl From<Vector2<f64>> for Vec2 {
+    fn from(v: Vector2<f64>) -> Self {
+        Vec2::new(v.x, v.y)
+    }
+}
+
+impl From<Vec2> for Vector2<f64> {
+    fn from(v: Vec2) -> Self {
+        Vector2 { x: v.x, y: v.y }
+    }
+}
@@ -0,0 +1,445 @@
+use crate::file::{from_metadata, metadata, TYPE_KEY};
+use crate::{AtlasField, FieldFileError, SDFTrait, MSDF, MTSDF, SDF};
+use half::f16;
+use image::imageops::{self, FilterType};
+use image::{ImageBuffer, Pixel};
+use std::io::{Read, Write};
+
+/// File identifier at the start of every KTX2 file.
+const IDENTIFIER: [u8; 12] = [
+    0xab, 0x4b, 0x54, 0x58, 0x20, 0x32, 0x30, 0xbb, 0x0d, 0x0a, 0x1a, 0x0a,
+];
+/// Size of the header and index, which are followed by the level index.
+const HEADER_SIZE: usize = 80;
+/// Size of one entry of the level index.
+const LEVEL_INDEX_SIZE: usize = 24;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Uncompressed pixel formats a KTX2 file can be written with. Channels of the field are stored in
+/// order, missing color channels are set to 0 and a missing alpha channel to 1.
+pub enum Ktx2Format {
+    /// `VK_FORMAT_R8_UNORM`, for [SDF](crate::SDF)s.
+    R8,
+    /// `VK_FORMAT_R8G8_UNORM`, for [SDF](crate::SDF)s.
+    RG8,
+    /// `VK_FORMAT_R8G8B8A8_UNORM`.
+    RGBA8,
+    /// `VK_FORMAT_R16_SFLOAT`, for [SDF](crate::SDF)s.
+    R16F,
+    /// `VK_FORMAT_R16G16B16A16_SFLOAT`.
+    RGBA16F,
+    /// `VK_FORMAT_R32G32B32A32_SFLOAT`.
+    RGBA32F,
+}
+
+impl Default for Ktx2Format {
+    fn default() -> Self {
+        Ktx2Format::RGBA8
+    }
+}
+
+impl Ktx2Format {
+    /// Number of channels per pixel.
+    pub fn channels(self) -> usize {
+        match self {
+            Ktx2Format::R8 | Ktx2Format::R16F => 1,
+            Ktx2Format::RG8 => 2,
+            Ktx2Format::RGBA8 | Ktx2Format::RGBA16F | Ktx2Format::RGBA32F => 4,
+        }
+    }
+
+    /// Size of a single channel in bytes.
+    pub fn sample_size(self) -> usize {
+        match self {
+            Ktx2Format::R8 | Ktx2Format::RG8 | Ktx2Format::RGBA8 => 1,
+            Ktx2Format::R16F | Ktx2Format::RGBA16F => 2,
+            Ktx2Format::RGBA32F => 4,
+        }
+    }
+
+    /// The `VkFormat` stored in the header.
+    pub fn vk_format(self) -> u32 {
+        match self {
+            Ktx2Format::R8 => 9,
+            Ktx2Format::RG8 => 16,
+            Ktx2Format::RGBA8 => 37,
+            Ktx2Format::R16F => 76,
+            Ktx2Format::RGBA16F => 97,
+            Ktx2Format::RGBA32F => 109,
+        }
+    }
+
+    fn from_vk_format(format: u32) -> Option<Self> {
+        [
+            Ktx2Format::R8,
+            Ktx2Format::RG8,
+            Ktx2Format::RGBA8,
+            Ktx2Format::R16F,
+            Ktx2Format::RGBA16F,
+            Ktx2Format::RGBA32F,
+        ]
+        .into_iter()
+        .find(|f| f.vk_format() == format)
+    }
+
+    fn is_float(self) -> bool {
+        self.sample_size() > 1
+    }
+
+    fn texel_size(self) -> usize {
+        self.channels() * self.sample_size()
+    }
+
+    fn encode(self, value: f32, output: &mut Vec<u8>) {
+        match self.sample_size() {
+            1 => output.push((value.clamp(0.0, 1.0) * 255.0).round() as u8),
+            2 => output.extend_from_slice(&f16::from_f32(value).to_bits().to_le_bytes()),
+            _ => output.extend_from_slice(&value.to_le_bytes()),
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> f32 {
+        match self.sample_size() {
+            1 => bytes[0] as f32 / 255.0,
+            2 => f16::from_bits(u16::from_le_bytes([bytes[0], bytes[1]])).to_f32(),
+            _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// KTX2 export options.
+pub struct Ktx2Config {
+    pub format: Ktx2Format,
+    /// Write a full mipmap chain, downsampled with a triangle filter. The range stays the same on
+    /// every level, so it covers fewer pixels on smaller levels.
+    pub mipmaps: bool,
+}
+
+impl Default for Ktx2Config {
+    fn default() -> Self {
+        Ktx2Config {
+            format: Default::default(),
+            mipmaps: false,
+        }
+    }
+}
+
+/// Distance fields that can be written as uncompressed KTX2 textures. The field type, range and
+/// mid-value are stored as key-value data, using the same keys as [FieldFile](crate::FieldFile).
+/// Implemented for [SDF], [MSDF], [MTSDF] and the [AtlasField] of an atlas.
+pub trait Ktx2Field: Sized {
+    /// Writes the field as a KTX2 texture. Fails if `config.format` has fewer channels than the
+    /// field.
+    fn write_ktx2<W: Write>(&self, writer: W, config: &Ktx2Config) -> Result<(), FieldFileError>;
+
+    /// Reads the first mipmap level of a texture written by [Ktx2Field::write_ktx2].
+    fn read_ktx2<R: Read>(reader: R) -> Result<Self, FieldFileError>;
+}
+
+impl<T, P> Ktx2Field for T
+where
+    T: SDFTrait<Image = ImageBuffer<P, Vec<f32>>>,
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    fn write_ktx2<W: Write>(
+        &self,
+        mut writer: W,
+        config: &Ktx2Config,
+    ) -> Result<(), FieldFileError> {
+        let format = config.format;
+        if format.channels() < P::CHANNEL_COUNT as usize {
+            return Err(FieldFileError::UnsupportedPixelFormat);
+        }
+
+        let (width, height) = self.image().dimensions();
+        let mut levels = vec![encode_level(self.image(), format)];
+        if config.mipmaps {
+            let (mut level_width, mut level_height) = (width, height);
+            while level_width > 1 || level_height > 1 {
+                level_width = (level_width / 2).max(1);
+                level_height = (level_height / 2).max(1);
+                let image = imageops::resize(
+                    self.image(),
+                    level_width,
+                    level_height,
+                    FilterType::Triangle,
+                );
+                levels.push(encode_level(&image, format));
+            }
+        }
+
+        let mut entries: Vec<(&str, String)> = metadata(self).to_vec();
+        entries.push((
+            "KTXwriter",
+            format!("msdf-rs {}", env!("CARGO_PKG_VERSION")),
+        ));
+        entries.sort();
+
+        let dfd = data_format_descriptor(format);
+        let kvd = key_value_data(&entries);
+
+        let dfd_offset = HEADER_SIZE + LEVEL_INDEX_SIZE * levels.len();
+        let kvd_offset = dfd_offset + dfd.len();
+
+        // levels are stored smallest first, each aligned to both the texel size and 4 bytes, and
+        // texel sizes are powers of two
+        let alignment = format.texel_size().max(4);
+        let mut offsets = vec![0; levels.len()];
+        let mut end = kvd_offset + kvd.len();
+        for (offset, level) in offsets.iter_mut().zip(&levels).rev() {
+            *offset = align(end, alignment);
+            end = *offset + level.len();
+        }
+
+        let mut header = Vec::with_capacity(dfd_offset);
+        header.extend_from_slice(&IDENTIFIER);
+        for value in [
+            format.vk_format(),
+            format.sample_size() as u32,
+            width,
+            height,
+            0, // depth
+            0, // layers
+            1, // faces
+            levels.len() as u32,
+            0, // supercompression
+            dfd_offset as u32,
+            dfd.len() as u32,
+            kvd_offset as u32,
+            kvd.len() as u32,
+        ] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        // no supercompression global data
+        header.extend_from_slice(&[0; 16]);
+        for (offset, level) in offsets.iter().zip(&levels) {
+            for value in [*offset, level.len(), level.len()] {
+                header.extend_from_slice(&(value as u64).to_le_bytes());
+            }
+        }
+
+        writer.write_all(&header)?;
+        writer.write_all(&dfd)?;
+        writer.write_all(&kvd)?;
+        let mut position = kvd_offset + kvd.len();
+        for (offset, level) in offsets.iter().zip(&levels).rev() {
+            writer.write_all(&vec![0; offset - position])?;
+            writer.write_all(level)?;
+            position = offset + level.len();
+        }
+
+        Ok(())
+    }
+
+    fn read_ktx2<R: Read>(mut reader: R) -> Result<Self, FieldFileError> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        if data.get(..IDENTIFIER.len()) != Some(&IDENTIFIER[..]) {
+            return Err(FieldFileError::Malformed("missing KTX2 identifier"));
+        }
+
+        let vk_format = read_u32(&data, 12)?;
+        let format =
+            Ktx2Format::from_vk_format(vk_format).ok_or(FieldFileError::UnsupportedPixelFormat)?;
+        let width = read_u32(&data, 20)?;
+        let height = read_u32(&data, 24)?;
+        let depth = read_u32(&data, 28)?;
+        let layers = read_u32(&data, 32)?;
+        let faces = read_u32(&data, 36)?;
+        let supercompression = read_u32(&data, 44)?;
+        if depth > 1 || layers > 1 || faces != 1 || supercompression != 0 {
+            return Err(FieldFileError::UnsupportedPixelFormat);
+        }
+        if format.channels() < P::CHANNEL_COUNT as usize {
+            return Err(FieldFileError::ChannelMismatch {
+                expected: P::CHANNEL_COUNT,
+                found: format.channels() as u8,
+            });
+        }
+
+        let kvd_offset = read_u32(&data, 56)? as usize;
+        let kvd_length = read_u32(&data, 60)? as usize;
+        let entries = parse_key_value_data(slice(&data, kvd_offset, kvd_length)?)?;
+
+        let level_offset = read_u64(&data, HEADER_SIZE)? as usize;
+        let level_length = read_u64(&data, HEADER_SIZE + 8)? as usize;
+        let texel_count = (width as usize)
+            .checked_mul(height as usize)
+            .ok_or(FieldFileError::Malformed("image too large"))?;
+        let level_size = texel_count
+            .checked_mul(format.texel_size())
+            .ok_or(FieldFileError::Malformed("image too large"))?;
+        if level_length != level_size {
+            return Err(FieldFileError::Malformed(
+                "level size does not match the dimensions",
+            ));
+        }
+        let level = slice(&data, level_offset, level_length)?;
+
+        let mut samples = Vec::with_capacity(texel_count * P::CHANNEL_COUNT as usize);
+        for texel in level.chunks_exact(format.texel_size()) {
+            let channels = texel
+                .chunks_exact(format.sample_size())
+                .take(P::CHANNEL_COUNT as usize);
+            samples.extend(channels.map(|bytes| format.decode(bytes)));
+        }
+
+        let image = ImageBuffer::from_raw(width, height, samples).ok_or(
+            FieldFileError::Malformed("sample count does not match the dimensions"),
+        )?;
+        from_metadata::<T, P>(image, &entries)
+    }
+}
+
+impl Ktx2Field for AtlasField {
+    fn write_ktx2<W: Write>(&self, writer: W, config: &Ktx2Config) -> Result<(), FieldFileError> {
+        match self {
+            AtlasField::SDF(field) | AtlasField::PsuedoSDF(field) => {
+                field.write_ktx2(writer, config)
+            }
+            AtlasField::MSDF(field) => field.write_ktx2(writer, config),
+            AtlasField::MTSDF(field) => field.write_ktx2(writer, config),
+        }
+    }
+
+    /// Reads a field of the type stored in the texture. Pseudo-SDFs are stored like SDFs, so
+    /// they are read back as [AtlasField::SDF].
+    fn read_ktx2<R: Read>(mut reader: R) -> Result<Self, FieldFileError> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        let kvd_offset = read_u32(&data, 56)? as usize;
+        let kvd_length = read_u32(&data, 60)? as usize;
+        let entries = parse_key_value_data(slice(&data, kvd_offset, kvd_length)?)?;
+        let field_type = entries
+            .iter()
+            .find(|(key, _)| key == TYPE_KEY)
+            .map(|(_, value)| value.as_str())
+            .ok_or(FieldFileError::MissingMetadata(TYPE_KEY))?;
+
+        match field_type {
+            "sdf" => SDF::read_ktx2(data.as_slice()).map(AtlasField::SDF),
+            "msdf" => MSDF::read_ktx2(data.as_slice()).map(AtlasField::MSDF),
+            "mtsdf" => MTSDF::read_ktx2(data.as_slice()).map(AtlasField::MTSDF),
+            _ => Err(FieldFileError::InvalidMetadata {
+                key: TYPE_KEY,
+                value: field_type.to_string(),
+            }),
+        }
+    }
+}
+
+fn encode_level<P>(image: &ImageBuffer<P, Vec<f32>>, format: Ktx2Format) -> Vec<u8>
+where
+    P: Pixel<Subpixel = f32> + 'static,
+{
+    let mut output = Vec::with_capacity(image.pixels().len() * format.texel_size());
+    for pixel in image.pixels() {
+        let channels = pixel.channels();
+        for channel in 0..format.channels() {
+            let default = if channel == 3 { 1.0 } else { 0.0 };
+            format.encode(
+                channels.get(channel).copied().unwrap_or(default),
+                &mut output,
+            );
+        }
+    }
+    output
+}
+
+/// Basic data format descriptor of an uncompressed format with linear RGBA channels.
+fn data_format_descriptor(format: Ktx2Format) -> Vec<u8> {
+    const BLOCK_HEADER_SIZE: usize = 24;
+    const SAMPLE_SIZE: usize = 16;
+
+    let block_size = BLOCK_HEADER_SIZE + SAMPLE_SIZE * format.channels();
+    let mut dfd = Vec::with_capacity(4 + block_size);
+    dfd.extend_from_slice(&((4 + block_size) as u32).to_le_bytes());
+    // Khronos vendor, basic descriptor type
+    dfd.extend_from_slice(&0u32.to_le_bytes());
+    // version 1.3 of the data format specification
+    dfd.extend_from_slice(&(2 | (block_size as u32) << 16).to_le_bytes());
+    // RGBSDA color model, BT.709 primaries, linear transfer function, straight alpha
+    dfd.extend_from_slice(&[1, 1, 1, 0]);
+    // texel block dimensions of 1x1x1x1, stored minus one
+    dfd.extend_from_slice(&[0; 4]);
+    dfd.push(format.texel_size() as u8);
+    dfd.extend_from_slice(&[0; 7]);
+
+    let bits = format.sample_size() * 8;
+    for channel in 0..format.channels() {
+        let id = if channel == 3 { 15 } else { channel as u8 };
+        let (qualifiers, lower, upper) = if format.is_float() {
+            // float and signed, normalized to [-1, 1]
+            (0xc0, (-1.0f32).to_bits(), 1.0f32.to_bits())
+        } else {
+            (0, 0, u8::MAX as u32)
+        };
+
+        dfd.extend_from_slice(&((channel * bits) as u16).to_le_bytes());
+        dfd.extend_from_slice(&[(bits - 1) as u8, id | qualifiers]);
+        dfd.extend_from_slice(&[0; 4]);
+        dfd.extend_from_slice(&lower.to_le_bytes());
+        dfd.extend_from_slice(&upper.to_le_bytes());
+    }
+
+    dfd
+}
+
+/// Encodes entries, which must be sorted by key, with null-terminated keys and values.
+fn key_value_data(entries: &[(&str, String)]) -> Vec<u8> {
+    let mut kvd = vec![];
+    for (key, value) in entries {
+        let length = key.len() + value.len() + 2;
+        kvd.extend_from_slice(&(length as u32).to_le_bytes());
+        kvd.extend_from_slice(key.as_bytes());
+        kvd.push(0);
+        kvd.extend_from_slice(value.as_bytes());
+        kvd.push(0);
+        kvd.resize(align(kvd.len(), 4), 0);
+    }
+    kvd
+}
+
+fn parse_key_value_data(mut kvd: &[u8]) -> Result<Vec<(String, String)>, FieldFileError> {
+    let mut entries = vec![];
+    while kvd.len() >= 4 {
+        let length = read_u32(kvd, 0)? as usize;
+        let entry = slice(kvd, 4, length)?;
+        let (key, value) = match entry.iter().position(|&b| b == 0) {
+            Some(end) => (&entry[..end], &entry[end + 1..]),
+            None => return Err(FieldFileError::Malformed("key without terminator")),
+        };
+        let value = value.strip_suffix(&[0]).unwrap_or(value);
+
+        entries.push((
+            String::from_utf8_lossy(key).into_owned(),
+            String::from_utf8_lossy(value).into_owned(),
+        ));
+        kvd = &kvd[align(4 + length, 4).min(kvd.len())..];
+    }
+    Ok(entries)
+}
+
+fn slice(data: &[u8], offset: usize, length: usize) -> Result<&[u8], FieldFileError> {
+    offset
+        .checked_add(length)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(FieldFileError::Malformed("unexpected end of file"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, FieldFileError> {
+    let bytes = slice(data, offset, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, FieldFileError> {
+    Ok(read_u32(data, offset)? as u64 | (read_u32(data, offset + 4)? as u64) << 32)
+}
+
+/// Rounds `offset` up to a multiple of `alignment`, which must be a power of two.
+fn align(offset: usize, alignment: usize) -> usize {
+    (offset + alignment - 1) & !(alignment - 1)
+}
@@ -52,7 +52,8 @@
 //! let sdf_config = Default::default();
 //! let msdf_config = Default::default();
 //!
-//! // Generate all types of SDF. Plain SDFs and Psuedo-SDFs do not require edge coloring.
+//! // Generate all types of SDF. Plain SDFs and Psuedo-SDFs do not require edge coloring. The range
+//! // is in shape units, so 10.0 * 64.0 is a range of 10 pixels at this projection.
 //! let sdf   = colored_shape.generate_sdf(32, 32, 10.0 * 64.0, &projection, &sdf_config);
 //! let psdf  = colored_shape.generate_psuedo_sdf(32, 32, 10.0 * 64.0, &projection, &sdf_config);
 //! let msdf  = colored_shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &msdf_config);
@@ -94,7 +95,7 @@
 //! # let image = ImageReader::open(path).unwrap().decode().unwrap();
 //! # let image = image.into_rgb32f();
 //! #
-//! // Load MSDF from an image::Rgb32FImage.
+//! // Load MSDF from an image::Rgb32FImage. The range of a loaded image is in pixels.
 //! let msdf = MSDF::from_image(image, 10.0, 0.5);
 //!
 //! // Render to a 1024x1024 image.
@@ -117,6 +118,7 @@
 //! # }
 //! ```
 
+use std::fmt;
 use std::os::raw::c_int;
 
 use image::{ImageBuffer, Luma, Rgb32FImage, Rgba32FImage};
@@ -128,60 +130,149 @@ mod test_helpers;
 #[cfg(test)]
 pub mod tests;
 
+mod analysis;
+mod artery;
+mod atlas;
+mod bc;
 mod bitmap;
+mod boolean;
+mod charset;
+mod color;
+mod coloring;
 mod config;
+mod container;
+mod dds;
+mod description;
+mod distance;
+mod file;
+mod geometry;
+mod ktx2;
 mod loader;
-
+mod offset;
+mod packed;
+mod range;
+mod svg;
+mod variation;
+
+pub use analysis::*;
+pub use artery::*;
+pub use atlas::*;
 pub use bitmap::*;
+pub use charset::*;
+pub use color::*;
+pub use coloring::*;
 pub use config::*;
+pub use container::*;
+pub use dds::*;
+pub use description::*;
+pub use distance::*;
+pub use file::*;
+pub use geometry::*;
+pub use ktx2::*;
 pub use loader::*;
+pub use offset::*;
+pub use packed::*;
+pub use range::*;
+pub use variation::*;
 
 #[derive(Debug)]
+#[non_exhaustive]
 /// Type for errors emitted by the generator.
 pub enum MsdfError {
     FreetypeInitializationFailure,
     FontLoadingFailure,
     GlyphLoadingFailure,
+    /// The font has no glyph for the requested character or name.
+    MissingGlyph,
+    /// The font isn't variable, or has no such variation axis.
+    InvalidVariation,
+}
+
+impl fmt::Display for MsdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            MsdfError::FreetypeInitializationFailure => "failed to initialize FreeType",
+            MsdfError::FontLoadingFailure => "failed to load the font",
+            MsdfError::GlyphLoadingFailure => "failed to load the glyph",
+            MsdfError::MissingGlyph => "the font has no such glyph",
+            MsdfError::InvalidVariation => "the font has no such variation axis",
+        };
+        f.write_str(message)
+    }
 }
 
+impl std::error::Error for MsdfError {}
+
 /// An msdfgen shape. Can be used to generate an SDF or Psuedo-SDF. Must be colored first using a
 /// coloring function to generate a MSDF or MTSDF.
 pub struct Shape {
     shape: msdfgen_Shape,
 }
 
+impl Clone for Shape {
+    fn clone(&self) -> Self {
+        unsafe {
+            let mut shape = msdfgen_Shape::new();
+            msdfsys_shapeCopy(&mut shape, &self.shape);
+
+            Shape { shape }
+        }
+    }
+}
+
 impl Shape {
     /// Assigns colors to edges of the shape in accordance to the multi-channel distance field
     /// technique. May split some edges if necessary. `angle` specifies the maximum angle (in
     /// radians) to be considered a corner, for example 3 (~172 degrees). Values below 1/2 PI will
     /// be treated as the external angle.
-    pub fn color_edges_simple(mut self, angle: f64) -> ColoredShape {
-        unsafe {
-            msdfgen_edgeColoringSimple(&mut self.shape, angle, 0);
-        } // hardcode seed for the time being
-
-        ColoredShape(self)
+    pub fn color_edges_simple(self, angle: f64) -> ColoredShape {
+        self.color_edges(
+            ColoringStrategy::Simple,
+            &EdgeColoringConfig { angle, seed: 0 },
+        )
     }
 
     /// The alternative "ink trap" coloring strategy is designed for better results with typefaces
     /// that use ink traps as a design feature. It guarantees that even if all edges that are
     /// shorter than both their neighboring edges are removed, the coloring remains consistent with
     /// the established rules.
-    pub fn color_edges_ink_trap(mut self, angle: f64) -> ColoredShape {
-        unsafe {
-            msdfgen_edgeColoringInkTrap(&mut self.shape, angle, 0);
-        }
-
-        ColoredShape(self)
+    pub fn color_edges_ink_trap(self, angle: f64) -> ColoredShape {
+        self.color_edges(
+            ColoringStrategy::InkTrap,
+            &EdgeColoringConfig { angle, seed: 0 },
+        )
     }
 
     /// The alternative coloring by distance tries to use different colors for edges that are close
     /// together. This should theoretically be the best strategy on average. However, since it needs
     /// to compute the distance between all pairs of edges, and perform a graph optimization task,
     /// it is much slower than the rest.
-    pub fn color_edges_by_distance(mut self, angle: f64) -> ColoredShape {
+    pub fn color_edges_by_distance(self, angle: f64) -> ColoredShape {
+        self.color_edges(
+            ColoringStrategy::ByDistance,
+            &EdgeColoringConfig { angle, seed: 0 },
+        )
+    }
+
+    /// Assigns colors to edges of the shape using the given strategy, corner angle and seed. The
+    /// result is deterministic for a given configuration.
+    pub fn color_edges(
+        mut self,
+        strategy: ColoringStrategy,
+        config: &EdgeColoringConfig,
+    ) -> ColoredShape {
         unsafe {
-            msdfgen_edgeColoringByDistance(&mut self.shape, angle, 0);
+            match strategy {
+                ColoringStrategy::Simple => {
+                    msdfgen_edgeColoringSimple(&mut self.shape, config.angle, config.seed)
+                }
+                ColoringStrategy::InkTrap => {
+                    msdfgen_edgeColoringInkTrap(&mut self.shape, config.angle, config.seed)
+                }
+                ColoringStrategy::ByDistance => {
+                    msdfgen_edgeColoringByDistance(&mut self.shape, config.angle, config.seed)
+                }
+            }
         }
 
         ColoredShape(self)
@@ -192,11 +283,13 @@ impl Shape {
         &self,
         width: u32,
         height: u32,
-        range: f64,
+        range: impl Into<DistanceRange>,
         projection: &Projection,
         config: &SDFConfig,
     ) -> SDF {
-        let image = ImageBuffer::<Luma<f32>, Vec<f32>>::new(width, height);
+        let range = range.into();
+        range.assert_valid();
+        let mut image = ImageBuffer::<Luma<f32>, Vec<f32>>::new(width, height);
 
         let msdf = msdfgen_Bitmap {
             pixels: image.as_flat_samples().samples.as_ptr() as *mut f32,
@@ -205,23 +298,37 @@ impl Shape {
             _phantom_0: Default::default(),
         };
 
+        let units = RangeUnits::ShapeUnits {
+            scale: projection.scale,
+        };
         let projection = projection.as_msdfgen_projection();
 
-        let config = msdfgen_GeneratorConfig {
+        let generator_config = msdfgen_GeneratorConfig {
             overlapSupport: config.overlap_support,
         };
 
         unsafe {
-            msdfgen_generateSDF(
-                &msdf as *const msdfgen_Bitmap<_> as *const _,
-                &self.shape,
-                &projection,
-                range,
-                &config,
-            );
+            match config.generator {
+                Generator::Modern => msdfgen_generateSDF(
+                    &msdf as *const msdfgen_Bitmap<_> as *const _,
+                    &self.shape,
+                    &projection,
+                    range.width(),
+                    &generator_config,
+                ),
+                Generator::Legacy => msdfgen_generateSDF_legacy(
+                    &msdf as *const msdfgen_Bitmap<_> as *const _,
+                    &self.shape,
+                    range.width(),
+                    &projection.scale,
+                    &projection.translate,
+                ),
+            }
         }
 
-        SDF::from_image(image, range, 0.5)
+        range.remap(&mut image);
+
+        SDF::from_image(image, range.width(), range.mid_value()).with_range_units(units)
     }
 
     /// Generates a single-channel signed pseudo-distance field.
@@ -229,11 +336,13 @@ impl Shape {
         &self,
         width: u32,
         height: u32,
-        range: f64,
+        range: impl Into<DistanceRange>,
         projection: &Projection,
         config: &SDFConfig,
     ) -> SDF {
-        let image = ImageBuffer::<Luma<f32>, Vec<f32>>::new(width, height);
+        let range = range.into();
+        range.assert_valid();
+        let mut image = ImageBuffer::<Luma<f32>, Vec<f32>>::new(width, height);
 
         let msdf = msdfgen_Bitmap {
             pixels: image.as_flat_samples().samples.as_ptr() as *mut f32,
@@ -242,28 +351,43 @@ impl Shape {
             _phantom_0: Default::default(),
         };
 
+        let units = RangeUnits::ShapeUnits {
+            scale: projection.scale,
+        };
         let projection = projection.as_msdfgen_projection();
 
-        let config = msdfgen_GeneratorConfig {
+        let generator_config = msdfgen_GeneratorConfig {
             overlapSupport: config.overlap_support,
         };
 
         unsafe {
-            msdfgen_generatePseudoSDF(
-                &msdf as *const msdfgen_Bitmap<_> as *const _,
-                &self.shape,
-                &projection,
-                range,
-                &config,
-            );
+            match config.generator {
+                Generator::Modern => msdfgen_generatePseudoSDF(
+                    &msdf as *const msdfgen_Bitmap<_> as *const _,
+                    &self.shape,
+                    &projection,
+                    range.width(),
+                    &generator_config,
+                ),
+                Generator::Legacy => msdfgen_generatePseudoSDF_legacy(
+                    &msdf as *const msdfgen_Bitmap<_> as *const _,
+                    &self.shape,
+                    range.width(),
+                    &projection.scale,
+                    &projection.translate,
+                ),
+            }
         }
 
-        SDF::from_image(image, range, 0.5)
+        range.remap(&mut image);
+
+        SDF::from_image(image, range.width(), range.mid_value()).with_range_units(units)
     }
 }
 
 /// A shape that has been colored by one of the coloring functions. A shape must be colored first
 /// before it can be used to generate an MSDF or MTSDF.
+#[derive(Clone)]
 pub struct ColoredShape(Shape);
 
 impl ColoredShape {
@@ -272,7 +396,7 @@ impl ColoredShape {
         &self,
         width: u32,
         height: u32,
-        range: f64,
+        range: impl Into<DistanceRange>,
         projection: &Projection,
         config: &SDFConfig,
     ) -> SDF {
@@ -285,7 +409,7 @@ impl ColoredShape {
         &self,
         width: u32,
         height: u32,
-        range: f64,
+        range: impl Into<DistanceRange>,
         projection: &Projection,
         config: &SDFConfig,
     ) -> SDF {
@@ -298,11 +422,13 @@ impl ColoredShape {
         &self,
         width: u32,
         height: u32,
-        range: f64,
+        range: impl Into<DistanceRange>,
         projection: &Projection,
         config: &MSDFConfig,
     ) -> MSDF {
-        let image = Rgb32FImage::new(width, height);
+        let range = range.into();
+        range.assert_valid();
+        let mut image = Rgb32FImage::new(width, height);
 
         let msdf = msdfgen_Bitmap {
             pixels: image.as_flat_samples().samples.as_ptr() as *mut f32,
@@ -311,21 +437,36 @@ impl ColoredShape {
             _phantom_0: Default::default(),
         };
 
+        let units = RangeUnits::ShapeUnits {
+            scale: projection.scale,
+        };
         let projection = projection.as_msdfgen_projection();
 
-        let config = config.as_msdfgen_config();
+        let generator_config = config.as_msdfgen_config();
 
         unsafe {
-            msdfgen_generateMSDF(
-                &msdf as *const msdfgen_Bitmap<_> as *const _,
-                &self.0.shape,
-                &projection,
-                range,
-                &config,
-            );
+            match config.generator {
+                Generator::Modern => msdfgen_generateMSDF(
+                    &msdf as *const msdfgen_Bitmap<_> as *const _,
+                    &self.0.shape,
+                    &projection,
+                    range.width(),
+                    &generator_config,
+                ),
+                Generator::Legacy => msdfgen_generateMSDF_legacy(
+                    &msdf as *const msdfgen_Bitmap<_> as *const _,
+                    &self.0.shape,
+                    range.width(),
+                    &projection.scale,
+                    &projection.translate,
+                    generator_config.errorCorrection,
+                ),
+            }
         }
 
-        MSDF::from_image(image, range, 0.5)
+        range.remap(&mut image);
+
+        MSDF::from_image(image, range.width(), range.mid_value()).with_range_units(units)
     }
 
     /// Generates a multi-channel signed distance field with true distance in the alpha channel.
@@ -333,11 +474,13 @@ impl ColoredShape {
         &self,
         width: u32,
         height: u32,
-        range: f64,
+        range: impl Into<DistanceRange>,
         projection: &Projection,
         config: &MSDFConfig,
     ) -> MTSDF {
-        let image = Rgba32FImage::new(width, height);
+        let range = range.into();
+        range.assert_valid();
+        let mut image = Rgba32FImage::new(width, height);
 
         let msdf = msdfgen_Bitmap {
             pixels: image.as_flat_samples().samples.as_ptr() as *mut f32,
@@ -346,20 +489,35 @@ impl ColoredShape {
             _phantom_0: Default::default(),
         };
 
+        let units = RangeUnits::ShapeUnits {
+            scale: projection.scale,
+        };
         let projection = projection.as_msdfgen_projection();
 
-        let config = config.as_msdfgen_config();
+        let generator_config = config.as_msdfgen_config();
 
         unsafe {
-            msdfgen_generateMTSDF(
-                &msdf as *const msdfgen_Bitmap<_> as *const _,
-                &self.0.shape,
-                &projection,
-                range,
-                &config,
-            );
+            match config.generator {
+                Generator::Modern => msdfgen_generateMTSDF(
+                    &msdf as *const msdfgen_Bitmap<_> as *const _,
+                    &self.0.shape,
+                    &projection,
+                    range.width(),
+                    &generator_config,
+                ),
+                Generator::Legacy => msdfgen_generateMTSDF_legacy(
+                    &msdf as *const msdfgen_Bitmap<_> as *const _,
+                    &self.0.shape,
+                    range.width(),
+                    &projection.scale,
+                    &projection.translate,
+                    generator_config.errorCorrection,
+                ),
+            }
         }
 
-        MTSDF::from_image(image, range, 0.5)
+        range.remap(&mut image);
+
+        MTSDF::from_image(image, range.width(), range.mid_value()).with_range_units(units)
     }
 }
@@ -1,13 +1,12 @@
-use crate::Shape;
+use crate::geometry::add_edge;
+use crate::{MsdfError, Segment, Shape};
 use mint::Vector2;
-use msdf_sys::{
-    msdfgen_Contour, msdfgen_EdgeColor_WHITE, msdfgen_EdgeHolder, msdfgen_Point2, msdfgen_Shape,
-};
-use std::alloc::{alloc, Layout};
-use ttf_parser::{Face, GlyphId, OutlineBuilder};
-
-fn point_from_font_coords(x: f32, y: f32) -> msdfgen_Point2 {
-    msdfgen_Point2 {
+use msdf_sys::{msdfgen_Contour, msdfgen_EdgeColor_WHITE, msdfgen_Shape};
+use std::collections::HashMap;
+use ttf_parser::{fonts_in_collection, name_id, Face, GlyphId, OutlineBuilder};
+
+fn point_from_font_coords(x: f32, y: f32) -> Vector2<f64> {
+    Vector2 {
         x: x as f64,
         y: y as f64,
     }
@@ -19,6 +18,28 @@ pub trait GlyphLoader {
     fn load_shape(&self, glyph: Self::Glyph) -> Option<Shape>;
 }
 
+/// Loads glyphs by character or name rather than by glyph, for loaders that can look them up.
+/// Kept apart from [GlyphLoader] so that existing implementations of it don't need to change.
+pub trait CharGlyphLoader: GlyphLoader {
+    /// Loads the glyph mapped to a character. Fails with [MsdfError::MissingGlyph] if there is no
+    /// such glyph, and with [MsdfError::GlyphLoadingFailure] if it has no outline, like a space.
+    fn load_char(&self, c: char) -> Result<Shape, MsdfError>;
+
+    /// Loads a glyph by its PostScript name, such as `A` or `uni0041`. Names come from the `post`
+    /// table, or from the charset of CFF fonts.
+    fn load_glyph_name(&self, name: &str) -> Result<Shape, MsdfError>;
+
+    /// Loads the glyphs of several characters with [CharGlyphLoader::load_char], keyed by
+    /// character.
+    fn load_chars<I>(&self, chars: I) -> HashMap<char, Result<Shape, MsdfError>>
+    where
+        I: IntoIterator<Item = char>,
+        Self: Sized,
+    {
+        chars.into_iter().map(|c| (c, self.load_char(c))).collect()
+    }
+}
+
 struct ShapeOutlineBuilder {
     shape: msdfgen_Shape,
     cur_pos: Vector2<f32>,
@@ -34,6 +55,18 @@ impl From<ShapeOutlineBuilder> for Shape {
     }
 }
 
+impl ShapeOutlineBuilder {
+    fn add_segment(&mut self, segment: Segment) {
+        unsafe {
+            add_edge(
+                self.current_contour.unwrap(),
+                &segment,
+                msdfgen_EdgeColor_WHITE,
+            );
+        }
+    }
+}
+
 impl OutlineBuilder for ShapeOutlineBuilder {
     fn move_to(&mut self, x: f32, y: f32) {
         unsafe {
@@ -43,47 +76,29 @@ impl OutlineBuilder for ShapeOutlineBuilder {
     }
 
     fn line_to(&mut self, x: f32, y: f32) {
-        unsafe {
-            let layout = Layout::new::<msdfgen_EdgeHolder>();
-            let ptr = alloc(layout) as *mut msdfgen_EdgeHolder;
-            *ptr = msdfgen_EdgeHolder::new2(
-                point_from_font_coords(self.cur_pos.x, self.cur_pos.y),
-                point_from_font_coords(x, y),
-                msdfgen_EdgeColor_WHITE,
-            );
-            self.current_contour.unwrap().as_mut().unwrap().addEdge(ptr);
-        }
+        self.add_segment(Segment::Linear([
+            point_from_font_coords(self.cur_pos.x, self.cur_pos.y),
+            point_from_font_coords(x, y),
+        ]));
         self.cur_pos = Vector2 { x, y };
     }
 
     fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
-        unsafe {
-            let layout = Layout::new::<msdfgen_EdgeHolder>();
-            let ptr = alloc(layout) as *mut msdfgen_EdgeHolder;
-            *ptr = msdfgen_EdgeHolder::new3(
-                point_from_font_coords(self.cur_pos.x, self.cur_pos.y),
-                point_from_font_coords(x1, y1),
-                point_from_font_coords(x, y),
-                msdfgen_EdgeColor_WHITE,
-            );
-            self.current_contour.unwrap().as_mut().unwrap().addEdge(ptr);
-        }
+        self.add_segment(Segment::Quadratic([
+            point_from_font_coords(self.cur_pos.x, self.cur_pos.y),
+            point_from_font_coords(x1, y1),
+            point_from_font_coords(x, y),
+        ]));
         self.cur_pos = Vector2 { x, y };
     }
 
     fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
-        unsafe {
-            let layout = Layout::new::<msdfgen_EdgeHolder>();
-            let ptr = alloc(layout) as *mut msdfgen_EdgeHolder;
-            *ptr = msdfgen_EdgeHolder::new4(
-                point_from_font_coords(self.cur_pos.x, self.cur_pos.y),
-                point_from_font_coords(x1, y1),
-                point_from_font_coords(x2, y2),
-                point_from_font_coords(x, y),
-                msdfgen_EdgeColor_WHITE,
-            );
-            self.current_contour.unwrap().as_mut().unwrap().addEdge(ptr);
-        }
+        self.add_segment(Segment::Cubic([
+            point_from_font_coords(self.cur_pos.x, self.cur_pos.y),
+            point_from_font_coords(x1, y1),
+            point_from_font_coords(x2, y2),
+            point_from_font_coords(x, y),
+        ]));
         self.cur_pos = Vector2 { x, y };
     }
 
@@ -110,3 +125,107 @@ impl GlyphLoader for Face<'_> {
         }
     }
 }
+
+impl CharGlyphLoader for Face<'_> {
+    fn load_char(&self, c: char) -> Result<Shape, MsdfError> {
+        let glyph = self.glyph_index(c).ok_or(MsdfError::MissingGlyph)?;
+        self.load_shape(glyph).ok_or(MsdfError::GlyphLoadingFailure)
+    }
+
+    fn load_glyph_name(&self, name: &str) -> Result<Shape, MsdfError> {
+        // names can only be looked up by glyph, so search all of them
+        let glyph = (0..self.number_of_glyphs())
+            .map(GlyphId)
+            .find(|&glyph| self.glyph_name(glyph) == Some(name))
+            .ok_or(MsdfError::MissingGlyph)?;
+        self.load_shape(glyph).ok_or(MsdfError::GlyphLoadingFailure)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Names of a face in a [FontSource].
+pub struct FaceInfo {
+    /// Index of the face in the collection, 0 for single fonts.
+    pub index: u32,
+    /// Family name, such as `Noto Sans CJK JP`. Typographic names are preferred over the legacy
+    /// ones, which split heavier weights into their own family.
+    pub family: Option<String>,
+    /// Style name, such as `Bold`.
+    pub style: Option<String>,
+}
+
+#[derive(Copy, Clone)]
+/// Font data holding a single face, or several for `.ttc` and `.otc` collections. Faces are loaded
+/// as [Face]s, which are [GlyphLoader]s.
+pub struct FontSource<'a> {
+    data: &'a [u8],
+    face_count: u32,
+}
+
+impl<'a> FontSource<'a> {
+    /// Wraps the data of a font or collection. Fails with [MsdfError::FontLoadingFailure] if its
+    /// first face can't be parsed.
+    pub fn new(data: &'a [u8]) -> Result<Self, MsdfError> {
+        let source = FontSource {
+            data,
+            face_count: fonts_in_collection(data).unwrap_or(1),
+        };
+        source.face(0)?;
+        Ok(source)
+    }
+
+    /// Whether the data is a collection rather than a single font.
+    pub fn is_collection(&self) -> bool {
+        fonts_in_collection(self.data).is_some()
+    }
+
+    pub fn face_count(&self) -> u32 {
+        self.face_count
+    }
+
+    /// Parses the face at `index`. Fails with [MsdfError::FontLoadingFailure] if there is no such
+    /// face or it is malformed.
+    pub fn face(&self, index: u32) -> Result<Face<'a>, MsdfError> {
+        Face::from_slice(self.data, index).map_err(|_| MsdfError::FontLoadingFailure)
+    }
+
+    /// Names of every face, in collection order. Faces that can't be parsed are skipped.
+    pub fn faces(&self) -> Vec<FaceInfo> {
+        (0..self.face_count)
+            .filter_map(|index| {
+                let face = self.face(index).ok()?;
+                Some(FaceInfo {
+                    index,
+                    family: face_name(&face, name_id::TYPOGRAPHIC_FAMILY, name_id::FAMILY),
+                    style: face_name(&face, name_id::TYPOGRAPHIC_SUBFAMILY, name_id::SUBFAMILY),
+                })
+            })
+            .collect()
+    }
+
+    /// Finds a face by family and style name, ignoring ASCII case. Fails with
+    /// [MsdfError::FontLoadingFailure] if no face matches.
+    pub fn find_face(&self, family: &str, style: &str) -> Result<Face<'a>, MsdfError> {
+        let is_named = |name: &Option<String>, expected: &str| match name {
+            Some(name) => name.eq_ignore_ascii_case(expected),
+            None => false,
+        };
+        let info = self
+            .faces()
+            .into_iter()
+            .find(|info| is_named(&info.family, family) && is_named(&info.style, style))
+            .ok_or(MsdfError::FontLoadingFailure)?;
+        self.face(info.index)
+    }
+}
+
+/// First Unicode name with the preferred ID, or with the fallback ID if there is none.
+fn face_name(face: &Face, preferred: u16, fallback: u16) -> Option<String> {
+    let name = |name_id: u16| {
+        face.names()
+            .into_iter()
+            .filter(|name| name.name_id == name_id && name.is_unicode())
+            .find_map(|name| name.to_string())
+    };
+    name(preferred).or_else(|| name(fallback))
+}
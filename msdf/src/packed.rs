@@ -0,0 +1,224 @@
+use crate::{
+    ColoredShape, DistanceRange, GrayFImage, MSDFConfig, Projection, RangeUnits, SDFConfig,
+    SDFTrait, MSDF, SDF,
+};
+use image::{Rgb32FImage, Rgba32FImage};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// What a single channel of a [PackedField] holds.
+pub enum PackedChannel {
+    /// Left at zero.
+    Empty,
+    /// True signed distance, as generated by [ColoredShape::generate_sdf].
+    SDF,
+    /// Signed pseudo-distance, as generated by [ColoredShape::generate_psuedo_sdf].
+    PseudoSDF,
+    /// Red channel of the multi-channel field generated by [ColoredShape::generate_msdf].
+    MSDFRed,
+    /// Green channel of the multi-channel field.
+    MSDFGreen,
+    /// Blue channel of the multi-channel field.
+    MSDFBlue,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Contents of the red, green, blue and alpha channels of a [PackedField].
+pub struct PackedLayout(pub [PackedChannel; 4]);
+
+impl PackedLayout {
+    /// Multi-channel field in RGB and true distance in alpha, equivalent to an MTSDF.
+    pub const MSDF_SDF: PackedLayout = PackedLayout([
+        PackedChannel::MSDFRed,
+        PackedChannel::MSDFGreen,
+        PackedChannel::MSDFBlue,
+        PackedChannel::SDF,
+    ]);
+
+    /// Multi-channel field in RGB and pseudo-distance in alpha.
+    pub const MSDF_PSEUDO_SDF: PackedLayout = PackedLayout([
+        PackedChannel::MSDFRed,
+        PackedChannel::MSDFGreen,
+        PackedChannel::MSDFBlue,
+        PackedChannel::PseudoSDF,
+    ]);
+
+    /// Index of the first channel holding `channel`.
+    pub fn position(&self, channel: PackedChannel) -> Option<usize> {
+        self.0.iter().position(|&c| c == channel)
+    }
+
+    fn contains(&self, channel: PackedChannel) -> bool {
+        self.position(channel).is_some()
+    }
+}
+
+/// Several distance fields of the same shape, packed into the channels of one four-channel [f32]
+/// image as described by a [PackedLayout].
+pub struct PackedField {
+    image: Rgba32FImage,
+    range: f64,
+    units: RangeUnits,
+    mid_value: f32,
+    layout: PackedLayout,
+}
+
+impl PackedField {
+    /// Create a packed field from an image and the layout it was packed with. `range` is in
+    /// pixels of the image, see [PackedField::with_range_units] for other units.
+    pub fn from_image(
+        image: Rgba32FImage,
+        range: f64,
+        mid_value: f32,
+        layout: PackedLayout,
+    ) -> Self {
+        PackedField {
+            image,
+            range,
+            units: RangeUnits::Pixels,
+            mid_value,
+            layout,
+        }
+    }
+
+    /// Change the units the range of this field is measured in.
+    pub fn with_range_units(self, units: RangeUnits) -> Self {
+        PackedField { units, ..self }
+    }
+
+    /// Convert a packed field to an image.
+    pub fn to_image(self) -> Rgba32FImage {
+        self.image
+    }
+
+    /// Get packed image.
+    pub fn image(&self) -> &Rgba32FImage {
+        &self.image
+    }
+
+    /// Get SDF range, in [PackedField::range_units].
+    pub fn range(&self) -> f64 {
+        self.range
+    }
+
+    /// Get the units of the SDF range.
+    pub fn range_units(&self) -> RangeUnits {
+        self.units
+    }
+
+    /// Get SDF range in pixels of the packed image.
+    pub fn pixel_range(&self) -> f64 {
+        self.range * self.units.pixels_per_unit()
+    }
+
+    /// Get SDF mid-value.
+    pub fn mid_value(&self) -> f32 {
+        self.mid_value
+    }
+
+    /// Get the layout of the channels.
+    pub fn layout(&self) -> PackedLayout {
+        self.layout
+    }
+
+    /// Extracts the true distance field, if the layout contains one.
+    pub fn sdf(&self) -> Option<SDF> {
+        self.single_channel(PackedChannel::SDF)
+    }
+
+    /// Extracts the pseudo-distance field, if the layout contains one.
+    pub fn psuedo_sdf(&self) -> Option<SDF> {
+        self.single_channel(PackedChannel::PseudoSDF)
+    }
+
+    /// Extracts the multi-channel field, if the layout contains all three of its channels.
+    pub fn msdf(&self) -> Option<MSDF> {
+        let red = self.layout.position(PackedChannel::MSDFRed)?;
+        let green = self.layout.position(PackedChannel::MSDFGreen)?;
+        let blue = self.layout.position(PackedChannel::MSDFBlue)?;
+
+        let image = Rgb32FImage::from_fn(self.image.width(), self.image.height(), |x, y| {
+            let pixel = self.image.get_pixel(x, y).0;
+            [pixel[red], pixel[green], pixel[blue]].into()
+        });
+
+        Some(MSDF::from_image(image, self.range, self.mid_value).with_range_units(self.units))
+    }
+
+    fn single_channel(&self, channel: PackedChannel) -> Option<SDF> {
+        let index = self.layout.position(channel)?;
+
+        let image = GrayFImage::from_fn(self.image.width(), self.image.height(), |x, y| {
+            [self.image.get_pixel(x, y).0[index]].into()
+        });
+
+        Some(SDF::from_image(image, self.range, self.mid_value).with_range_units(self.units))
+    }
+}
+
+impl ColoredShape {
+    /// Generates every field required by `layout` and packs them into the channels of a single
+    /// image. Each field is generated as if by its own generation function, using the overlap
+    /// support and generator of `config`.
+    pub fn generate_packed(
+        &self,
+        width: u32,
+        height: u32,
+        range: impl Into<DistanceRange>,
+        projection: &Projection,
+        layout: PackedLayout,
+        config: &MSDFConfig,
+    ) -> PackedField {
+        let range = range.into();
+        range.assert_valid();
+        let sdf_config = SDFConfig {
+            overlap_support: config.overlap_support,
+            generator: config.generator,
+        };
+
+        let sdf = layout
+            .contains(PackedChannel::SDF)
+            .then(|| self.generate_sdf(width, height, range, projection, &sdf_config));
+        let psuedo_sdf = layout
+            .contains(PackedChannel::PseudoSDF)
+            .then(|| self.generate_psuedo_sdf(width, height, range, projection, &sdf_config));
+        let msdf = [
+            PackedChannel::MSDFRed,
+            PackedChannel::MSDFGreen,
+            PackedChannel::MSDFBlue,
+        ]
+        .iter()
+        .any(|&channel| layout.contains(channel))
+        .then(|| self.generate_msdf(width, height, range, projection, config));
+
+        let single = |field: &Option<SDF>, x, y| {
+            field
+                .as_ref()
+                .map_or(0.0, |field| field.image().get_pixel(x, y).0[0])
+        };
+        let multi = |index: usize, x, y| {
+            msdf.as_ref()
+                .map_or(0.0, |msdf| msdf.image().get_pixel(x, y).0[index])
+        };
+
+        let image = Rgba32FImage::from_fn(width, height, |x, y| {
+            let mut pixel = [0.0; 4];
+            for (value, channel) in pixel.iter_mut().zip(layout.0) {
+                *value = match channel {
+                    PackedChannel::Empty => 0.0,
+                    PackedChannel::SDF => single(&sdf, x, y),
+                    PackedChannel::PseudoSDF => single(&psuedo_sdf, x, y),
+                    PackedChannel::MSDFRed => multi(0, x, y),
+                    PackedChannel::MSDFGreen => multi(1, x, y),
+                    PackedChannel::MSDFBlue => multi(2, x, y),
+                };
+            }
+            pixel.into()
+        });
+
+        PackedField::from_image(image, range.width(), range.mid_value(), layout).with_range_units(
+            RangeUnits::ShapeUnits {
+                scale: projection.scale,
+            },
+        )
+    }
+}
@@ -0,0 +1,114 @@
+use mint::Vector2;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Range of signed distances a field can represent. Generation functions take it in shape units.
+/// Distances are positive inside the shape. `lower` is stored as 0 and `upper` as 1, so an
+/// asymmetric range spends more of the precision of a quantized field on one side of the outline,
+/// for example on a wide outer glow.
+///
+/// A plain `f64` converts to a symmetric range of that width, matching msdfgen's `range`.
+/// Generation functions panic if `lower` is not below `upper`, see [DistanceRange::new].
+pub struct DistanceRange {
+    /// Distance stored as 0, usually negative (outside the shape).
+    pub lower: f64,
+    /// Distance stored as 1, usually positive (inside the shape).
+    pub upper: f64,
+}
+
+impl DistanceRange {
+    /// A range between `lower` and `upper`, or `None` unless both are finite and `lower` is below
+    /// `upper`.
+    pub fn new(lower: f64, upper: f64) -> Option<Self> {
+        let range = DistanceRange { lower, upper };
+        range.is_valid().then_some(range)
+    }
+
+    /// A range of the given width, centered on the outline.
+    pub fn symmetric(width: f64) -> Self {
+        DistanceRange {
+            lower: -width / 2.0,
+            upper: width / 2.0,
+        }
+    }
+
+    /// Recovers the range from the width and mid-value stored by fields.
+    pub fn from_mid_value(width: f64, mid_value: f32) -> Self {
+        DistanceRange {
+            lower: -mid_value as f64 * width,
+            upper: (1.0 - mid_value as f64) * width,
+        }
+    }
+
+    /// Difference between the upper and lower bound.
+    pub fn width(&self) -> f64 {
+        self.upper - self.lower
+    }
+
+    /// Value stored for a distance of 0, which lies on the outline.
+    pub fn mid_value(&self) -> f32 {
+        (-self.lower / self.width()) as f32
+    }
+
+    /// Whether both bounds are finite and `lower` is below `upper`. Other ranges have no positive
+    /// width and would produce NaN values.
+    pub fn is_valid(&self) -> bool {
+        self.lower.is_finite() && self.upper.is_finite() && self.lower < self.upper
+    }
+
+    /// Panics unless the range [is valid](DistanceRange::is_valid).
+    pub(crate) fn assert_valid(&self) {
+        assert!(
+            self.is_valid(),
+            "invalid distance range from {} to {}",
+            self.lower,
+            self.upper
+        );
+    }
+
+    /// Shifts values generated by msdfgen with a symmetric range of the same width into this
+    /// range.
+    pub(crate) fn remap(&self, samples: &mut [f32]) {
+        let shift = self.mid_value() - 0.5;
+        if shift == 0.0 {
+            return;
+        }
+
+        for sample in samples {
+            *sample += shift;
+        }
+    }
+}
+
+impl From<f64> for DistanceRange {
+    fn from(width: f64) -> Self {
+        DistanceRange::symmetric(width)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Units the range of a field is measured in.
+pub enum RangeUnits {
+    /// Pixels of the field itself. Fields loaded with [SDFTrait::from_image](crate::SDFTrait::from_image)
+    /// use these units.
+    Pixels,
+    /// Units of the shape the field was generated from, as passed to the generation functions.
+    /// `scale` is the scale of the projection used, which converts shape units to pixels.
+    ShapeUnits { scale: Vector2<f64> },
+}
+
+impl RangeUnits {
+    /// Number of field pixels per unit. For non-uniform scales the smaller factor is used, like
+    /// msdfgen does.
+    pub fn pixels_per_unit(&self) -> f64 {
+        match self {
+            RangeUnits::Pixels => 1.0,
+            RangeUnits::ShapeUnits { scale } => scale.x.min(scale.y),
+        }
+    }
+}
+
+impl Default for RangeUnits {
+    fn default() -> Self {
+        RangeUnits::Pixels
+    }
+}
@@ -0,0 +1,275 @@
+use crate::description::{ParsedContours, Parser};
+use crate::geometry::Vec2;
+use crate::{EdgeColor, ParseShapeError, Segment, Shape};
+use std::f64::consts::PI;
+
+impl Shape {
+    /// Parses the path data of an SVG `<path>` element, the value of its `d` attribute. All
+    /// commands are supported, with elliptical arcs converted to cubic segments. Open subpaths are
+    /// closed with a line, since fields can only be generated for closed contours. SVG uses y-down
+    /// coordinates, so the y axis of the shape is not inverted.
+    pub fn from_svg_path(path: &str) -> Result<Shape, ParseShapeError> {
+        Ok(Shape::from_parsed(&parse_svg_path(path)?, false))
+    }
+}
+
+pub(crate) fn parse_svg_path(path: &str) -> Result<ParsedContours, ParseShapeError> {
+    let mut parser = Parser::new(path);
+    let mut builder = PathBuilder::default();
+    let mut command = None;
+
+    while let Some(c) = parser.peek() {
+        if c.is_ascii_alphabetic() {
+            parser.next();
+            command = Some(c);
+        } else if command.is_none() {
+            return Err(parser.error("expected a path command"));
+        }
+        let c = command.unwrap();
+        let relative = c.is_ascii_lowercase();
+        let origin = if relative {
+            builder.current
+        } else {
+            Vec2::default()
+        };
+
+        match c.to_ascii_uppercase() {
+            b'M' => {
+                let point = origin + coordinates(&mut parser)?;
+                builder.move_to(point);
+                // further pairs are implicit lines
+                command = Some(if relative { b'l' } else { b'L' });
+            }
+            b'L' => {
+                let point = origin + coordinates(&mut parser)?;
+                builder.line_to(point);
+            }
+            b'H' => {
+                let x = number(&mut parser)?;
+                let point = Vec2::new(x + origin.x, builder.current.y);
+                builder.line_to(point);
+            }
+            b'V' => {
+                let y = number(&mut parser)?;
+                let point = Vec2::new(builder.current.x, y + origin.y);
+                builder.line_to(point);
+            }
+            b'C' => {
+                let p1 = origin + coordinates(&mut parser)?;
+                let p2 = origin + coordinates(&mut parser)?;
+                let end = origin + coordinates(&mut parser)?;
+                builder.cubic_to(p1, p2, end);
+            }
+            b'S' => {
+                let p1 = builder.reflected_cubic_control();
+                let p2 = origin + coordinates(&mut parser)?;
+                let end = origin + coordinates(&mut parser)?;
+                builder.cubic_to(p1, p2, end);
+            }
+            b'Q' => {
+                let p1 = origin + coordinates(&mut parser)?;
+                let end = origin + coordinates(&mut parser)?;
+                builder.quadratic_to(p1, end);
+            }
+            b'T' => {
+                let p1 = builder.reflected_quadratic_control();
+                let end = origin + coordinates(&mut parser)?;
+                builder.quadratic_to(p1, end);
+            }
+            b'A' => {
+                let rx = number(&mut parser)?;
+                let ry = number(&mut parser)?;
+                let rotation = number(&mut parser)?;
+                let large_arc = flag(&mut parser)?;
+                let sweep = flag(&mut parser)?;
+                let end = origin + coordinates(&mut parser)?;
+                builder.arc_to(
+                    Vec2::new(rx, ry),
+                    rotation.to_radians(),
+                    large_arc,
+                    sweep,
+                    end,
+                );
+            }
+            b'Z' => {
+                builder.close();
+                // a close is never repeated implicitly
+                command = None;
+                continue;
+            }
+            _ => return Err(parser.error("unknown path command")),
+        }
+        parser.eat(b',');
+    }
+
+    builder.close();
+    Ok(builder.contours)
+}
+
+fn number(parser: &mut Parser) -> Result<f64, ParseShapeError> {
+    let number = parser
+        .number()
+        .ok_or_else(|| parser.error("expected a number"))?;
+    parser.eat(b',');
+    Ok(number)
+}
+
+fn coordinates(parser: &mut Parser) -> Result<Vec2, ParseShapeError> {
+    let x = number(parser)?;
+    let y = number(parser)?;
+    Ok(Vec2::new(x, y))
+}
+
+/// Arc flags are single digits, which may be written without separators.
+fn flag(parser: &mut Parser) -> Result<bool, ParseShapeError> {
+    let flag = match parser.next() {
+        Some(b'0') => false,
+        Some(b'1') => true,
+        _ => return Err(parser.error("expected an arc flag")),
+    };
+    parser.eat(b',');
+    Ok(flag)
+}
+
+#[derive(Default)]
+struct PathBuilder {
+    contours: ParsedContours,
+    contour: Vec<(Segment, EdgeColor)>,
+    start: Vec2,
+    current: Vec2,
+}
+
+impl PathBuilder {
+    fn push(&mut self, segment: Segment, end: Vec2) {
+        self.contour.push((segment, EdgeColor::White));
+        self.current = end;
+    }
+
+    fn move_to(&mut self, point: Vec2) {
+        self.close();
+        self.start = point;
+        self.current = point;
+    }
+
+    fn line_to(&mut self, point: Vec2) {
+        if point != self.current {
+            self.push(Segment::Linear([self.current.into(), point.into()]), point);
+        }
+    }
+
+    fn quadratic_to(&mut self, p1: Vec2, end: Vec2) {
+        let segment = Segment::Quadratic([self.current.into(), p1.into(), end.into()]);
+        self.push(segment, end);
+    }
+
+    fn cubic_to(&mut self, p1: Vec2, p2: Vec2, end: Vec2) {
+        let segment = Segment::Cubic([self.current.into(), p1.into(), p2.into(), end.into()]);
+        self.push(segment, end);
+    }
+
+    /// First control point of a smooth cubic, the reflection of the previous second control point.
+    fn reflected_cubic_control(&self) -> Vec2 {
+        match self.contour.last() {
+            Some((Segment::Cubic([_, _, p2, end]), _)) => Vec2::from(*end) * 2.0 - Vec2::from(*p2),
+            _ => self.current,
+        }
+    }
+
+    /// Control point of a smooth quadratic, the reflection of the previous control point.
+    fn reflected_quadratic_control(&self) -> Vec2 {
+        match self.contour.last() {
+            Some((Segment::Quadratic([_, p1, end]), _)) => Vec2::from(*end) * 2.0 - Vec2::from(*p1),
+            _ => self.current,
+        }
+    }
+
+    /// Adds an elliptical arc as cubic segments of at most a quarter turn, following the endpoint
+    /// to center conversion of the SVG specification.
+    fn arc_to(&mut self, radii: Vec2, rotation: f64, large_arc: bool, sweep: bool, end: Vec2) {
+        let start = self.current;
+        let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+        if start == end {
+            return;
+        }
+        if rx == 0.0 || ry == 0.0 {
+            self.line_to(end);
+            return;
+        }
+
+        let (sin, cos) = rotation.sin_cos();
+        let half = (start - end) * 0.5;
+        let p = Vec2::new(cos * half.x + sin * half.y, -sin * half.x + cos * half.y);
+
+        // scale up radii that are too small to reach the end point
+        let lambda = (p.x * p.x) / (rx * rx) + (p.y * p.y) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = rx * rx * ry * ry - rx * rx * p.y * p.y - ry * ry * p.x * p.x;
+        let denominator = rx * rx * p.y * p.y + ry * ry * p.x * p.x;
+        let mut factor = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            factor = -factor;
+        }
+        let center_prime = Vec2::new(factor * rx * p.y / ry, -factor * ry * p.x / rx);
+        let middle = (start + end) * 0.5;
+        let center = Vec2::new(
+            cos * center_prime.x - sin * center_prime.y + middle.x,
+            sin * center_prime.x + cos * center_prime.y + middle.y,
+        );
+
+        let angle = |v: Vec2| v.y.atan2(v.x);
+        let start_angle = angle(Vec2::new(
+            (p.x - center_prime.x) / rx,
+            (p.y - center_prime.y) / ry,
+        ));
+        let mut sweep_angle = angle(Vec2::new(
+            (-p.x - center_prime.x) / rx,
+            (-p.y - center_prime.y) / ry,
+        )) - start_angle;
+        if sweep && sweep_angle < 0.0 {
+            sweep_angle += 2.0 * PI;
+        } else if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= 2.0 * PI;
+        }
+
+        let point_at = |theta: f64| {
+            let (sin_theta, cos_theta) = theta.sin_cos();
+            Vec2::new(
+                center.x + rx * cos * cos_theta - ry * sin * sin_theta,
+                center.y + rx * sin * cos_theta + ry * cos * sin_theta,
+            )
+        };
+        let derivative_at = |theta: f64| {
+            let (sin_theta, cos_theta) = theta.sin_cos();
+            Vec2::new(
+                -rx * cos * sin_theta - ry * sin * cos_theta,
+                -rx * sin * sin_theta + ry * cos * cos_theta,
+            )
+        };
+
+        let parts = (sweep_angle.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+        let step = sweep_angle / parts as f64;
+        // length of the control arms of a cubic approximating an arc of this angle
+        let arm = 4.0 / 3.0 * (step / 4.0).tan();
+        for i in 0..parts {
+            let from = start_angle + step * i as f64;
+            let to = from + step;
+            let p1 = self.current + derivative_at(from) * arm;
+            let last = if i + 1 == parts { end } else { point_at(to) };
+            let p2 = last - derivative_at(to) * arm;
+            self.cubic_to(p1, p2, last);
+        }
+    }
+
+    /// Ends the current subpath, closing it with a line if needed.
+    fn close(&mut self) {
+        if !self.contour.is_empty() {
+            self.line_to(self.start);
+            self.contours.push(std::mem::take(&mut self.contour));
+        }
+        self.current = self.start;
+    }
+}
@@ -1,20 +1,39 @@
-use crate::{GlyphLoader, Projection, SDFTrait, Shape, MSDF, MTSDF, SDF};
-use image::DynamicImage;
+use crate::{
+    parse_glyph_set, raw_header_path, Affine2, ArteryEncoding, Atlas, AtlasConfig, AtlasField,
+    AtlasGlyph, AtlasRect, AtlasType, CharGlyphLoader, Charset, ColorGlyphLoader, ColoredShape,
+    ColoringSearchConfig, ColoringStrategy, ContainerError, ContainerField, ContainerHeader,
+    DdsField, DdsFormat, DistanceRange, EdgeColor, EdgeColoringConfig, EdgeColoringError, FaceInfo,
+    FieldFile, FieldFileError, FontSource, FontVariations, Generator, GlyphLoader, JoinStyle,
+    Ktx2Config, Ktx2Field, Ktx2Format, LayerColor, MSDFConfig, MsdfError, PackedChannel,
+    PackedLayout, Projection, RangeUnits, SDFConfig, SDFTrait, SampleFormat, Segment, Shape, MSDF,
+    MTSDF, SDF,
+};
+use image::{DynamicImage, Rgba};
 use std::default::Default;
 use std::env;
 use std::fs::File;
 use std::io::{BufReader, Read};
-use ttf_parser::Face;
+use ttf_parser::{Face, GlyphId, Tag, Variation};
 
+use crate::color::{parse_color_layers, parse_palette, resolve_color, LayerRecord};
+use crate::coloring::render_difference;
 use crate::test_helpers::compare_images;
+use crate::variation::parse_named_instances;
+#[cfg(feature = "serde")]
+use crate::ProjectedField;
 use image::io::Reader as ImageReader;
 use mint::Vector2;
 
-fn with_glyph<F: FnOnce(Shape, Projection)>(glyph: char, size: u32, callback: F) {
+fn with_face<F: FnOnce(&Face)>(callback: F) {
+    with_font("Roboto-Medium.ttf", callback)
+}
+
+/// Loads a font from the test resources, such as the fixtures of `make_fixtures.py`.
+fn with_font<F: FnOnce(&Face)>(name: &str, callback: F) {
     let path = env::current_dir()
         .unwrap()
         .join("test_resources")
-        .join("Roboto-Medium.ttf");
+        .join(name);
     let file = File::open(path).unwrap();
     let mut reader = BufReader::new(file);
 
@@ -24,25 +43,31 @@ fn with_glyph<F: FnOnce(Shape, Projection)>(glyph: char, size: u32, callback: F)
 
     let face = Face::from_slice(font_data.as_slice(), 0).unwrap();
 
-    let glyph_index = face.glyph_index(glyph).unwrap();
+    callback(&face);
+}
 
-    let bb = face.glyph_bounding_box(glyph_index).unwrap();
+fn with_glyph<F: FnOnce(Shape, Projection)>(glyph: char, size: u32, callback: F) {
+    with_face(|face| {
+        let glyph_index = face.glyph_index(glyph).unwrap();
 
-    let scale = Vector2 {
-        x: 1.0 / 64.0,
-        y: 1.0 / 64.0,
-    };
-    let translation = Vector2 {
-        // try to come up with a decent fit
-        x: (size as f64 * 64.0 - (bb.width() as f64)) / 2.0 - (bb.x_min as f64),
-        y: (size as f64 * 64.0 - (bb.height() as f64)) / 2.0 - (bb.y_min as f64),
-    };
+        let bb = face.glyph_bounding_box(glyph_index).unwrap();
 
-    let projection = Projection { scale, translation };
+        let scale = Vector2 {
+            x: 1.0 / 64.0,
+            y: 1.0 / 64.0,
+        };
+        let translation = Vector2 {
+            // try to come up with a decent fit
+            x: (size as f64 * 64.0 - (bb.width() as f64)) / 2.0 - (bb.x_min as f64),
+            y: (size as f64 * 64.0 - (bb.height() as f64)) / 2.0 - (bb.y_min as f64),
+        };
 
-    let shape = face.load_shape(glyph_index).unwrap();
+        let projection = Projection { scale, translation };
 
-    callback(shape, projection);
+        let shape = face.load_shape(glyph_index).unwrap();
+
+        callback(shape, projection);
+    });
 }
 
 #[test]
@@ -98,42 +123,2035 @@ fn can_generate_mtsdf() {
 }
 
 #[test]
-fn can_project_msdf() {
-    with_glyph('E', 32, |shape, _| {
+fn can_generate_legacy_sdf() {
+    // without overlapping contours, both generators compute the same true distance
+    with_glyph('A', 32, |shape, projection| {
+        let config = SDFConfig {
+            generator: Generator::Legacy,
+            ..Default::default()
+        };
+
+        let sdf = shape.generate_sdf(32, 32, 10.0 * 64.0, &projection, &config);
+        let sdf: DynamicImage = DynamicImage::from(sdf.to_image());
+        let sdf = sdf.into_rgba8();
+
+        compare_images(&sdf, "sdf");
+    });
+}
+
+#[test]
+fn can_generate_legacy_fields() {
+    with_glyph('B', 32, |shape, projection| {
+        let range = 10.0 * 64.0;
+        let sdf_config = SDFConfig {
+            generator: Generator::Legacy,
+            ..Default::default()
+        };
+        let msdf_config = MSDFConfig {
+            generator: Generator::Legacy,
+            ..Default::default()
+        };
+
+        let psdf = shape.generate_psuedo_sdf(32, 32, range, &projection, &sdf_config);
+        let plain = shape.clone();
         let shape = shape.color_edges_simple(3.0);
+        let msdf = shape.generate_msdf(32, 32, range, &projection, &msdf_config);
+        let mtsdf = shape.generate_mtsdf(32, 32, range, &projection, &msdf_config);
 
-        let projection = Projection {
-            scale: Vector2 {
-                x: 1.5 / 64.0,
-                y: 0.5 / 64.0,
+        assert!(shape.measure_quality(&psdf, &projection).sign_flips <= 2);
+        assert!(shape.measure_quality(&msdf, &projection).sign_flips <= 2);
+        assert!(shape.measure_quality(&mtsdf, &projection).sign_flips <= 2);
+        assert!(mtsdf.image().pixels().all(|pixel| pixel.0[3].is_finite()));
+
+        // the legacy generators skip some corrections, but render the same glyph as the current
+        // ones
+        let psdf_reference =
+            plain.generate_psuedo_sdf(32, 32, range, &projection, &Default::default());
+        let msdf_reference = shape.generate_msdf(32, 32, range, &projection, &Default::default());
+        let mtsdf_reference = shape.generate_mtsdf(32, 32, range, &projection, &Default::default());
+        assert!(render_difference(&psdf.render(128, 128), &psdf_reference.render(128, 128)) < 0.02);
+        assert!(render_difference(&msdf.render(128, 128), &msdf_reference.render(128, 128)) < 0.02);
+        assert!(
+            render_difference(&mtsdf.render(128, 128), &mtsdf_reference.render(128, 128)) < 0.02
+        );
+    });
+}
+
+#[test]
+fn can_generate_asymmetric_range() {
+    with_glyph('C', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let range = DistanceRange {
+            lower: -8.0 * 64.0,
+            upper: 2.0 * 64.0,
+        };
+
+        let symmetric = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let asymmetric = shape.generate_msdf(32, 32, range, &projection, &Default::default());
+
+        assert_eq!(asymmetric.range(), 10.0 * 64.0);
+        assert!((asymmetric.mid_value() - 0.8).abs() < 1e-6);
+        assert!((asymmetric.distance_range().lower - range.lower).abs() < 1e-3);
+        assert!((asymmetric.distance_range().upper - range.upper).abs() < 1e-3);
+
+        for (a, b) in symmetric.image().pixels().zip(asymmetric.image().pixels()) {
+            for channel in 0..3 {
+                let a = (a.0[channel] - symmetric.mid_value()) as f64 * symmetric.range();
+                let b = (b.0[channel] - asymmetric.mid_value()) as f64 * asymmetric.range();
+                assert!((a - b).abs() < 1e-2);
+            }
+        }
+
+        // rendering takes the mid-value into account, so both fields look the same
+        let symmetric = symmetric.render(64, 64);
+        let asymmetric = asymmetric.render(64, 64);
+        for (a, b) in symmetric.pixels().zip(asymmetric.pixels()) {
+            assert!((a.0[0] - b.0[0]).abs() < 1e-3);
+        }
+    });
+}
+
+#[test]
+fn rejects_invalid_range() {
+    assert!(DistanceRange::new(-8.0, 2.0).is_some());
+    assert!(DistanceRange::new(2.0, 2.0).is_none());
+    assert!(DistanceRange::new(2.0, -8.0).is_none());
+    assert!(DistanceRange::new(f64::NEG_INFINITY, 2.0).is_none());
+    assert!(DistanceRange::new(f64::NAN, 2.0).is_none());
+    assert!(!DistanceRange::symmetric(0.0).is_valid());
+}
+
+#[test]
+#[should_panic(expected = "invalid distance range")]
+fn generation_rejects_invalid_range() {
+    with_glyph('C', 32, |shape, projection| {
+        let range = DistanceRange {
+            lower: 2.0 * 64.0,
+            upper: -8.0 * 64.0,
+        };
+        shape.generate_sdf(32, 32, range, &projection, &Default::default());
+    });
+}
+
+#[test]
+fn can_get_pixel_range() {
+    with_glyph('A', 32, |shape, projection| {
+        let sdf = shape.generate_sdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+
+        assert_eq!(sdf.range(), 10.0 * 64.0);
+        assert_eq!(
+            sdf.range_units(),
+            RangeUnits::ShapeUnits {
+                scale: projection.scale
+            }
+        );
+        assert_eq!(sdf.pixel_range(), 10.0);
+
+        // a field loaded back from an image needs its range in pixels
+        let loaded = SDF::from_image(sdf.image().clone(), 10.0, 0.5);
+        assert_eq!(loaded.range_units(), RangeUnits::Pixels);
+        assert_eq!(loaded.pixel_range(), 10.0);
+        assert_eq!(loaded.render(64, 64), sdf.render(64, 64));
+
+        let report = shape.measure_quality(&loaded, &projection);
+        assert!(report.max_deviation < 10.0 * 64.0 * 1e-4);
+    });
+}
+
+#[test]
+fn container_round_trip() {
+    with_glyph('D', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let sdf = shape.generate_sdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let msdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let mtsdf = shape.generate_mtsdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+
+        let mut data = vec![];
+        sdf.write_container(&mut data, SampleFormat::F32, Some(&projection))
+            .unwrap();
+        let (read, header) = SDF::read_container(data.as_slice()).unwrap();
+        assert_eq!(header.channels, 1);
+        assert_eq!(header.projection, Some(projection));
+        assert_eq!(read.range(), sdf.range());
+        assert_eq!(read.range_units(), sdf.range_units());
+        assert_eq!(read.mid_value(), sdf.mid_value());
+        assert_eq!(read.to_image(), sdf.to_image());
+
+        let mut data = vec![];
+        msdf.write_container(&mut data, SampleFormat::F32, None)
+            .unwrap();
+        let (read, header) = MSDF::read_container(data.as_slice()).unwrap();
+        assert_eq!(header.projection, None);
+        assert_eq!(read.to_image(), msdf.to_image());
+
+        let mut data = vec![];
+        mtsdf
+            .write_container(&mut data, SampleFormat::F32, None)
+            .unwrap();
+        let (read, _) = MTSDF::read_container(data.as_slice()).unwrap();
+        assert_eq!(read.to_image(), mtsdf.to_image());
+    });
+}
+
+#[test]
+fn container_quantizes_samples() {
+    with_glyph('E', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let msdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+
+        for (format, step) in [(SampleFormat::U8, 255.0), (SampleFormat::U16, 65535.0)] {
+            let mut data = vec![];
+            msdf.write_container(&mut data, format, None).unwrap();
+            let (read, header) = MSDF::read_container(data.as_slice()).unwrap();
+
+            assert_eq!(header.format, format);
+            for (a, b) in read.image().iter().zip(msdf.image().iter()) {
+                assert!((a - b.clamp(0.0, 1.0)).abs() <= 0.5 / step + f32::EPSILON);
+            }
+        }
+    });
+}
+
+#[test]
+fn container_rejects_invalid_data() {
+    let sdf = SDF::from_image(image::ImageBuffer::new(4, 4), 4.0, 0.5);
+    let mut data = vec![];
+    sdf.write_container(&mut data, SampleFormat::U8, None)
+        .unwrap();
+
+    assert!(matches!(
+        MSDF::read_container(data.as_slice()),
+        Err(ContainerError::ChannelMismatch {
+            expected: 3,
+            found: 1
+        })
+    ));
+    assert!(matches!(
+        SDF::read_container(&data[..data.len() - 1]),
+        Err(ContainerError::Io(_))
+    ));
+
+    let mut newer = data.clone();
+    newer[4] = 0xff;
+    assert!(matches!(
+        ContainerHeader::read(newer.as_slice()),
+        Err(ContainerError::UnsupportedVersion(_))
+    ));
+
+    let mut garbage = data;
+    garbage[0] = b'X';
+    assert!(matches!(
+        SDF::read_container(garbage.as_slice()),
+        Err(ContainerError::InvalidMagic)
+    ));
+
+    // dimensions whose sample count overflows are rejected before reading any samples
+    let mtsdf = MTSDF::from_image(image::ImageBuffer::new(1, 1), 4.0, 0.5);
+    let mut huge = vec![];
+    mtsdf
+        .write_container(&mut huge, SampleFormat::F32, None)
+        .unwrap();
+    huge[8..16].copy_from_slice(&[0xff; 8]);
+    assert!(matches!(
+        MTSDF::read_container(huge.as_slice()),
+        Err(ContainerError::TooLarge)
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn can_serialize_fields() {
+    with_glyph('F', 32, |shape, projection| {
+        let sdf = shape.generate_sdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+
+        let json = serde_json::to_string(&sdf).unwrap();
+        let read: SDF = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(read.range_units(), sdf.range_units());
+        assert_eq!(read.image(), sdf.image());
+        assert!(serde_json::from_str::<MSDF>(&json).is_err());
+
+        let projected = ProjectedField {
+            field: sdf,
+            projection: Some(projection),
+        };
+        let json = serde_json::to_string(&projected).unwrap();
+        let read: ProjectedField<SDF> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(read.projection, projected.projection);
+        assert_eq!(read.field.image(), projected.field.image());
+        // a bare field ignores the stored projection
+        assert!(serde_json::from_str::<SDF>(&json).is_ok());
+    });
+}
+
+#[test]
+fn png_round_trip() {
+    with_glyph('G', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let msdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let path = env::temp_dir().join("msdf_png_round_trip.png");
+
+        msdf.save_png(&path).unwrap();
+        let read = MSDF::load_png(&path).unwrap();
+
+        assert_eq!(read.range(), msdf.range());
+        assert_eq!(read.range_units(), msdf.range_units());
+        assert_eq!(read.mid_value(), msdf.mid_value());
+        for (a, b) in read.image().iter().zip(msdf.image().iter()) {
+            assert!((a - b.clamp(0.0, 1.0)).abs() <= 0.5 / 255.0 + f32::EPSILON);
+        }
+
+        assert!(matches!(
+            SDF::load_png(&path),
+            Err(FieldFileError::ChannelMismatch {
+                expected: 1,
+                found: 3
+            })
+        ));
+    });
+}
+
+#[test]
+fn tiff_round_trip() {
+    with_glyph('H', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let mtsdf = shape.generate_mtsdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let path = env::temp_dir().join("msdf_tiff_round_trip.tiff");
+
+        mtsdf.save_tiff(&path).unwrap();
+        let read = MTSDF::load_tiff(&path).unwrap();
+
+        assert_eq!(read.range(), mtsdf.range());
+        assert_eq!(read.range_units(), mtsdf.range_units());
+        assert_eq!(read.mid_value(), mtsdf.mid_value());
+        assert_eq!(read.to_image(), mtsdf.to_image());
+    });
+}
+
+#[test]
+fn ktx2_round_trip() {
+    with_glyph('I', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let sdf = shape.generate_sdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let mtsdf = shape.generate_mtsdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+
+        let config = Ktx2Config {
+            format: Ktx2Format::RGBA32F,
+            ..Default::default()
+        };
+        let mut data = vec![];
+        mtsdf.write_ktx2(&mut data, &config).unwrap();
+        let read = MTSDF::read_ktx2(data.as_slice()).unwrap();
+        assert_eq!(read.range(), mtsdf.range());
+        assert_eq!(read.range_units(), mtsdf.range_units());
+        assert_eq!(read.mid_value(), mtsdf.mid_value());
+        assert_eq!(read.to_image(), mtsdf.to_image());
+
+        let config = Ktx2Config {
+            format: Ktx2Format::R8,
+            ..Default::default()
+        };
+        let mut data = vec![];
+        sdf.write_ktx2(&mut data, &config).unwrap();
+        let read = SDF::read_ktx2(data.as_slice()).unwrap();
+        for (a, b) in read.image().iter().zip(sdf.image().iter()) {
+            assert!((a - b.clamp(0.0, 1.0)).abs() <= 0.5 / 255.0 + f32::EPSILON);
+        }
+
+        // half floats aren't clamped, and keep 11 significant bits
+        let config = Ktx2Config {
+            format: Ktx2Format::R16F,
+            ..Default::default()
+        };
+        let mut data = vec![];
+        sdf.write_ktx2(&mut data, &config).unwrap();
+        let read = SDF::read_ktx2(data.as_slice()).unwrap();
+        for (a, b) in read.image().iter().zip(sdf.image().iter()) {
+            assert!((a - b).abs() <= b.abs() / 2048.0 + 1e-6);
+        }
+    });
+}
+
+#[test]
+fn ktx2_writes_mipmaps() {
+    let sdf = SDF::from_image(image::ImageBuffer::new(16, 4), 4.0, 0.5);
+    let config = Ktx2Config {
+        format: Ktx2Format::R8,
+        mipmaps: true,
+    };
+
+    let mut data = vec![];
+    sdf.write_ktx2(&mut data, &config).unwrap();
+    let level_count = u32::from_le_bytes(data[40..44].try_into().unwrap());
+    assert_eq!(level_count, 5);
+    assert_eq!(SDF::read_ktx2(data.as_slice()).unwrap().image().width(), 16);
+
+    let mut data = vec![];
+    sdf.write_ktx2(&mut data, &Default::default()).unwrap();
+    assert!(matches!(
+        MSDF::read_ktx2(data.as_slice()),
+        Err(FieldFileError::InvalidMetadata { .. })
+    ));
+
+    let msdf = MSDF::from_image(image::ImageBuffer::new(4, 4), 4.0, 0.5);
+    assert!(msdf.write_ktx2(&mut vec![], &config).is_err());
+}
+
+#[test]
+fn can_write_atlas_ktx2() {
+    // pseudo-SDFs are stored like SDFs
+    let field = AtlasField::PsuedoSDF(SDF::from_image(image::ImageBuffer::new(4, 4), 2.0, 0.5));
+    let mut data = vec![];
+    field.write_ktx2(&mut data, &Default::default()).unwrap();
+    let read = AtlasField::read_ktx2(data.as_slice()).unwrap();
+    assert_eq!(read.atlas_type(), AtlasType::SDF);
+    assert_eq!(read.pixel_range(), 2.0);
+
+    with_face(|face| {
+        let config = AtlasConfig {
+            atlas_type: AtlasType::MTSDF,
+            ..Default::default()
+        };
+        let atlas = Atlas::from_face(face, "AB".chars(), &config);
+        let path = env::temp_dir().join("msdf_atlas.ktx2");
+
+        let ktx2_config = Ktx2Config {
+            format: Ktx2Format::RGBA32F,
+            mipmaps: false,
+        };
+        atlas
+            .field
+            .write_ktx2(File::create(&path).unwrap(), &ktx2_config)
+            .unwrap();
+        let read = AtlasField::read_ktx2(File::open(&path).unwrap()).unwrap();
+
+        assert_eq!(read.atlas_type(), AtlasType::MTSDF);
+        assert_eq!(read.dimensions(), atlas.field.dimensions());
+        assert_eq!(read.pixel_range(), atlas.field.pixel_range());
+        match (&read, &atlas.field) {
+            (AtlasField::MTSDF(read), AtlasField::MTSDF(field)) => {
+                assert_eq!(read.image(), field.image())
+            }
+            _ => panic!("expected MTSDF atlases"),
+        }
+    });
+}
+
+#[test]
+fn ktx2_rejects_overflowing_dimensions() {
+    let mtsdf = MTSDF::from_image(image::ImageBuffer::new(1, 1), 4.0, 0.5);
+    let mut data = vec![];
+    mtsdf
+        .write_ktx2(
+            &mut data,
+            &Ktx2Config {
+                format: Ktx2Format::RGBA32F,
+                mipmaps: false,
             },
-            translation: Vector2 {
-                x: 4.0 * 64.0,
-                y: 4.0 * 64.0,
+        )
+        .unwrap();
+
+    // a header claiming more texels than can be addressed fails instead of overflowing
+    data[20..28].copy_from_slice(&[0xff; 8]);
+    assert!(matches!(
+        MTSDF::read_ktx2(data.as_slice()),
+        Err(FieldFileError::Malformed(_))
+    ));
+}
+
+#[test]
+fn dds_round_trip() {
+    with_glyph('J', 30, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let mtsdf = shape.generate_mtsdf(30, 30, 10.0 * 64.0, &projection, &Default::default());
+
+        let mut data = vec![];
+        mtsdf.write_dds(&mut data, DdsFormat::RGBA32F).unwrap();
+        let read =
+            MTSDF::read_dds(data.as_slice(), mtsdf.pixel_range(), mtsdf.mid_value()).unwrap();
+        assert_eq!(read.range_units(), RangeUnits::Pixels);
+        assert_eq!(read.to_image(), mtsdf.to_image());
+
+        assert!(matches!(
+            MTSDF::read_dds(&data[..data.len() - 1], 10.0, 0.5),
+            Err(FieldFileError::Malformed(_))
+        ));
+    });
+}
+
+#[test]
+fn dds_rejects_overflowing_dimensions() {
+    let mtsdf = MTSDF::from_image(image::ImageBuffer::new(1, 1), 4.0, 0.5);
+    let mut data = vec![];
+    mtsdf.write_dds(&mut data, DdsFormat::RGBA32F).unwrap();
+
+    // a header claiming more pixels than can be addressed fails instead of overflowing
+    data[12..20].copy_from_slice(&[0xff; 8]);
+    assert!(matches!(
+        MTSDF::read_dds(data.as_slice(), 4.0, 0.5),
+        Err(FieldFileError::Malformed(_))
+    ));
+}
+
+#[test]
+fn block_compression_preserves_glyphs() {
+    with_glyph('K', 30, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let sdf = shape.generate_sdf(30, 30, 10.0 * 64.0, &projection, &Default::default());
+        let msdf = shape.generate_msdf(30, 30, 10.0 * 64.0, &projection, &Default::default());
+
+        let mut data = vec![];
+        sdf.write_dds(&mut data, DdsFormat::BC4).unwrap();
+        let bc4 = SDF::read_dds(data.as_slice(), sdf.pixel_range(), sdf.mid_value()).unwrap();
+        assert!(MSDF::read_dds(data.as_slice(), 10.0, 0.5).is_err());
+
+        let mut data = vec![];
+        msdf.write_dds(&mut data, DdsFormat::BC7).unwrap();
+        let bc7 = MSDF::read_dds(data.as_slice(), msdf.pixel_range(), msdf.mid_value()).unwrap();
+
+        // compression error compared to the uncompressed fields, in shape units
+        let uncompressed = shape.measure_quality(&sdf, &projection);
+        let compressed = shape.measure_quality(&bc4, &projection);
+        assert!(compressed.mean_error - uncompressed.mean_error < 0.02 * 640.0);
+        assert!(compressed.sign_flips <= uncompressed.sign_flips + 2);
+
+        // BC7 fits all channels of a block on a single line, so errors are larger
+        let uncompressed = shape.measure_quality(&msdf, &projection);
+        let compressed = shape.measure_quality(&bc7, &projection);
+        assert!(compressed.mean_error - uncompressed.mean_error < 0.03 * 640.0);
+        assert!(compressed.sign_flips <= uncompressed.sign_flips + 8);
+    });
+}
+
+#[test]
+fn raw_float_round_trip() {
+    with_glyph('L', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let msdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let path = env::temp_dir().join("msdf_raw_round_trip.bin");
+
+        msdf.save_raw(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 32 * 32 * 3 * 4);
+        let read = MSDF::load_raw(&path).unwrap();
+
+        assert_eq!(read.range(), msdf.range());
+        assert_eq!(read.range_units(), msdf.range_units());
+        assert_eq!(read.mid_value(), msdf.mid_value());
+        assert_eq!(read.to_image(), msdf.to_image());
+
+        std::fs::remove_file(raw_header_path(&path)).unwrap();
+        assert!(matches!(MSDF::load_raw(&path), Err(FieldFileError::Io(_))));
+    });
+}
+
+#[test]
+fn raw_float_rejects_overflowing_dimensions() {
+    let mtsdf = MTSDF::from_image(image::ImageBuffer::new(1, 1), 4.0, 0.5);
+    let path = env::temp_dir().join("msdf_raw_overflow.bin");
+    mtsdf.save_raw(&path).unwrap();
+
+    // a sidecar claiming more samples than can be addressed fails instead of overflowing
+    let header = std::fs::read_to_string(raw_header_path(&path)).unwrap();
+    let header = header
+        .lines()
+        .map(|line| match line.split_once('=') {
+            Some((key @ ("width" | "height"), _)) => format!("{}={}", key, u32::MAX),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    std::fs::write(raw_header_path(&path), header).unwrap();
+
+    assert!(matches!(
+        MTSDF::load_raw(&path),
+        Err(FieldFileError::Malformed(_))
+    ));
+    std::fs::remove_file(raw_header_path(&path)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "exr")]
+#[test]
+fn exr_round_trip() {
+    with_glyph('M', 32, |shape, projection| {
+        let sdf = shape.generate_sdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let path = env::temp_dir().join("msdf_exr_round_trip.exr");
+
+        sdf.save_exr(&path).unwrap();
+        let read = SDF::load_exr(&path).unwrap();
+
+        assert_eq!(read.range(), sdf.range());
+        assert_eq!(read.range_units(), sdf.range_units());
+        assert_eq!(read.to_image(), sdf.to_image());
+        assert!(matches!(
+            MTSDF::load_exr(&path),
+            Err(FieldFileError::ChannelMismatch { .. })
+        ));
+    });
+}
+
+#[test]
+fn can_generate_atlas() {
+    with_face(|face| {
+        let config = AtlasConfig {
+            padding: 1,
+            max_width: 128,
+            ..Default::default()
+        };
+        let atlas = Atlas::from_face(face, "Hello, World!".chars(), &config);
+        let (width, height) = atlas.field.dimensions();
+        assert!(width <= 128);
+        assert_eq!(atlas.field.atlas_type(), AtlasType::MSDF);
+        assert_eq!(atlas.field.pixel_range(), config.pixel_range);
+
+        // repeated characters are only packed once
+        assert_eq!(atlas.glyphs.len(), 10);
+        assert!(atlas.glyph(' ').unwrap().atlas_rect.is_none());
+
+        let rects: Vec<AtlasRect> = atlas.glyphs.iter().filter_map(|g| g.atlas_rect).collect();
+        assert_eq!(rects.len(), 9);
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.x >= 1 && a.x + a.width < width);
+            assert!(a.y >= 1 && a.y + a.height < height);
+            for b in &rects[i + 1..] {
+                let apart_x = a.x + a.width < b.x || b.x + b.width < a.x;
+                let apart_y = a.y + a.height < b.y || b.y + b.height < a.y;
+                assert!(apart_x || apart_y);
+            }
+        }
+
+        // the quad of a glyph covers its outline, in ems
+        let o = atlas.glyph('o').unwrap();
+        let bounds = o.plane_bounds.unwrap();
+        let bb = face.glyph_bounding_box(o.glyph_id).unwrap();
+        let units_per_em = face.units_per_em() as f64;
+        assert!(bounds.left < bb.x_min as f64 / units_per_em);
+        assert!(bounds.top > bb.y_max as f64 / units_per_em);
+        let rect = o.atlas_rect.unwrap();
+        assert!((bounds.width() * atlas.em_size - rect.width as f64).abs() < 1e-9);
+    });
+}
+
+#[test]
+fn arfont_round_trip() {
+    with_face(|face| {
+        let config = AtlasConfig {
+            atlas_type: AtlasType::MTSDF,
+            ..Default::default()
+        };
+        let atlas = Atlas::from_face(face, "AVATAR ".chars(), &config);
+
+        let mut data = vec![];
+        atlas
+            .write_arfont(&mut data, ArteryEncoding::RawFloat)
+            .unwrap();
+        let read = Atlas::read_arfont(data.as_slice()).unwrap();
+
+        assert_eq!(read.em_size, atlas.em_size);
+        assert_eq!(read.kerning.len(), atlas.kerning.len());
+        assert!((read.metrics.line_height - atlas.metrics.line_height).abs() < 1e-6);
+        for (read, glyph) in read.glyphs.iter().zip(&atlas.glyphs) {
+            assert_eq!(read.codepoint, glyph.codepoint);
+            assert_eq!(read.glyph_id, glyph.glyph_id);
+            assert_eq!(read.atlas_rect, glyph.atlas_rect);
+            assert!((read.advance - glyph.advance).abs() < 1e-6);
+        }
+        match (&read.field, &atlas.field) {
+            (AtlasField::MTSDF(read), AtlasField::MTSDF(field)) => {
+                assert_eq!(read.pixel_range(), field.pixel_range());
+                assert_eq!(read.image(), field.image());
+            }
+            _ => panic!("atlas type changed"),
+        }
+
+        let mut data = vec![];
+        atlas.write_arfont(&mut data, ArteryEncoding::Png).unwrap();
+        assert!(Atlas::read_arfont(data.as_slice()).is_ok());
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(matches!(
+            Atlas::read_arfont(data.as_slice()),
+            Err(FieldFileError::Malformed(_))
+        ));
+    });
+}
+
+#[test]
+fn can_read_atlas_gen_arfont() {
+    // written by make_fixtures.py in msdf-atlas-gen's layout, see there for replacing it with a
+    // file written by msdf-atlas-gen
+    let path = env::current_dir()
+        .unwrap()
+        .join("test_resources")
+        .join("atlas_gen_msdf.arfont");
+    let data = std::fs::read(path).unwrap();
+    let atlas = Atlas::read_arfont(data.as_slice()).unwrap();
+
+    assert_eq!(atlas.em_size, 32.0);
+    assert!((atlas.metrics.ascender - 0.927734375).abs() < 1e-6);
+    let field = match &atlas.field {
+        AtlasField::MSDF(field) => field,
+        _ => panic!("expected an MSDF atlas"),
+    };
+    assert_eq!(field.pixel_range(), 2.0);
+    assert_eq!(field.image().dimensions(), (8, 6));
+    // the PNG is stored top to bottom
+    let pixel = field.image().get_pixel(1, 2).0;
+    assert_eq!(pixel, [32.0 / 255.0, 80.0 / 255.0, 223.0 / 255.0]);
+
+    let codepoints: Vec<Option<char>> = atlas.glyphs.iter().map(|g| g.codepoint).collect();
+    assert_eq!(codepoints, [Some(' '), Some('A'), Some('V')]);
+    assert!(atlas.glyphs[0].atlas_rect.is_none() && atlas.glyphs[0].plane_bounds.is_none());
+
+    // bounds inset by half a pixel cover whole pixels once read
+    let a = &atlas.glyphs[1];
+    assert_eq!(
+        a.atlas_rect,
+        Some(AtlasRect {
+            x: 0,
+            y: 2,
+            width: 4,
+            height: 4
+        })
+    );
+    let bounds = a.plane_bounds.unwrap();
+    assert_eq!(
+        [bounds.left, bounds.bottom, bounds.right, bounds.top],
+        [-0.125, -0.125, 0.375, 0.375]
+    );
+    assert_eq!(a.advance, 0.625);
+    let v = &atlas.glyphs[2];
+    assert_eq!(
+        v.atlas_rect,
+        Some(AtlasRect {
+            x: 4,
+            y: 2,
+            width: 4,
+            height: 4
+        })
+    );
+    assert_eq!(v.plane_bounds.unwrap().left, -0.0625);
+
+    assert_eq!(atlas.kerning.len(), 1);
+    assert_eq!(atlas.kerning[0].advance, -77.0 / 2048.0);
+
+    // a glyph above the image, with the checksum updated
+    let mut moved = data[..data.len() - 4].to_vec();
+    let glyph_a = 112 + 192 + 48;
+    moved[glyph_a + 36..glyph_a + 40].copy_from_slice(&20.5f32.to_le_bytes());
+    let checksum = crc32fast::hash(&moved);
+    moved.extend_from_slice(&checksum.to_le_bytes());
+    assert!(matches!(
+        Atlas::read_arfont(moved.as_slice()),
+        Err(FieldFileError::Malformed(_))
+    ));
+}
+
+#[test]
+fn can_write_arfont_records() {
+    let glyph = |c: char, id: u16, x: u32| AtlasGlyph {
+        codepoint: Some(c),
+        glyph_id: GlyphId(id),
+        advance: 0.5,
+        plane_bounds: Some(crate::Bounds {
+            left: 0.0,
+            bottom: 0.0,
+            right: 0.25,
+            top: 0.25,
+        }),
+        atlas_rect: Some(AtlasRect {
+            x,
+            y: 0,
+            width: 2,
+            height: 2,
+        }),
+    };
+    let atlas = Atlas {
+        field: AtlasField::MSDF(MSDF::from_image(image::ImageBuffer::new(8, 4), 2.0, 0.5)),
+        // two characters sharing a glyph
+        glyphs: vec![glyph('A', 36, 0), glyph('V', 57, 2), glyph('W', 57, 4)],
+        kerning: vec![
+            crate::KerningPair {
+                left: GlyphId(36),
+                right: GlyphId(57),
+                advance: -0.25,
             },
+            crate::KerningPair {
+                left: GlyphId(36),
+                right: GlyphId(999),
+                advance: -0.5,
+            },
+        ],
+        layers: vec![],
+        metrics: Default::default(),
+        em_size: 8.0,
+    };
+
+    let mut data = vec![];
+    atlas.write_arfont(&mut data, ArteryEncoding::Png).unwrap();
+
+    // image types of the Artery Font specification, in the variant and the image header
+    let u32_at = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+    assert_eq!(u32_at(112 + 12), 6);
+    let image = 112 + u32_at(60) as usize;
+    assert_eq!(u32_at(image + 24), 6);
+
+    let read = Atlas::read_arfont(data.as_slice()).unwrap();
+    let codepoints: Vec<Option<char>> = read.glyphs.iter().map(|g| g.codepoint).collect();
+    assert_eq!(codepoints, [Some('A'), Some('V'), Some('W')]);
+    assert_eq!(read.glyphs[2].glyph_id, GlyphId(57));
+    assert_eq!(read.glyphs[1].atlas_rect, atlas.glyphs[1].atlas_rect);
+    // pairs with glyphs outside of the atlas are left out
+    assert_eq!(
+        read.kerning,
+        [crate::KerningPair {
+            left: GlyphId(36),
+            right: GlyphId(57),
+            advance: -0.25,
+        }]
+    );
+}
+
+#[test]
+fn can_read_gpos_kerning() {
+    with_face(|face| {
+        // Roboto only kerns with the pair adjustments of its GPOS table
+        assert!(face.tables().kern.is_none());
+        let glyphs: Vec<AtlasGlyph> = "AVTo."
+            .chars()
+            .map(|c| AtlasGlyph {
+                codepoint: Some(c),
+                glyph_id: face.glyph_index(c).unwrap(),
+                advance: 0.0,
+                plane_bounds: None,
+                atlas_rect: None,
+            })
+            .collect();
+        let kerning = crate::atlas::kerning(face, &glyphs);
+        let advance = |left, right| {
+            let (left, right) = (face.glyph_index(left), face.glyph_index(right));
+            kerning
+                .iter()
+                .find(|pair| Some(pair.left) == left && Some(pair.right) == right)
+                .map(|pair| pair.advance)
         };
 
-        let sdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
-        let sdf: DynamicImage = DynamicImage::from(sdf.to_image());
-        let sdf = sdf.into_rgba8();
+        assert_eq!(advance('A', 'V'), Some(-77.0 / 2048.0));
+        assert_eq!(advance('T', 'o'), Some(-208.0 / 2048.0));
+        assert_eq!(advance('o', 'A'), None);
+        assert!(kerning.iter().all(|pair| pair.advance != 0.0));
+    });
+}
 
-        compare_images(&sdf, "projected_msdf");
+#[test]
+fn can_parse_charset() {
+    let charset = Charset::parse("[0x41, 'C'], \"x\u{e9}\\\"\" 100 '\\n'").unwrap();
+    assert_eq!(charset.iter().collect::<String>(), "\n\"ABCdx\u{e9}");
+
+    let error = Charset::parse("[1, 2").unwrap_err();
+    assert_eq!(error.offset, 5);
+    assert_eq!(Charset::parse("65 0x110000").unwrap_err().offset, 3);
+
+    let glyphs = parse_glyph_set("[3, 5] 1").unwrap();
+    assert_eq!(glyphs, [GlyphId(3), GlyphId(4), GlyphId(5), GlyphId(1)]);
+    assert!(parse_glyph_set("\"A\"").is_err());
+    assert!(parse_glyph_set("70000").is_err());
+}
+
+#[test]
+fn can_use_charset_presets() {
+    assert_eq!(Charset::ascii().len(), 95);
+    assert_eq!(Charset::latin1().len(), 95 + 96);
+    assert!(Charset::latin_extended().contains('\u{141}'));
+    assert!(Charset::cyrillic().contains('\u{416}') && !Charset::cyrillic().contains('A'));
+    assert!(Charset::greek().contains('\u{3a9}'));
+
+    let charset = Charset::from_text("Hello\nworld");
+    assert_eq!(charset.iter().collect::<String>(), "Hdelorw");
+}
+
+#[test]
+fn can_resolve_charset() {
+    with_face(|face| {
+        let charset: Charset = "A\u{4e00}".chars().collect();
+        let resolved = charset.resolve(face);
+
+        assert_eq!(resolved.glyphs, [('A', face.glyph_index('A').unwrap())]);
+        assert_eq!(resolved.missing, ['\u{4e00}']);
     });
 }
 
 #[test]
-fn can_generate_unicode() {
-    with_glyph('ç', 32, |shape, projection| {
-        let shape = shape.color_edges_simple(3.0);
+fn png_without_metadata_is_rejected() {
+    let path = env::temp_dir().join("msdf_png_without_metadata.png");
+    DynamicImage::new_luma8(4, 4).save(&path).unwrap();
+
+    assert!(matches!(
+        SDF::load_png(&path),
+        Err(FieldFileError::MissingMetadata(_))
+    ));
+}
+
+#[test]
+fn can_color_with_seed() {
+    // the reference image was generated with the simple strategy and seed 0 on another machine
+    with_glyph('C', 32, |shape, projection| {
+        let shape = shape.color_edges(ColoringStrategy::Simple, &Default::default());
 
         let sdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
         let sdf: DynamicImage = DynamicImage::from(sdf.to_image());
         let sdf = sdf.into_rgba8();
 
-        compare_images(&sdf, "unicode_msdf");
+        compare_images(&sdf, "msdf");
     });
 }
 
+#[test]
+fn coloring_is_deterministic() {
+    let strategies = [
+        ColoringStrategy::Simple,
+        ColoringStrategy::InkTrap,
+        ColoringStrategy::ByDistance,
+    ];
+
+    for strategy in strategies {
+        let mut colorings = vec![];
+        for seed in [0, 1, 42, u64::MAX] {
+            let config = EdgeColoringConfig { angle: 3.0, seed };
+
+            let generate = || {
+                let mut result = None;
+                with_glyph('S', 32, |shape, projection| {
+                    let shape = shape.color_edges(strategy, &config);
+                    let msdf =
+                        shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+                    result = Some((shape.edge_colors(), msdf.to_image()));
+                });
+                result.unwrap()
+            };
+
+            let first = generate();
+            assert_eq!(first, generate());
+
+            let (colors, _) = first;
+            if !colorings.contains(&colors) {
+                colorings.push(colors);
+            }
+        }
+
+        // the seed changes which colors are picked
+        assert!(colorings.len() > 1, "{:?} ignores the seed", strategy);
+    }
+}
+
+#[test]
+fn can_assign_edge_colors() {
+    with_glyph('H', 32, |mut shape, projection| {
+        let colored = shape.clone().color_edges_simple(3.0);
+        assert!(colored.validate_coloring(3.0).is_ok());
+
+        let colors = shape.edge_colors();
+        assert!(colors
+            .iter()
+            .flatten()
+            .all(|&color| color == EdgeColor::White));
+        assert!(matches!(
+            shape.validate_coloring(3.0),
+            Err(EdgeColoringError::SharedChannels { .. })
+        ));
+
+        for (contour, edges) in colors.iter().enumerate() {
+            for edge in 0..edges.len() {
+                let color = if edges.len() % 2 == 1 && edge == edges.len() - 1 {
+                    EdgeColor::Yellow
+                } else if edge % 2 == 0 {
+                    EdgeColor::Cyan
+                } else {
+                    EdgeColor::Magenta
+                };
+                shape.set_edge_color(contour, edge, color);
+            }
+        }
+        assert_eq!(shape.edge_colors()[0][0], EdgeColor::Cyan);
+        assert_eq!(shape.edge_colors()[0][1], EdgeColor::Magenta);
+
+        let shape = shape.into_colored(3.0).unwrap();
+        let msdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let sdf = shape.generate_sdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+
+        // the median of a valid coloring agrees with the true distance about which side is inside
+        let mismatched = msdf
+            .image()
+            .pixels()
+            .zip(sdf.image().pixels())
+            .filter(|(a, b)| {
+                let [r, g, bl] = a.0;
+                let median = r.max(g).min(r.min(g).max(bl));
+                (median > 0.5) != (b.0[0] > 0.5)
+            })
+            .count();
+        assert!(mismatched <= 2);
+    });
+}
+
+#[test]
+fn rejects_single_channel_edges() {
+    with_glyph('O', 32, |shape, _| {
+        let mut shape = shape.color_edges_simple(3.0);
+        shape.set_edge_color(0, 0, EdgeColor::Red);
+
+        let error = shape.validate_coloring(3.0).unwrap_err();
+        assert_eq!(
+            error,
+            EdgeColoringError::TooFewChannels {
+                contour: 0,
+                edge: 0,
+                color: EdgeColor::Red,
+            }
+        );
+        assert!(error.to_string().contains("edge 0 of contour 0"));
+    });
+}
+
+#[test]
+fn validates_single_edge_contours() {
+    // a closed loop of one cubic edge, with a corner where it meets itself
+    let looped = vec![Segment::Cubic([
+        Vector2 { x: 0.0, y: 0.0 },
+        Vector2 { x: 10.0, y: 10.0 },
+        Vector2 { x: -10.0, y: 10.0 },
+        Vector2 { x: 0.0, y: 0.0 },
+    ])];
+    let mut shape = Shape::from_contours(&[looped], true);
+
+    shape.set_edge_color(0, 0, EdgeColor::White);
+    assert!(shape.validate_coloring(3.0).is_ok());
+    shape.set_edge_color(0, 0, EdgeColor::Cyan);
+    assert!(shape.validate_coloring(3.0).is_ok());
+
+    for color in [EdgeColor::Red, EdgeColor::Black] {
+        shape.set_edge_color(0, 0, color);
+        assert_eq!(
+            shape.validate_coloring(3.0),
+            Err(EdgeColoringError::TooFewChannels {
+                contour: 0,
+                edge: 0,
+                color,
+            })
+        );
+    }
+}
+
+#[test]
+fn can_clone_shape_with_empty_contours() {
+    let square = vec![
+        Segment::Linear([Vector2 { x: 0.0, y: 0.0 }, Vector2 { x: 0.0, y: 1.0 }]),
+        Segment::Linear([Vector2 { x: 0.0, y: 1.0 }, Vector2 { x: 1.0, y: 1.0 }]),
+        Segment::Linear([Vector2 { x: 1.0, y: 1.0 }, Vector2 { x: 0.0, y: 0.0 }]),
+    ];
+    let mut shape = Shape::from_contours(&[vec![], square], true);
+    shape.set_edge_color(1, 0, EdgeColor::Cyan);
+    shape.set_edge_color(1, 2, EdgeColor::Yellow);
+
+    let clone = shape.clone();
+    assert_eq!(clone.edge_colors().len(), 2);
+    assert_eq!(clone.edge_colors(), shape.edge_colors());
+    assert_eq!(clone.contours(), shape.contours());
+}
+
+#[test]
+fn can_clone_shape() {
+    with_glyph('B', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let clone = shape.clone();
+
+        assert_eq!(clone.edge_colors(), shape.edge_colors());
+
+        let config = Default::default();
+        let original = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &config);
+        let cloned = clone.generate_msdf(32, 32, 10.0 * 64.0, &projection, &config);
+        assert_eq!(original.to_image(), cloned.to_image());
+    });
+}
+
+#[test]
+fn can_pick_best_coloring() {
+    with_glyph('M', 32, |shape, projection| {
+        let config = ColoringSearchConfig {
+            seeds: 2,
+            ..Default::default()
+        };
+        let range = 10.0 * 64.0;
+        let (best, score) = shape
+            .clone()
+            .color_edges_best(32, 32, range, &projection, &config);
+
+        assert!(best.validate_coloring(config.angle).is_ok());
+        assert!((0.0..0.05).contains(&score));
+
+        let exact = shape
+            .generate_sdf(32, 32, range, &projection, &Default::default())
+            .render(128, 128);
+        let score_of = |candidate: &ColoredShape| {
+            let msdf = candidate.generate_msdf(32, 32, range, &projection, &config.msdf_config);
+            render_difference(&msdf.render(128, 128), &exact)
+        };
+        assert!((score_of(&best) - score).abs() < 1e-12);
+
+        // no other candidate of the search renders closer to the exact field
+        for strategy in [
+            ColoringStrategy::Simple,
+            ColoringStrategy::InkTrap,
+            ColoringStrategy::ByDistance,
+        ] {
+            for seed in 0..config.seeds {
+                let coloring = EdgeColoringConfig {
+                    angle: config.angle,
+                    seed,
+                };
+                let candidate = shape.clone().color_edges(strategy, &coloring);
+                assert!(score <= score_of(&candidate));
+            }
+        }
+    });
+}
+
+#[test]
+fn exact_sdf_has_no_error() {
+    with_glyph('A', 32, |shape, projection| {
+        let range = 10.0 * 64.0;
+        let sdf = shape.generate_sdf(32, 32, range, &projection, &Default::default());
+        let report = shape.measure_quality(&sdf, &projection);
+
+        assert!(report.max_deviation < range * 1e-4);
+        assert_eq!(report.sign_flips, 0);
+        assert_eq!(report.error.dimensions(), (32, 32));
+
+        // a field of a quarter of the resolution scaled up to the same size only matches the
+        // exact distance where it interpolates it
+        let low = Projection {
+            scale: Vector2 {
+                x: projection.scale.x / 4.0,
+                y: projection.scale.y / 4.0,
+            },
+            translation: projection.translation,
+        };
+        let low = shape.generate_sdf(8, 8, range, &low, &Default::default());
+        let upscaled =
+            image::imageops::resize(low.image(), 32, 32, image::imageops::FilterType::Triangle);
+        let upscaled = SDF::from_image(upscaled, 10.0, 0.5);
+        let report = shape.measure_quality(&upscaled, &projection);
+
+        assert!(report.mean_error > range * 1e-3);
+        assert!(report.max_deviation > report.mean_error);
+    });
+}
+
+#[test]
+fn can_measure_quality() {
+    with_glyph('A', 32, |shape, projection| {
+        let range = 10.0 * 64.0;
+        let shape = shape.color_edges_simple(3.0);
+
+        let msdf = shape.generate_msdf(32, 32, range, &projection, &Default::default());
+        let mtsdf = shape.generate_mtsdf(32, 32, range, &projection, &Default::default());
+
+        for report in [
+            shape.measure_quality(&msdf, &projection),
+            shape.measure_quality(&mtsdf, &projection),
+        ] {
+            assert!(report.sign_flips <= 2);
+            assert!(report.mean_error < range * 0.1);
+            assert!(report.max_deviation <= range / 2.0);
+            assert!(report.mean_error <= report.max_deviation);
+        }
+    });
+}
+
+#[test]
+fn signed_distance_matches_sdf() {
+    with_glyph('A', 32, |shape, projection| {
+        let range = 10.0 * 64.0;
+        let sdf = shape.generate_sdf(32, 32, range, &projection, &Default::default());
+
+        for (x, y, pixel) in sdf.image().enumerate_pixels() {
+            // loaded glyphs are y-up, so the first row of the image is the top of the shape
+            let point = Vector2 {
+                x: (x as f64 + 0.5) / projection.scale.x - projection.translation.x,
+                y: ((31 - y) as f64 + 0.5) / projection.scale.y - projection.translation.y,
+            };
+
+            let expected = (pixel.0[0] as f64 - 0.5) * range;
+            let distance = shape.signed_distance(point);
+
+            assert!((distance - expected).abs() < range * 1e-4);
+            assert_eq!(shape.contains(point), expected > 0.0);
+        }
+    });
+}
+
+#[test]
+fn can_find_nearest_edge() {
+    // clockwise, like the outer contours of TrueType glyphs, so that the inside is positive
+    let corners = [(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)];
+    let square = (0..4)
+        .map(|i| {
+            let (x0, y0) = corners[i];
+            let (x1, y1) = corners[(i + 1) % 4];
+            Segment::Linear([Vector2 { x: x0, y: y0 }, Vector2 { x: x1, y: y1 }])
+        })
+        .collect::<Vec<_>>();
+    let shape = Shape::from_contours(&[square], true);
+
+    let below = shape.nearest_edge(Vector2 { x: 4.0, y: -3.0 }).unwrap();
+    assert_eq!((below.contour, below.edge), (0, 3));
+    assert!((below.param - 0.6).abs() < 1e-9);
+    assert!((below.distance + 3.0).abs() < 1e-9);
+
+    let inside = shape.nearest_edge(Vector2 { x: 8.0, y: 5.0 }).unwrap();
+    assert_eq!(inside.edge, 2);
+    assert!((inside.distance - 2.0).abs() < 1e-9);
+    assert!(shape.contains(Vector2 { x: 8.0, y: 5.0 }));
+
+    let corner = shape.signed_distance(Vector2 { x: 13.0, y: 14.0 });
+    assert!((corner + 5.0).abs() < 1e-9);
+
+    // the sign follows the orientation of the edges, while containment doesn't
+    let mut reversed = shape.clone();
+    reversed.reverse();
+    assert!((reversed.signed_distance(Vector2 { x: 8.0, y: 5.0 }) + 2.0).abs() < 1e-9);
+    assert!(reversed.contains(Vector2 { x: 8.0, y: 5.0 }));
+
+    let empty = Shape::from_contours(&[], true);
+    assert!(empty.nearest_edge(Vector2 { x: 0.0, y: 0.0 }).is_none());
+    assert_eq!(
+        empty.signed_distance(Vector2 { x: 0.0, y: 0.0 }),
+        f64::NEG_INFINITY
+    );
+}
+
+#[test]
+fn can_pack_msdf_with_sdf() {
+    with_glyph('D', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let config = Default::default();
+
+        let packed = shape.generate_packed(
+            32,
+            32,
+            10.0 * 64.0,
+            &projection,
+            PackedLayout::MSDF_SDF,
+            &config,
+        );
+
+        let msdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &config);
+        let sdf = shape.generate_sdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+
+        assert_eq!(packed.msdf().unwrap().to_image(), msdf.to_image());
+        assert_eq!(packed.sdf().unwrap().to_image(), sdf.to_image());
+        assert!(packed.psuedo_sdf().is_none());
+    });
+}
+
+#[test]
+fn can_pack_custom_layout() {
+    with_glyph('B', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+        let layout = PackedLayout([
+            PackedChannel::PseudoSDF,
+            PackedChannel::Empty,
+            PackedChannel::MSDFGreen,
+            PackedChannel::SDF,
+        ]);
+
+        let packed = shape.generate_packed(
+            32,
+            32,
+            10.0 * 64.0,
+            &projection,
+            layout,
+            &Default::default(),
+        );
+
+        let psdf = shape.generate_psuedo_sdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let msdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+
+        assert_eq!(packed.layout(), layout);
+        assert_eq!(packed.psuedo_sdf().unwrap().to_image(), psdf.to_image());
+        assert!(packed.msdf().is_none());
+        for (packed, msdf) in packed.image().pixels().zip(msdf.image().pixels()) {
+            assert_eq!(packed.0[1], 0.0);
+            assert_eq!(packed.0[2], msdf.0[1]);
+        }
+    });
+}
+
+#[test]
+fn can_project_msdf() {
+    with_glyph('E', 32, |shape, _| {
+        let shape = shape.color_edges_simple(3.0);
+
+        let projection = Projection {
+            scale: Vector2 {
+                x: 1.5 / 64.0,
+                y: 0.5 / 64.0,
+            },
+            translation: Vector2 {
+                x: 4.0 * 64.0,
+                y: 4.0 * 64.0,
+            },
+        };
+
+        let sdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let sdf: DynamicImage = DynamicImage::from(sdf.to_image());
+        let sdf = sdf.into_rgba8();
+
+        compare_images(&sdf, "projected_msdf");
+    });
+}
+
+#[test]
+fn can_generate_unicode() {
+    with_glyph('ç', 32, |shape, projection| {
+        let shape = shape.color_edges_simple(3.0);
+
+        let sdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let sdf: DynamicImage = DynamicImage::from(sdf.to_image());
+        let sdf = sdf.into_rgba8();
+
+        compare_images(&sdf, "unicode_msdf");
+    });
+}
+
+#[test]
+fn can_load_glyphs_by_char() {
+    with_face(|face| {
+        let by_id = face.load_shape(face.glyph_index('A').unwrap()).unwrap();
+        let by_char = face.load_char('A').unwrap();
+        assert_eq!(by_char.contours(), by_id.contours());
+
+        assert!(matches!(
+            face.load_char('\u{4e00}'),
+            Err(MsdfError::MissingGlyph)
+        ));
+        assert!(matches!(
+            face.load_char(' '),
+            Err(MsdfError::GlyphLoadingFailure)
+        ));
+        // this build of Roboto has no glyph names
+        assert!(matches!(
+            face.load_glyph_name("A"),
+            Err(MsdfError::MissingGlyph)
+        ));
+
+        let shapes = face.load_chars("AAB\u{4e00}".chars());
+        assert_eq!(shapes.len(), 3);
+        assert!(shapes[&'A'].is_ok() && shapes[&'B'].is_ok());
+        assert!(matches!(shapes[&'\u{4e00}'], Err(MsdfError::MissingGlyph)));
+    });
+}
+
+#[test]
+fn can_load_glyphs_by_name() {
+    // names of the standard Macintosh set and names stored in the post table both resolve
+    with_font("glyph_names.ttf", |face| {
+        let by_name = face.load_glyph_name("A").unwrap();
+        assert_eq!(by_name.contours(), face.load_char('A').unwrap().contours());
+
+        let alternate = face.load_glyph_name("A.alt").unwrap();
+        assert_eq!(alternate.contours().len(), 1);
+        assert_eq!(alternate.contours()[0].len(), 3);
+
+        assert!(matches!(
+            face.load_glyph_name("space"),
+            Err(MsdfError::GlyphLoadingFailure)
+        ));
+        assert!(matches!(
+            face.load_glyph_name("B"),
+            Err(MsdfError::MissingGlyph)
+        ));
+    });
+}
+
+#[test]
+fn can_read_named_instances() {
+    let fixed = |value: f64| ((value * 65536.0) as i32).to_be_bytes();
+    let mut fvar = vec![];
+    // version, axes offset, reserved, axis count and size, instance count and size
+    for value in [1u16, 0, 16, 2, 2, 20, 2, 12] {
+        fvar.extend(value.to_be_bytes());
+    }
+    for (tag, min, default, max) in [
+        (b"wght", 100.0, 400.0, 900.0),
+        (b"wdth", 75.0, 100.0, 100.0),
+    ] {
+        fvar.extend(tag);
+        for value in [min, default, max] {
+            fvar.extend(fixed(value));
+        }
+        fvar.extend([0, 0, 1, 0]);
+    }
+    for (name_id, weight, width) in [(258u16, 400.0, 100.0), (259, 700.0, 75.5)] {
+        fvar.extend(name_id.to_be_bytes());
+        fvar.extend([0, 0]);
+        fvar.extend(fixed(weight));
+        fvar.extend(fixed(width));
+    }
+
+    let instances = parse_named_instances(&fvar);
+    assert_eq!(instances.len(), 2);
+    assert_eq!(instances[1].0, 259);
+    assert_eq!(
+        instances[1].1,
+        [
+            Variation {
+                axis: Tag::from_bytes(b"wght"),
+                value: 700.0
+            },
+            Variation {
+                axis: Tag::from_bytes(b"wdth"),
+                value: 75.5
+            }
+        ]
+    );
+    assert!(parse_named_instances(&fvar[..40]).is_empty());
+}
+
+#[test]
+fn can_load_variable_instances() {
+    // the right edge of the square moves with the weight axis
+    with_font("variable.ttf", |face| {
+        let instances = face.named_instances();
+        let names = instances
+            .iter()
+            .map(|instance| instance.name.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(names, [Some("Thin"), Some("Regular"), Some("Black")]);
+        assert_eq!(
+            instances[2].variations,
+            [Variation {
+                axis: Tag::from_bytes(b"wght"),
+                value: 900.0
+            }]
+        );
+
+        let glyph = face.glyph_index('A').unwrap();
+        for (instance, right) in instances.iter().zip([300.0, 500.0, 700.0]) {
+            let face = face.with_variations(&instance.variations).unwrap();
+            assert_eq!(face.glyph_bounding_box(glyph).unwrap().x_max as f64, right);
+
+            let bounds = face.load_shape(glyph).unwrap().bounds().unwrap();
+            assert_eq!(bounds.left, 100.0);
+            assert_eq!(bounds.right, right);
+        }
+    });
+}
+
+#[test]
+fn rejects_variations_of_static_fonts() {
+    with_face(|face| {
+        assert!(face.named_instances().is_empty());
+        assert!(face.with_variations(&[]).is_ok());
+
+        let bold = Variation {
+            axis: Tag::from_bytes(b"wght"),
+            value: 700.0,
+        };
+        assert!(matches!(
+            face.with_variations(&[bold]),
+            Err(MsdfError::InvalidVariation)
+        ));
+    });
+}
+
+#[test]
+fn can_load_faces_of_collections() {
+    let path = env::current_dir()
+        .unwrap()
+        .join("test_resources")
+        .join("Roboto-Medium.ttf");
+    let font = std::fs::read(path).unwrap();
+
+    let source = FontSource::new(&font).unwrap();
+    assert!(!source.is_collection());
+    assert_eq!(source.face_count(), 1);
+    let roboto = FaceInfo {
+        index: 0,
+        family: Some("Roboto".to_owned()),
+        style: Some("Medium".to_owned()),
+    };
+    assert_eq!(source.faces().len(), 1);
+    assert_eq!(source.faces()[0], roboto);
+
+    // a collection holding the font twice, with table offsets moved past the collection header
+    let mut collection = b"ttcf".to_vec();
+    for value in [0x0001_0000u32, 2, 20, 20] {
+        collection.extend(value.to_be_bytes());
+    }
+    let mut font = font;
+    let table_count = u16::from_be_bytes([font[4], font[5]]) as usize;
+    for table in 0..table_count {
+        let offset = 12 + table * 16 + 8;
+        let moved = u32::from_be_bytes(font[offset..offset + 4].try_into().unwrap()) + 20;
+        font[offset..offset + 4].copy_from_slice(&moved.to_be_bytes());
+    }
+    collection.extend(font);
+
+    let source = FontSource::new(&collection).unwrap();
+    assert!(source.is_collection());
+    assert_eq!(source.face_count(), 2);
+    assert_eq!(source.faces()[1], FaceInfo { index: 1, ..roboto });
+    assert!(source.find_face("roboto", "medium").is_ok());
+    assert!(matches!(
+        source.find_face("Roboto", "Bold"),
+        Err(MsdfError::FontLoadingFailure)
+    ));
+    assert!(matches!(source.face(2), Err(MsdfError::FontLoadingFailure)));
+    assert!(matches!(
+        FontSource::new(b"not a font"),
+        Err(MsdfError::FontLoadingFailure)
+    ));
+}
+
+#[test]
+fn can_read_palettes() {
+    let mut cpal = vec![];
+    // version, entries per palette, palette count, record count, records offset, first records
+    for value in [0u16, 2, 2, 4, 0, 16, 0, 2] {
+        cpal.extend(value.to_be_bytes());
+    }
+    for bgra in [
+        [0, 0, 255, 255],
+        [255, 0, 0, 128],
+        [0, 255, 0, 255],
+        [0, 0, 0, 255],
+    ] {
+        cpal.extend(bgra);
+    }
+
+    let palette = parse_palette(&cpal, 0);
+    assert_eq!(palette, [Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 128])]);
+    assert_eq!(parse_palette(&cpal, 1)[0], Rgba([0, 255, 0, 255]));
+    assert!(parse_palette(&cpal, 2).is_empty());
+    assert!(parse_palette(&cpal[..20], 0).is_empty());
+
+    assert_eq!(
+        resolve_color(&[(1, 0.5)], &palette),
+        Some(LayerColor::Palette(Rgba([0, 0, 255, 64])))
+    );
+    // gradients are averaged
+    assert_eq!(
+        resolve_color(&[(0, 1.0), (1, 1.0)], &palette),
+        Some(LayerColor::Palette(Rgba([128, 0, 128, 192])))
+    );
+    assert_eq!(
+        resolve_color(&[(0xffff, 1.0)], &palette),
+        Some(LayerColor::Foreground(255))
+    );
+    assert_eq!(resolve_color(&[(5, 1.0)], &palette), None);
+}
+
+#[test]
+fn can_read_color_layers() {
+    let mut colr = vec![];
+    let u32s = |values: &[u32]| {
+        values
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect::<Vec<_>>()
+    };
+    let u16s = |values: &[u16]| {
+        values
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect::<Vec<_>>()
+    };
+    let offset24 = |offset: u32| offset.to_be_bytes()[1..].to_vec();
+
+    // version 1 header: base glyph records at 34, layer records at 40, base glyph list at 48,
+    // layer list at 64
+    colr.extend(u16s(&[1, 1]));
+    colr.extend(u32s(&[34, 40]));
+    colr.extend(u16s(&[2]));
+    colr.extend(u32s(&[48, 64, 0, 0, 0]));
+    // COLRv0 glyph 3 has two layers
+    colr.extend(u16s(&[3, 0, 2]));
+    colr.extend(u16s(&[7, 1, 8, 0xffff]));
+    // COLRv1 glyph 4 paints two layers
+    colr.extend(u32s(&[1]));
+    colr.extend(u16s(&[4]));
+    colr.extend(u32s(&[10]));
+    colr.extend([1, 2]);
+    colr.extend(u32s(&[0]));
+    colr.extend(u32s(&[2, 12, 23]));
+    // glyph 5 with a half transparent solid fill
+    colr.push(10);
+    colr.extend(offset24(6));
+    colr.extend(u16s(&[5]));
+    colr.push(2);
+    colr.extend(u16s(&[1, 0x2000]));
+    // glyph 6 moved by (100, -50) with a linear gradient
+    colr.push(14);
+    colr.extend(offset24(8));
+    colr.extend(u16s(&[100, -50i16 as u16]));
+    colr.push(10);
+    colr.extend(offset24(6));
+    colr.extend(u16s(&[6]));
+    colr.push(4);
+    colr.extend(offset24(16));
+    colr.extend(u16s(&[0; 6]));
+    colr.push(0);
+    colr.extend(u16s(&[2, 0, 0, 0x4000, 0x4000, 1, 0x4000]));
+
+    assert_eq!(
+        parse_color_layers(&colr, GlyphId(3)),
+        Some(vec![
+            LayerRecord {
+                glyph: GlyphId(7),
+                transform: Affine2::IDENTITY,
+                stops: vec![(1, 1.0)],
+            },
+            LayerRecord {
+                glyph: GlyphId(8),
+                transform: Affine2::IDENTITY,
+                stops: vec![(0xffff, 1.0)],
+            },
+        ])
+    );
+    assert_eq!(
+        parse_color_layers(&colr, GlyphId(4)),
+        Some(vec![
+            LayerRecord {
+                glyph: GlyphId(5),
+                transform: Affine2::IDENTITY,
+                stops: vec![(1, 0.5)],
+            },
+            LayerRecord {
+                glyph: GlyphId(6),
+                transform: Affine2::from_translation(Vector2 { x: 100.0, y: -50.0 }),
+                stops: vec![(0, 1.0), (1, 1.0)],
+            },
+        ])
+    );
+    assert_eq!(parse_color_layers(&colr, GlyphId(9)), None);
+    assert_eq!(parse_color_layers(&colr[..30], GlyphId(4)), None);
+}
+
+#[test]
+fn can_load_color_glyphs() {
+    // A is a red square below a smaller square in the text color, B has no color layers
+    with_font("color.ttf", |face| {
+        assert_eq!(face.palette_count(), 2);
+        let glyph = face.glyph_index('A').unwrap();
+        assert!(face
+            .load_color_glyph(face.glyph_index('B').unwrap(), 0)
+            .is_none());
+
+        let color_glyph = face.load_color_glyph(glyph, 0).unwrap();
+        let colors = color_glyph
+            .layers
+            .iter()
+            .map(|layer| layer.color)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            colors,
+            [
+                LayerColor::Palette(Rgba([255, 0, 0, 255])),
+                LayerColor::Foreground(255)
+            ]
+        );
+        let front = color_glyph.layers[1].shape.bounds().unwrap();
+        assert_eq!(
+            (front.left, front.bottom, front.right, front.top),
+            (200.0, 200.0, 400.0, 500.0)
+        );
+        assert_eq!(color_glyph.bounds().unwrap().left, 100.0);
+
+        let blue = face.load_color_glyph(glyph, 1).unwrap();
+        assert_eq!(
+            blue.layers[0].color,
+            LayerColor::Palette(Rgba([0, 0, 255, 128]))
+        );
+
+        // the layers line up, 10 font units per pixel
+        let config = AtlasConfig {
+            atlas_type: AtlasType::SDF,
+            ..Default::default()
+        };
+        let projection = Projection {
+            scale: Vector2 { x: 0.1, y: 0.1 },
+            translation: Vector2 { x: 0.0, y: 0.0 },
+        };
+        let fields = color_glyph.generate(64, 64, 100.0, &projection, &config);
+        assert_eq!(fields.len(), 2);
+        let (back, front) = match (&fields[0], &fields[1]) {
+            (AtlasField::SDF(back), AtlasField::SDF(front)) => (back, front),
+            _ => panic!("expected SDF layers"),
+        };
+        let expected = color_glyph.layers[1].shape.clone().color_edges_simple(3.0);
+        let expected = expected.generate_sdf(64, 64, 100.0, &projection, &Default::default());
+        assert_eq!(front.image(), expected.image());
+
+        // the middle row crosses both squares, only the back one reaches to x = 150
+        assert!(back.image().get_pixel(15, 32).0[0] > 0.5);
+        assert!(front.image().get_pixel(15, 32).0[0] < 0.5);
+        assert!(front.image().get_pixel(30, 32).0[0] > 0.5);
+    });
+}
+
+#[test]
+fn can_build_atlas_with_color_glyphs() {
+    with_font("color.ttf", |face| {
+        let glyphs = [
+            (Some('A'), face.glyph_index('A').unwrap()),
+            (Some('B'), face.glyph_index('B').unwrap()),
+        ];
+        let config = AtlasConfig {
+            padding: 1,
+            ..Default::default()
+        };
+        let atlas = Atlas::from_color_glyphs(face, glyphs, 0, &config);
+
+        // the color glyph is placed as its layers, the other glyph as itself
+        assert_eq!(atlas.layers.len(), 2);
+        assert!(atlas.layers.iter().all(|layer| layer.glyph == 0));
+        assert_eq!(
+            atlas.layers[0].color,
+            LayerColor::Palette(Rgba([255, 0, 0, 255]))
+        );
+        assert_eq!(atlas.layers[1].color, LayerColor::Foreground(255));
+        assert!(atlas.glyphs[0].atlas_rect.is_none());
+        assert!(atlas.glyphs[1].atlas_rect.is_some());
+
+        // plane bounds are in ems, 1000 font units each, and cover the layer outlines
+        let back = atlas.layers[0].plane_bounds.unwrap();
+        let front = atlas.layers[1].plane_bounds.unwrap();
+        assert!(back.left <= 0.1 && back.right >= 0.5 && back.bottom <= 0.0 && back.top >= 0.7);
+        assert!(front.left <= 0.2 && front.right >= 0.4 && front.bottom <= 0.2 && front.top >= 0.5);
+        assert!(front.left > back.left && front.right < back.right);
+
+        let rects = [
+            atlas.layers[0].atlas_rect.unwrap(),
+            atlas.layers[1].atlas_rect.unwrap(),
+            atlas.glyphs[1].atlas_rect.unwrap(),
+        ];
+        let (width, height) = atlas.field.dimensions();
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.x + a.width <= width && a.y + a.height <= height);
+            for b in &rects[i + 1..] {
+                let apart = a.x + a.width <= b.x
+                    || b.x + b.width <= a.x
+                    || a.y + a.height <= b.y
+                    || b.y + b.height <= a.y;
+                assert!(apart);
+            }
+        }
+    });
+}
+
+#[test]
+fn can_build_atlas_without_color_glyphs() {
+    with_face(|face| {
+        assert_eq!(face.palette_count(), 0);
+        assert!(face
+            .load_color_glyph(face.glyph_index('A').unwrap(), 0)
+            .is_none());
+
+        let config = AtlasConfig::default();
+        let glyphs = Charset::from_text("Color").resolve(face).glyphs;
+        let glyphs = glyphs.into_iter().map(|(c, glyph)| (Some(c), glyph));
+        let atlas = Atlas::from_color_glyphs(face, glyphs, 0, &config);
+        assert!(atlas.layers.is_empty());
+        assert!(atlas.glyphs.iter().all(|glyph| glyph.atlas_rect.is_some()));
+    });
+}
+
+#[test]
+fn can_transform_identity() {
+    with_glyph('C', 32, |mut shape, projection| {
+        shape.transform(&Affine2::IDENTITY);
+        let shape = shape.color_edges_simple(3.0);
+
+        let sdf = shape.generate_msdf(32, 32, 10.0 * 64.0, &projection, &Default::default());
+        let sdf: DynamicImage = DynamicImage::from(sdf.to_image());
+        let sdf = sdf.into_rgba8();
+
+        compare_images(&sdf, "msdf");
+    });
+}
+
+#[test]
+fn can_translate_shape() {
+    with_glyph('A', 32, |mut shape, _| {
+        let before = shape.bounds().unwrap();
+        shape.transform(&Affine2::from_translation(Vector2 { x: 100.0, y: -50.0 }));
+        let after = shape.bounds().unwrap();
+
+        assert!((after.left - (before.left + 100.0)).abs() < 1e-9);
+        assert!((after.right - (before.right + 100.0)).abs() < 1e-9);
+        assert!((after.bottom - (before.bottom - 50.0)).abs() < 1e-9);
+        assert!((after.top - (before.top - 50.0)).abs() < 1e-9);
+    });
+}
+
+#[test]
+fn can_rotate_shape() {
+    with_glyph('A', 32, |mut shape, _| {
+        let before = shape.bounds().unwrap();
+        shape.rotate(std::f64::consts::FRAC_PI_2);
+        let after = shape.bounds().unwrap();
+
+        assert!((after.left + before.top).abs() < 1e-6);
+        assert!((after.right + before.bottom).abs() < 1e-6);
+        assert!((after.bottom - before.left).abs() < 1e-6);
+        assert!((after.top - before.right).abs() < 1e-6);
+    });
+}
+
+#[test]
+fn can_skew_shape() {
+    with_glyph('H', 32, |mut shape, _| {
+        let angle = 12f64.to_radians();
+        let before = shape.bounds().unwrap();
+        shape.skew_x(angle);
+        let after = shape.bounds().unwrap();
+
+        // the top right corner of the right stem moves furthest
+        assert!((after.right - (before.right + angle.tan() * before.top)).abs() < 1e-6);
+        assert!((after.bottom - before.bottom).abs() < 1e-9);
+        assert!((after.top - before.top).abs() < 1e-9);
+    });
+}
+
+#[test]
+fn can_offset_shape() {
+    with_glyph('O', 32, |shape, _| {
+        let before = shape.bounds().unwrap();
+        let bold = shape.offset(32.0, JoinStyle::Round).bounds().unwrap();
+        let thin = shape.offset(-32.0, JoinStyle::Round).bounds().unwrap();
+
+        assert!((bold.left - (before.left - 32.0)).abs() < 1.0);
+        assert!((bold.right - (before.right + 32.0)).abs() < 1.0);
+        assert!((bold.bottom - (before.bottom - 32.0)).abs() < 1.0);
+        assert!((bold.top - (before.top + 32.0)).abs() < 1.0);
+
+        assert!((thin.left - (before.left + 32.0)).abs() < 1.0);
+        assert!((thin.right - (before.right - 32.0)).abs() < 1.0);
+        assert!((thin.bottom - (before.bottom + 32.0)).abs() < 1.0);
+        assert!((thin.top - (before.top - 32.0)).abs() < 1.0);
+    });
+}
+
+#[test]
+fn can_offset_with_joins() {
+    // the feet of the A are acute corners, where a miter reaches further out than an arc
+    with_glyph('A', 32, |shape, _| {
+        let miter = shape.offset(32.0, JoinStyle::Miter { limit: 4.0 });
+        let round = shape.offset(32.0, JoinStyle::Round);
+
+        assert!(miter.bounds().unwrap().left < round.bounds().unwrap().left - 1.0);
+    });
+}
+
+#[test]
+fn offset_keeps_contours_without_area() {
+    let point = |x, y| Vector2 { x, y };
+    let square = vec![
+        Segment::Linear([point(0.0, 0.0), point(0.0, 10.0)]),
+        Segment::Linear([point(0.0, 10.0), point(10.0, 10.0)]),
+        Segment::Linear([point(10.0, 10.0), point(10.0, 0.0)]),
+        Segment::Linear([point(10.0, 0.0), point(0.0, 0.0)]),
+    ];
+    // a line traced back and forth has no filled side to move away from
+    let spike = vec![
+        Segment::Linear([point(20.0, 0.0), point(20.0, 10.0)]),
+        Segment::Linear([point(20.0, 10.0), point(20.0, 0.0)]),
+    ];
+    let shape = Shape::from_contours(&[square, spike.clone()], false);
+
+    let contours = shape.offset(1.0, JoinStyle::Round).contours();
+    assert_eq!(contours.len(), 2);
+    assert_eq!(contours[1], spike);
+}
+
+#[test]
+fn offset_matches_shifted_threshold() {
+    // a true distance field shifted by the offset distance describes the round-joined offset
+    // outline, which makes it a good reference for the geometric offset
+    with_glyph('A', 32, |shape, projection| {
+        let range = 10.0 * 64.0;
+        let distance = 2.0 * 64.0;
+        let threshold = 0.5 - (distance / range) as f32;
+
+        let shifted = shape.generate_sdf(32, 32, range, &projection, &Default::default());
+        let offset = shape.offset(distance, JoinStyle::Round).generate_sdf(
+            32,
+            32,
+            range,
+            &projection,
+            &Default::default(),
+        );
+
+        let mismatched = shifted
+            .image()
+            .pixels()
+            .zip(offset.image().pixels())
+            .filter(|(a, b)| (a.0[0] > threshold) != (b.0[0] > 0.5))
+            .count();
+
+        assert!(mismatched <= 2);
+
+        // shifting the threshold of an MSDF extends its corners like miters, and at large
+        // magnification the mitered offset renders the same
+        let offset = shape
+            .offset(distance, JoinStyle::Miter { limit: 10.0 })
+            .color_edges_simple(3.0)
+            .generate_msdf(32, 32, range, &projection, &Default::default());
+        let msdf = shape.color_edges_simple(3.0).generate_msdf(
+            32,
+            32,
+            range,
+            &projection,
+            &Default::default(),
+        );
+        let units = msdf.range_units();
+        let shifted_msdf =
+            MSDF::from_image(msdf.to_image(), range, threshold).with_range_units(units);
+
+        let difference =
+            render_difference(&offset.render(512, 512), &shifted_msdf.render(512, 512));
+        assert!(difference < 0.01);
+    });
+}
+
+#[test]
+fn can_resolve_overlaps() {
+    // the cedilla overlaps the c
+    with_glyph('ç', 32, |shape, projection| {
+        let overlapping = SDFConfig {
+            overlap_support: true,
+            ..Default::default()
+        };
+        let resolved_config = SDFConfig {
+            overlap_support: false,
+            ..Default::default()
+        };
+
+        let reference = shape.generate_sdf(32, 32, 10.0 * 64.0, &projection, &overlapping);
+        let resolved = shape.resolve_overlaps().generate_sdf(
+            32,
+            32,
+            10.0 * 64.0,
+            &projection,
+            &resolved_config,
+        );
+
+        for (a, b) in reference.image().pixels().zip(resolved.image().pixels()) {
+            assert_eq!(a.0[0] > 0.5, b.0[0] > 0.5);
+            assert!((a.0[0] - b.0[0]).abs() < 0.02);
+        }
+    });
+}
+
+#[test]
+fn can_merge_overlapping_contours() {
+    let square = |x: f64, y: f64| {
+        let corners = [(x, y), (x, y + 2.0), (x + 2.0, y + 2.0), (x + 2.0, y)];
+        (0..4)
+            .map(|i| {
+                let (x0, y0) = corners[i];
+                let (x1, y1) = corners[(i + 1) % 4];
+                Segment::Linear([Vector2 { x: x0, y: y0 }, Vector2 { x: x1, y: y1 }])
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let shape = Shape::from_contours(&[square(0.0, 0.0), square(1.0, 1.0)], true);
+    let resolved = shape.resolve_overlaps();
+    let contours = resolved.contours();
+
+    assert_eq!(contours.len(), 1);
+    assert_eq!(contours[0].len(), 8);
+    assert_eq!(resolved.bounds(), shape.bounds());
+}
+
+#[test]
+fn can_parse_shape_description() {
+    let shape = Shape::from_description(
+        "{ 0, 0; m; 0, 2; y; 2, 2; m; 2, 0; y; # }\n{ 3, 0; (4, 1); 5, 0; # }",
+    )
+    .unwrap();
+
+    assert_eq!(shape.contours().len(), 2);
+    assert!(matches!(shape.contours()[1][0], Segment::Quadratic(_)));
+    assert_eq!(
+        shape.edge_colors()[0],
+        [
+            EdgeColor::Magenta,
+            EdgeColor::Yellow,
+            EdgeColor::Magenta,
+            EdgeColor::Yellow
+        ]
+    );
+    assert!(shape.contains(Vector2 { x: 1.0, y: 1.0 }));
+
+    let error = Shape::from_description("{ 0, 0; 1 }").err().unwrap();
+    assert_eq!(error.offset, 10);
+}
+
+#[test]
+fn can_parse_svg_path() {
+    let shape = Shape::from_svg_path("M0 0 H10 V10 H0 Z m2 2 a3 3 0 0 1 6 0").unwrap();
+    let contours = shape.contours();
+
+    assert_eq!(contours.len(), 2);
+    assert!(contours[1]
+        .iter()
+        .take(2)
+        .all(|segment| matches!(segment, Segment::Cubic(_))));
+
+    // the half circle from (2, 2) to (8, 2) bulges down to (5, -1), below the square
+    let bounds = shape.bounds().unwrap();
+    assert!((bounds.left - 0.0).abs() < 1e-9 && (bounds.right - 10.0).abs() < 1e-9);
+    assert!((bounds.bottom + 1.0).abs() < 1e-9 && (bounds.top - 10.0).abs() < 1e-9);
+
+    assert!(Shape::from_svg_path("0 0 L 1 1").is_err());
+}
+
+#[test]
+fn can_reverse_shape() {
+    let mut shape = Shape::from_description("{ 0, 0; m; 0, 2; y; 2, 2; m; 2, 0; y; # }").unwrap();
+    let center = Vector2 { x: 1.0, y: 1.0 };
+    let distance = shape.signed_distance(center);
+    let projection = Projection {
+        scale: Vector2 { x: 4.0, y: 4.0 },
+        translation: Vector2 { x: 1.0, y: 1.0 },
+    };
+    let center_value = |shape: &Shape| {
+        let sdf = shape.generate_sdf(16, 16, 1.0, &projection, &Default::default());
+        sdf.image().get_pixel(8, 8).0[0]
+    };
+    assert!(distance > 0.0 && center_value(&shape) > 0.5);
+
+    shape.reverse();
+
+    // reversing turns the shape inside out, though the center is still within its contour
+    assert!((shape.signed_distance(center) + distance).abs() < 1e-9);
+    assert!(center_value(&shape) < 0.5);
+    assert!(shape.contains(center));
+    assert_eq!(
+        shape.edge_colors()[0],
+        [
+            EdgeColor::Yellow,
+            EdgeColor::Magenta,
+            EdgeColor::Yellow,
+            EdgeColor::Magenta
+        ]
+    );
+}
+
 #[test]
 fn can_render_sdf() {
     let path = env::current_dir()
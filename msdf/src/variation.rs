@@ -0,0 +1,105 @@
+use crate::MsdfError;
+use ttf_parser::{Face, Tag, Variation};
+
+#[derive(Clone, Debug, PartialEq)]
+/// A named instance of a variable font, such as `Bold` or `Condensed Light`.
+pub struct NamedInstance {
+    /// Subfamily name of the instance from the `name` table, if it has one.
+    pub name: Option<String>,
+    /// Value of every axis at this instance.
+    pub variations: Vec<Variation>,
+}
+
+/// Picks instances of variable fonts, whose glyphs can then be loaded with [crate::GlyphLoader].
+pub trait FontVariations: Sized {
+    /// Named instances listed in the `fvar` table. Empty for fonts that aren't variable.
+    fn named_instances(&self) -> Vec<NamedInstance>;
+
+    /// Copy of the face with the given axes set, such as `wght` at 700. Axes that aren't listed
+    /// keep their default values. Fails with [MsdfError::InvalidVariation] if the face isn't
+    /// variable or has no such axis.
+    fn with_variations(&self, variations: &[Variation]) -> Result<Self, MsdfError>;
+}
+
+impl FontVariations for Face<'_> {
+    fn named_instances(&self) -> Vec<NamedInstance> {
+        let fvar = match self.table_data(Tag::from_bytes(b"fvar")) {
+            Some(fvar) => fvar,
+            None => return vec![],
+        };
+
+        parse_named_instances(fvar)
+            .into_iter()
+            .map(|(name_id, variations)| NamedInstance {
+                name: self
+                    .names()
+                    .into_iter()
+                    .filter(|name| name.name_id == name_id && name.is_unicode())
+                    .find_map(|name| name.to_string()),
+                variations,
+            })
+            .collect()
+    }
+
+    fn with_variations(&self, variations: &[Variation]) -> Result<Self, MsdfError> {
+        let mut face = self.clone();
+        for variation in variations {
+            face.set_variation(variation.axis, variation.value)
+                .ok_or(MsdfError::InvalidVariation)?;
+        }
+        Ok(face)
+    }
+}
+
+/// Reads the instance records of an `fvar` table, with the name ID of every instance. Returns
+/// nothing if the table is malformed.
+pub(crate) fn parse_named_instances(fvar: &[u8]) -> Vec<(u16, Vec<Variation>)> {
+    let u16_at = |offset: usize| {
+        let bytes = fvar.get(offset..offset + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+    let u32_at = |offset: usize| {
+        let bytes = fvar.get(offset..offset + 4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+
+    let parse = || -> Option<Vec<(u16, Vec<Variation>)>> {
+        if u32_at(0)? != 0x0001_0000 {
+            return None;
+        }
+        let axes_offset = u16_at(4)? as usize;
+        let axis_count = u16_at(8)? as usize;
+        let axis_size = u16_at(10)? as usize;
+        let instance_count = u16_at(12)? as usize;
+        let instance_size = u16_at(14)? as usize;
+        if instance_size < 4 + axis_count * 4 {
+            return None;
+        }
+
+        let tags = (0..axis_count)
+            .map(|axis| u32_at(axes_offset + axis * axis_size).map(Tag))
+            .collect::<Option<Vec<Tag>>>()?;
+
+        let instances_offset = axes_offset + axis_count * axis_size;
+        (0..instance_count)
+            .map(|instance| {
+                let offset = instances_offset + instance * instance_size;
+                let variations = tags
+                    .iter()
+                    .enumerate()
+                    .map(|(axis, &tag)| {
+                        // coordinates are 16.16 fixed point numbers
+                        let value = u32_at(offset + 4 + axis * 4)? as i32;
+                        Some(Variation {
+                            axis: tag,
+                            value: value as f32 / 65536.0,
+                        })
+                    })
+                    .collect::<Option<Vec<Variation>>>()?;
+                Some((u16_at(offset)?, variations))
+            })
+            .collect()
+    };
+
+    parse().unwrap_or_default()
+}
@@ -0,0 +1,246 @@
+#!/usr/bin/env python3
+"""Writes the binary fixtures of the tests that can't be generated by the crate itself.
+
+Run from this directory with `python3 make_fixtures.py`. Only the standard library is used.
+"""
+
+import struct
+import zlib
+
+
+def png(width, height, channels, pixels):
+    """Encodes 8-bit rows, top to bottom, as a PNG."""
+    color_type = {1: 0, 3: 2, 4: 6}[channels]
+
+    def chunk(kind, data):
+        body = kind + data
+        return struct.pack(">I", len(data)) + body + struct.pack(">I", zlib.crc32(body))
+
+    rows = b"".join(b"\0" + bytes(row) for row in pixels)
+    return (
+        b"\x89PNG\r\n\x1a\n"
+        + chunk(b"IHDR", struct.pack(">IIBBBBB", width, height, 8, color_type, 0, 0, 0))
+        + chunk(b"IDAT", zlib.compress(rows))
+        + chunk(b"IEND", b"")
+    )
+
+
+def padded(data):
+    return data + b"\0" * (-len(data) % 4)
+
+
+def ttf(tables):
+    """Assembles a TrueType font from its tables, keyed by tag."""
+    tags = sorted(tables)
+    entry_selector = len(tags).bit_length() - 1
+    search_range = 16 << entry_selector
+    data = struct.pack(">IHHHH", 0x00010000, len(tags), search_range, entry_selector,
+                       16 * len(tags) - search_range)
+
+    offset = len(data) + 16 * len(tags)
+    body = b""
+    for tag in tags:
+        table = padded(tables[tag])
+        checksum = sum(struct.unpack(">%dI" % (len(table) // 4), table)) & 0xffffffff
+        data += struct.pack(">4sIII", tag, checksum, offset + len(body), len(tables[tag]))
+        body += table
+    return data + body
+
+
+def simple_glyph(contours):
+    """A glyph of straight contours, given as lists of on-curve points. Empty without contours."""
+    if not contours:
+        return b""
+
+    points = [point for contour in contours for point in contour]
+    xs, ys = [x for x, _ in points], [y for _, y in points]
+    data = struct.pack(">5h", len(contours), min(xs), min(ys), max(xs), max(ys))
+    end = -1
+    for contour in contours:
+        end += len(contour)
+        data += struct.pack(">H", end)
+    data += struct.pack(">H", 0) + bytes([1] * len(points))
+    for values in (xs, ys):
+        previous = 0
+        for value in values:
+            data += struct.pack(">h", value - previous)
+            previous = value
+    return data
+
+
+def cmap(mapping):
+    """A format 4 `cmap` with a segment per character, mapping characters to glyph ids."""
+    segments = [(code, (glyph - code) & 0xffff) for code, glyph in sorted(mapping.items())]
+    segments.append((0xffff, 1))
+    count = len(segments)
+    entry_selector = count.bit_length() - 1
+    search_range = 2 << entry_selector
+
+    subtable = struct.pack(">%dH" % count, *(code for code, _ in segments)) + b"\0\0"
+    subtable += struct.pack(">%dH" % count, *(code for code, _ in segments))
+    subtable += struct.pack(">%dH" % count, *(delta for _, delta in segments))
+    subtable += struct.pack(">%dH" % count, *([0] * count))
+    subtable = struct.pack(">7H", 4, 14 + len(subtable), 0, 2 * count, search_range,
+                           entry_selector, 2 * count - search_range) + subtable
+    return struct.pack(">HHHHI", 0, 1, 3, 1, 12) + subtable
+
+
+def font_tables(glyphs, mapping, units_per_em=1000):
+    """The required tables of a font with the given `(advance, contours)` glyphs and `cmap`."""
+    glyf, loca = b"", []
+    for _, contours in glyphs:
+        loca.append(len(glyf))
+        glyf += padded(simple_glyph(contours))
+    loca.append(len(glyf))
+
+    return {
+        b"head": struct.pack(">4I2H2q4h2H3h", 0x00010000, 0x00010000, 0, 0x5f0f3cf5, 0,
+                             units_per_em, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 0),
+        b"hhea": struct.pack(">I3hH3h3h4hhH", 0x00010000, 800, -200, 0,
+                             max(advance for advance, _ in glyphs), 0, 0, 0, 1, 0, 0,
+                             0, 0, 0, 0, 0, len(glyphs)),
+        b"maxp": struct.pack(">IH", 0x00005000, len(glyphs)),
+        b"hmtx": b"".join(struct.pack(">Hh", advance, 0) for advance, _ in glyphs),
+        b"cmap": cmap(mapping),
+        b"loca": struct.pack(">%dI" % len(loca), *loca),
+        b"glyf": glyf,
+    }
+
+
+def glyph_names_ttf():
+    """A font with glyph names in a version 2 `post` table: `A` and `space` from the standard
+    Macintosh names, and `A.alt` stored in the table itself without a character."""
+    square = [[(100, 0), (100, 700), (500, 700), (500, 0)]]
+    triangle = [[(100, 0), (300, 700), (500, 0)]]
+    tables = font_tables([(500, []), (600, square), (250, []), (600, triangle)],
+                         {0x41: 1, 0x20: 2})
+
+    post = struct.pack(">IihhIIIII", 0x00020000, 0, -100, 50, 0, 0, 0, 0, 0)
+    post += struct.pack(">5H", 4, 0, 36, 3, 258)
+    post += bytes([5]) + b"A.alt"
+    tables[b"post"] = post
+    return ttf(tables)
+
+
+def name_table(names):
+    """A format 0 `name` table with Windows Unicode records, keyed by name ID."""
+    records, strings = b"", b""
+    for name_id, name in sorted(names.items()):
+        encoded = name.encode("utf-16-be")
+        records += struct.pack(">6H", 3, 1, 0x409, name_id, len(encoded), len(strings))
+        strings += encoded
+    return struct.pack(">3H", 0, len(names), 6 + len(records)) + records + strings
+
+
+def variable_ttf():
+    """A variable font with a `wght` axis from 100 to 900 and the named instances Thin, Regular
+    and Black. The right edge of the square `A` moves 200 units in at Thin and out at Black, while
+    its advance stays the same."""
+    square = [[(100, 0), (100, 700), (500, 700), (500, 0)]]
+    tables = font_tables([(500, []), (600, square)], {0x41: 1})
+    tables[b"name"] = name_table({1: "Variable Test", 2: "Regular", 256: "Weight",
+                                  257: "Thin", 258: "Regular", 259: "Black"})
+
+    fixed = lambda value: round(value * 65536)
+    fvar = struct.pack(">8H", 1, 0, 16, 2, 1, 20, 3, 8)
+    fvar += struct.pack(">4s3iHH", b"wght", fixed(100), fixed(400), fixed(900), 0, 256)
+    for name_id, weight in [(257, 100), (258, 400), (259, 900)]:
+        fvar += struct.pack(">HHi", name_id, 0, fixed(weight))
+    tables[b"fvar"] = fvar
+
+    # one tuple per side of the default, both moving the right edge of the square and sharing
+    # "all points", which include the four phantom points after the outline
+    tuples = b""
+    data = bytes([0])
+    for peak, delta in [(0x4000, 200), (0xc000, -200)]:
+        deltas = struct.pack(">B8h", 0x40 | 7, 0, 0, delta, delta, 0, 0, 0, 0)
+        deltas += bytes([0x80 | 7])
+        tuples += struct.pack(">3H", len(deltas), 0x8000, peak)
+        data += deltas
+    variations = padded(struct.pack(">HH", 0x8000 | 2, 4 + len(tuples)) + tuples + data)
+    gvar = struct.pack(">HHHHIHHI", 1, 0, 1, 0, 20, 2, 0, 26)
+    gvar += struct.pack(">3H", 0, 0, len(variations) // 2) + variations
+    tables[b"gvar"] = gvar
+    return ttf(tables)
+
+
+def color_ttf():
+    """A font whose `A` is a COLRv0 glyph of two layers: a square filled with the first palette
+    color below a smaller square in the text color. `B` has no color layers. The `CPAL` table
+    has two palettes of one color, opaque red and half transparent blue."""
+    square = [[(100, 0), (100, 700), (500, 700), (500, 0)]]
+    inner = [[(200, 200), (200, 500), (400, 500), (400, 200)]]
+    triangle = [[(100, 0), (300, 700), (500, 0)]]
+    tables = font_tables([(500, []), (600, square), (600, square), (600, inner), (600, triangle)],
+                         {0x41: 1, 0x42: 4})
+
+    colr = struct.pack(">HHIIH", 0, 1, 14, 20, 2)
+    colr += struct.pack(">3H", 1, 0, 2)
+    colr += struct.pack(">4H", 2, 0, 3, 0xffff)
+    tables[b"COLR"] = colr
+
+    # color records are stored as BGRA
+    cpal = struct.pack(">4HI2H", 0, 1, 2, 2, 16, 0, 1)
+    cpal += bytes([0, 0, 255, 255, 255, 0, 0, 128])
+    tables[b"CPAL"] = cpal
+    return ttf(tables)
+
+
+def atlas_gen_arfont():
+    """An MSDF atlas laid out like msdf-atlas-gen's Artery Font export: no metadata or glyph
+    indices, glyphs by codepoint, bounds inset by half a pixel with the atlas y pointing up, and
+    a PNG image without orientation.
+
+    This is a stand-in written from msdf-atlas-gen's exporter, not output of msdf-atlas-gen
+    itself. It should be replaced by the output of
+
+        msdf-atlas-gen -font Roboto-Medium.ttf -chars "[0x20, 0x41, 0x56]" -type msdf -size 8 \
+            -pxrange 2 -format png -arfontout atlas_gen_msdf.arfont
+
+    together with the expected values of `can_read_atlas_gen_arfont`."""
+    width, height = 8, 6
+    pixels = [[(x * 32, y * 40, 255 - x * 32)[c] for x in range(width) for c in range(3)]
+              for y in range(height)]
+    image_data = padded(png(width, height, 3, pixels))
+
+    # codepoint, plane bounds and atlas bounds of a 4x4 pixel box at 8 pixels per em, advance
+    glyphs = [
+        (0x20, (0, 0, 0, 0), (0, 0, 0, 0), 0.25),
+        (0x41, (-0.0625, -0.0625, 0.3125, 0.3125), (0.5, 0.5, 3.5, 3.5), 0.625),
+        (0x56, (0.0, 0.0625, 0.375, 0.4375), (4.5, 0.5, 7.5, 3.5), 0.5625),
+    ]
+    kern_pairs = [(0x41, 0x56, -0.03759765625)]
+
+    metrics = [32, 2, 1, 0.927734375, -0.244140625, 1.171875, -0.0732421875, 0.048828125]
+    variant = struct.pack("<12I", 0, 0, 1, 6, 0, 0, 0, 0, 0, 0, 0, 0)
+    variant += struct.pack("<32f", *(metrics + [0] * 24))
+    variant += struct.pack("<4I", 0, 0, len(glyphs), len(kern_pairs))
+    for codepoint, plane, bounds, advance in glyphs:
+        variant += struct.pack("<2I10f", codepoint, 0, *plane, *bounds, advance, 0)
+    for first, second, advance in kern_pairs:
+        variant += struct.pack("<2I2f", first, second, advance, 0)
+
+    image = struct.pack("<16I", 0, 8, width, height, 3, 8, 6, 0, 0, 0, 0, 0, 0, 0, 0,
+                        len(image_data))
+    image += image_data
+
+    data = b"ARTERY/FONT\0\0\0\0\0"
+    data += struct.pack("<8I", 0x4d276a5c, 1, 0, 0x14, 0, 0, 0, 0)
+    data += struct.pack("<8I", 0, 0, 1, len(variant), 1, len(image), 0, 0)
+    data += b"\0" * 32
+    data += variant + image
+    data += struct.pack("<6I", 0, 0x55ccb363, 0, 0, 0, 0)
+    data += struct.pack("<I", len(data) + 8)
+    data += struct.pack("<I", zlib.crc32(data))
+    return data
+
+
+if __name__ == "__main__":
+    with open("atlas_gen_msdf.arfont", "wb") as file:
+        file.write(atlas_gen_arfont())
+    with open("glyph_names.ttf", "wb") as file:
+        file.write(glyph_names_ttf())
+    with open("variable.ttf", "wb") as file:
+        file.write(variable_ttf())
+    with open("color.ttf", "wb") as file:
+        file.write(color_ttf())